@@ -2,19 +2,18 @@ use std::f32::consts::PI;
 
 use bevy::{
     color::palettes::tailwind::{
-        GREEN_400, SLATE_800, SLATE_950,
+        AMBER_400, GREEN_400, SLATE_800, SLATE_950,
     },
+    core_pipeline::prepass::{DepthPrepass, NormalPrepass},
     gltf::GltfPlugin,
     mesh::VertexAttributeValues,
     prelude::*,
-    render::view::Hdr,
 };
-use edge_detection_custom_phase::{
-    ATTRIBUTE_SECTION_COLOR, DrawSection, SectionGroupId,
-    SectionTexturePhasePlugin, SectionsPrepass,
-    post_process::{
-        PostProcessPlugin, PostProcessSettings,
-    },
+use bevy_section_outlines::{
+    ATTRIBUTE_SECTION_COLOR, CreaseSections, DrawSection,
+    EdgeDetection, OutlineMethod, OutlineSettings,
+    OutlineStyle, PostProcessDisplay, SectionOutlinePlugin,
+    generate_section_colors,
 };
 
 fn main() {
@@ -33,12 +32,18 @@ fn main() {
             MeshPickingPlugin,
         ))
         // our plugins
-        .add_plugins((
-            SectionTexturePhasePlugin,
-            PostProcessPlugin,
-        ))
+        .add_plugins(SectionOutlinePlugin)
         .add_systems(Startup, setup)
-        .add_systems(Update, (rotate, vertical))
+        .add_systems(
+            Update,
+            (
+                rotate,
+                vertical,
+                cycle_display,
+                toggle_edges,
+                toggle_method,
+            ),
+        )
         .add_observer(
             |clicked: On<Pointer<Click>>,
              mut commands: Commands,
@@ -65,6 +70,36 @@ fn main() {
                 }
             },
         )
+        // highlight hovered shapes, even through the
+        // background cubes
+        .add_observer(
+            |over: On<Pointer<Over>>,
+             mut commands: Commands,
+             query: Query<(), With<DemoShape>>| {
+                if query.contains(over.entity) {
+                    commands.entity(over.entity).insert(
+                        OutlineStyle {
+                            color: AMBER_400.into(),
+                            width: 3,
+                            fill: Some(
+                                Color::from(AMBER_400)
+                                    .with_alpha(0.2),
+                            ),
+                            draw_occluded: true,
+                        },
+                    );
+                }
+            },
+        )
+        .add_observer(
+            |out: On<Pointer<Out>>, mut commands: Commands| {
+                if let Ok(mut entity) =
+                    commands.get_entity(out.entity)
+                {
+                    entity.remove::<OutlineStyle>();
+                }
+            },
+        )
         .run();
 }
 
@@ -80,11 +115,10 @@ fn setup(
 ) {
     // a number of shapes, all with their vertex
     // colors set in fairly arbitrary ways.
-    // Typically this is done by using the normal
-    // information to calulate colors for
-    // the faces, but in practice these colors will be
-    // hand-selected by an artist in software like
-    // Blender where they can then control which
+    // `generate_section_colors` splits a mesh at
+    // its creases, but in practice these colors will
+    // often be hand-selected by an artist in software
+    // like Blender where they can then control which
     // lines appear based on where they create
     // "edges" by selecting sufficiently different
     // colored faces.
@@ -113,77 +147,13 @@ fn setup(
                 colors,
             )
         }),
-        meshes.add({
-            let mesh = Cuboid::default().mesh().build();
-            let Some(VertexAttributeValues::Float32x3(
-                positions,
-            )) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
-            else {
-                return;
-            };
-
-            // all cube edges become lines
-            // cube normals are always 1 (or -1) on one axis
-            // and 0 on the other two axes
-            let colors: Vec<[f32; 4]> = positions
-                .iter()
-                .map(|[x, y, z]| {
-                    match (*x != 0., *y != 0., *z != 0.) {
-                        (true, false, false) => {
-                            [0.1, 0., 0., 1.]
-                        }
-                        (false, true, false) => {
-                            [1., 0., 0., 1.]
-                        }
-                        (false, false, true) => {
-                            [0.6, 0., 0., 1.]
-                        }
-                        _ => [0., 0., 0., 1.],
-                    }
-                })
-                .collect();
-
-            mesh.with_inserted_attribute(
-                ATTRIBUTE_SECTION_COLOR,
-                colors,
-            )
-        }),
-        meshes.add({
-            let mesh =
-                Tetrahedron::default().mesh().build();
-            let Some(VertexAttributeValues::Float32x3(
-                positions,
-            )) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
-            else {
-                return;
-            };
-
-            let colors: Vec<[f32; 4]> = positions
-                .iter()
-                .map(|[x, y, z]| {
-                    match (*x > 0., *y > 0., *z > 0.) {
-                        (false, false, false) => {
-                            [0.8, 0., 0., 1.]
-                        }
-                        (true, false, true) => {
-                            [1., 0., 0., 1.]
-                        }
-                        (true, true, false) => {
-                            [0.2, 0., 0., 1.]
-                        }
-                        (false, true, true) => {
-                            [0.6, 0., 0., 1.]
-                        }
-                        _ => [0., 0., 0., 1.],
-                    }
-                })
-                .collect();
-
-            mesh.with_inserted_attribute(
-                ATTRIBUTE_SECTION_COLOR,
-                colors,
-            )
-        }),
+        // all cube edges become lines
+        meshes.add(crease_sections(
+            Cuboid::default().mesh().build(),
+        )),
+        meshes.add(crease_sections(
+            Tetrahedron::default().mesh().build(),
+        )),
         meshes.add({
             let mesh = Capsule3d::default().mesh().build();
             let Some(VertexAttributeValues::Float32x3(
@@ -433,18 +403,31 @@ fn setup(
 
             ..default()
         },
-        // THIS ONLY WORKS WITH HDR CAMERAS
-        Hdr,
-        // disable msaa for simplicity
+        // outlines don't support msaa
         Msaa::Off,
-        PostProcessSettings {
+        OutlineSettings {
             stroke_color: Color::WHITE.into(),
             width: 2,
+            ..default()
         },
-        SectionsPrepass,
+        // depth and normal edges read the prepass
+        DepthPrepass,
+        NormalPrepass,
     ));
 }
 
+/// Gives every face of `mesh` meeting its neighbours
+/// at a crease its own section.
+fn crease_sections(mut mesh: Mesh) -> Mesh {
+    if let Err(err) = generate_section_colors(
+        &mut mesh,
+        CreaseSections::default(),
+    ) {
+        warn!("Couldn't split the mesh: {err}");
+    }
+    mesh
+}
+
 #[derive(Component)]
 struct Rotates;
 
@@ -474,3 +457,76 @@ fn vertical(
 
 #[derive(Component)]
 struct DemoShape;
+
+/// Space cycles through the debug views
+fn cycle_display(
+    input: Res<ButtonInput<KeyCode>>,
+    mut settings: Query<&mut OutlineSettings>,
+) {
+    if !input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for mut settings in &mut settings {
+        settings.display = match settings.display {
+            PostProcessDisplay::Default => {
+                PostProcessDisplay::SobelOnly
+            }
+            PostProcessDisplay::SobelOnly => {
+                PostProcessDisplay::SectionTextureOnly
+            }
+            PostProcessDisplay::SectionTextureOnly => {
+                PostProcessDisplay::DepthEdgesOnly
+            }
+            PostProcessDisplay::DepthEdgesOnly => {
+                PostProcessDisplay::Default
+            }
+        };
+        info!("displaying {:?}", settings.display);
+    }
+}
+
+/// E switches between section id edges and hybrid
+/// section id, depth and normal edges
+fn toggle_edges(
+    input: Res<ButtonInput<KeyCode>>,
+    mut settings: Query<&mut OutlineSettings>,
+) {
+    if !input.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    for mut settings in &mut settings {
+        settings.edges = if settings.edges.depth.is_some() {
+            EdgeDetection::default()
+        } else {
+            EdgeDetection {
+                thinning_distance: Some(12.),
+                ..EdgeDetection::hybrid()
+            }
+        };
+        info!("edges: {:?}", settings.edges);
+    }
+}
+
+/// M switches between sobel outlines and wide, glowing
+/// jump flood outlines
+fn toggle_method(
+    input: Res<ButtonInput<KeyCode>>,
+    mut settings: Query<&mut OutlineSettings>,
+) {
+    if !input.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    for mut settings in &mut settings {
+        (settings.method, settings.width) =
+            match settings.method {
+                OutlineMethod::Sobel => (
+                    OutlineMethod::JumpFlood { glow: 8. },
+                    6,
+                ),
+                OutlineMethod::JumpFlood { .. } => {
+                    (OutlineMethod::Sobel, 2)
+                }
+            };
+        info!("method: {:?}", settings.method);
+    }
+}
@@ -1,48 +1,69 @@
-#import bevy_shader_utils::simplex_noise_2d::simplex_noise_2d
+#import bevy_shader_utils::fbm::fbm_gradient
+#import landscape_compute_intro::compute_mesh::{
+    mesh, read_position, write_position, write_normal, write_tangent
+}
 
 // This shader is used for the compute_mesh example
-// The actual work it does is not important for the example and
-// has been hardcoded to return a cube mesh
-
-// `vertex_start` is the starting offset of the mesh data in the *vertex_data* storage buffer
-// `index_start` is the starting offset of the index data in the *index_data* storage buffer
-struct DataRanges {
-    num_vertices: u32,
-    vertex_start: u32,
-    vertex_end: u32,
-    index_start: u32,
-    index_end: u32,
+// It displaces a flat plane chunk of terrain with fbm noise,
+// one invocation per vertex
+
+struct TerrainNoise {
+    // world units per unit of noise
+    scale: f32,
+    height: f32,
+    seed: vec2<f32>,
+}
+
+// `offset` is where the center of the chunk is on the xz plane
+// and the vertices from `skirt_start` on hang `skirt_depth`
+// below the border to hide cracks between levels of detail
+struct ChunkParams {
+    noise: TerrainNoise,
+    offset: vec2<f32>,
+    skirt_start: u32,
+    skirt_depth: f32,
 }
 
-@group(0) @binding(0) var<uniform> data_range: DataRanges;
-@group(0) @binding(1) var<storage, read_write> vertex_data: array<f32>;
-@group(0) @binding(2) var<storage, read_write> index_data: array<u32>;
+// bindings 0 to 2 are the mesh, from compute_mesh
+@group(0) @binding(3) var<uniform> chunk: ChunkParams;
+
+// the unscaled height at a point in noise space, and
+// its gradient in .yz
+fn terrain(p: vec2<f32>) -> vec3<f32> {
+    return fbm_gradient(p);
+}
 
-@compute @workgroup_size(1)
+@compute @workgroup_size(64)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let terrain_scale = 50.;
-    for (var i = 0u; i < data_range.num_vertices; i++) {
-        let offset = i * 12 + data_range.vertex_start;
-        let x = vertex_data[offset];
-        let z = vertex_data[offset + 2];
-        // vertex_data[offset + 1] = (x + z) / 20.;//(f32(offset) / f32(data_range.num_vertices));
-        let height = simplex_noise_2d(vec2(x / terrain_scale, z / terrain_scale));
-        let root = vec3(x / terrain_scale, height, z / terrain_scale);
-        let x_vec = vec3(x / terrain_scale + 0.01, height, z / terrain_scale);
-        let z_vec = vec3(x / terrain_scale, height, z / terrain_scale + 0.01);
-        let normal = cross(x_vec, z_vec);
-
-        vertex_data[offset + 3] = normal.x;
-        vertex_data[offset + 4] = normal.y;
-        vertex_data[offset + 5] = normal.z;
-
-        let tangent = cross(normal, vec3(0., 1., 0.));
-
-        vertex_data[offset + 8] = tangent.x;
-        vertex_data[offset + 9] = tangent.y;
-        vertex_data[offset + 10] = tangent.z;
-        vertex_data[offset + 11] = 1.;
-
-        vertex_data[offset + 1] = simplex_noise_2d(vec2(x / terrain_scale, z / terrain_scale)) * 10.;
+    let i = global_id.x;
+    // the last workgroup can run past the end of the mesh
+    if i >= mesh.vertex_count {
+        return;
+    }
+    let position = read_position(i);
+    // sample the noise in world space, so neighboring chunks line up
+    let x = position.x + chunk.offset.x;
+    let z = position.z + chunk.offset.y;
+    let p = vec2(x, z) / chunk.noise.scale + chunk.noise.seed;
+    let noise = terrain(p);
+    // the height and its slope along x and z, in world units
+    let height = noise.x * chunk.noise.height;
+    let slope = noise.yz * chunk.noise.height / chunk.noise.scale;
+    let normal = normalize(vec3(-slope.x, 1., -slope.y));
+
+    write_normal(i, normal);
+
+    // MikkTSpace's tangent follows u, which runs along x, on the
+    // surface. v runs along z like on a Plane3d, so the bitangent
+    // sign is 1, like bevy gives a Plane3d
+    let along_u = vec3(1., slope.x, 0.);
+    let tangent = normalize(along_u - normal * dot(normal, along_u));
+
+    write_tangent(i, vec4(tangent, 1.));
+
+    var y = height;
+    if i >= chunk.skirt_start {
+        y -= chunk.skirt_depth;
     }
+    write_position(i, vec3(position.x, y, position.z));
 }
@@ -0,0 +1,970 @@
+//! Generates meshes in compute shaders that write
+//! straight into the `MeshAllocator` slabs, and
+//! optionally copies the results back to the main world.
+//!
+//! A generator is a [`MeshGeneratorParams`] type, the
+//! parameters its shader is given and where that shader
+//! is, and a [`ComputeMeshGenerator`] component on each
+//! entity whose mesh it fills. The shader imports
+//! `landscape_compute_intro::compute_mesh`, which finds
+//! every attribute wherever the mesh's vertex buffer
+//! layout puts it, so the same plugin works for any mesh
+//! with f32 attributes and u32 indices.
+
+use std::{
+    any::type_name,
+    marker::PhantomData,
+    sync::{mpsc, Mutex},
+};
+
+use bevy::{
+    asset::{
+        load_internal_asset, uuid_handle, RenderAssetUsages,
+    },
+    ecs::query::QueryItem,
+    mesh::{
+        Indices, MeshVertexAttribute,
+        MeshVertexBufferLayout, MeshVertexBufferLayouts,
+        PrimitiveTopology, VertexAttributeValues,
+        VertexFormat,
+    },
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+    render::{
+        extract_component::{
+            ExtractComponent, ExtractComponentPlugin,
+        },
+        mesh::{
+            allocator::{MeshAllocator, MeshBufferSlice},
+            RenderMesh, RenderMeshBufferInfo,
+        },
+        render_asset::{ExtractedAssets, RenderAssets},
+        render_graph::{self, RenderGraph, RenderLabel},
+        render_resource::{
+            binding_types::{
+                storage_buffer, uniform_buffer,
+            },
+            encase::internal::WriteInto,
+            *,
+        },
+        renderer::{
+            render_system, RenderContext, RenderDevice,
+            RenderQueue,
+        },
+        sync_world::MainEntity,
+        Render, RenderApp, RenderStartup, RenderSystems,
+    },
+    shader::ShaderRef,
+};
+
+/// `compute_mesh.wgsl`, imported by generator shaders
+/// as `landscape_compute_intro::compute_mesh`
+pub const COMPUTE_MESH_SHADER: Handle<Shader> =
+    uuid_handle!("429b8e66-541c-4a7b-a6be-d3d1c1434f33");
+
+/// The parameters a generator's shader gets at
+/// `@group(0) @binding(3)`, and where that shader is.
+/// Bindings 0 to 2 are the mesh, from
+/// `landscape_compute_intro::compute_mesh`.
+pub trait MeshGeneratorParams:
+    ShaderType
+    + WriteInto
+    + Clone
+    + PartialEq
+    + std::fmt::Debug
+    + Send
+    + Sync
+    + 'static
+{
+    /// The shader with the entry point
+    fn shader() -> ShaderRef;
+
+    fn entry_point() -> &'static str {
+        "main"
+    }
+
+    /// The workgroups to dispatch for a mesh with
+    /// `vertex_count` vertices and `index_count`
+    /// indices. By default one invocation per vertex,
+    /// for a `@workgroup_size(64)`.
+    fn workgroups(
+        &self,
+        vertex_count: u32,
+        _index_count: u32,
+    ) -> UVec3 {
+        UVec3::new(vertex_count.div_ceil(64), 1, 1)
+    }
+
+    /// Generates `mesh` on the cpu instead, for apps
+    /// without a renderer, returning whether it did.
+    /// Generators without a cpu version, the default,
+    /// aren't read back in those apps.
+    fn generate_on_cpu(
+        &self,
+        _mesh: &mut GeneratedMesh,
+    ) -> bool {
+        false
+    }
+}
+
+/// Runs the [`ComputeMeshGenerator`]s of `T`. Add one
+/// for each kind of generator.
+pub struct ComputeMeshGeneratorPlugin<T>(PhantomData<T>);
+
+impl<T> Default for ComputeMeshGeneratorPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: MeshGeneratorParams> Plugin
+    for ComputeMeshGeneratorPlugin<T>
+{
+    fn build(&self, app: &mut App) {
+        // generated meshes that are read back come
+        // through here, from the render world or the cpu
+        let (sender, receiver) = mpsc::channel();
+        app.add_plugins(ExtractComponentPlugin::<
+            ComputeMeshGenerator<T>,
+        >::default())
+            .insert_resource(MeshGeneratedSender::<T>(
+                sender.clone(),
+            ))
+            .insert_resource(MeshGeneratedReceiver::<T>(
+                Mutex::new(receiver),
+            ))
+            .add_systems(
+                PreUpdate,
+                receive_generated_meshes::<T>,
+            );
+
+        let Some(render_app) =
+            app.get_sub_app_mut(RenderApp)
+        else {
+            // Without a renderer, like in headless apps and
+            // tests, the meshes that are read back are
+            // generated on the cpu instead.
+            app.add_systems(
+                PostUpdate,
+                generate_on_cpu::<T>,
+            );
+            return;
+        };
+
+        render_app
+            .insert_resource(MeshGeneratedSender::<T>(
+                sender,
+            ))
+            .init_resource::<GeneratorsToRun<T>>()
+            .add_systems(
+                RenderStartup,
+                (
+                    init_compute_pipeline::<T>,
+                    add_compute_render_graph_node::<T>,
+                ),
+            )
+            .add_systems(
+                Render,
+                (
+                    prepare_generators::<T>.in_set(
+                        RenderSystems::PrepareResources,
+                    ),
+                    // the copies have to be submitted before
+                    // they can be mapped
+                    map_readbacks::<T>
+                        .after(render_system)
+                        .in_set(RenderSystems::Render),
+                ),
+            );
+
+        // the shaders' import, only needed with a renderer
+        load_internal_asset!(
+            app,
+            COMPUTE_MESH_SHADER,
+            "shaders/compute_mesh.wgsl",
+            Shader::from_wgsl
+        );
+    }
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) =
+            app.get_sub_app_mut(RenderApp)
+        else {
+            return;
+        };
+        render_app
+            .world_mut()
+            .resource_mut::<MeshAllocator>()
+            // This allows using the mesh allocator slabs as
+            // storage buffers directly in the compute shader.
+            // Which means that we can write from our compute
+            // shader directly to the allocated mesh slabs.
+            .extra_buffer_usages = BufferUsages::STORAGE;
+    }
+}
+
+/// Fills `mesh` with `T`'s shader, and fills it again
+/// whenever this or the mesh changes.
+///
+/// The shader writes the mesh's vertices and indices in
+/// place, so there are as many as the mesh has. Give it
+/// a mesh of another size, like one from
+/// [`empty_mesh`], to change them. Generators whose
+/// output varies, like marching cubes, can make room
+/// for the most they generate and write degenerate
+/// triangles in the rest.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct ComputeMeshGenerator<T: MeshGeneratorParams> {
+    /// A mesh with f32 attributes and u32 indices
+    pub mesh: Handle<Mesh>,
+    pub params: T,
+    /// Whether to copy the generated vertices and
+    /// indices back to the main world, triggering
+    /// [`MeshGenerated`]
+    pub readback: bool,
+}
+
+impl<T: MeshGeneratorParams> ExtractComponent
+    for ComputeMeshGenerator<T>
+{
+    type QueryData = &'static Self;
+    type QueryFilter = ();
+    type Out = Self;
+
+    fn extract_component(
+        generator: QueryItem<'_, '_, Self::QueryData>,
+    ) -> Option<Self> {
+        Some(generator.clone())
+    }
+}
+
+/// Triggered on a generator's entity when its mesh has
+/// been read back, every time it's generated again.
+#[derive(EntityEvent, Clone, Debug)]
+pub struct MeshGenerated<T: MeshGeneratorParams> {
+    pub entity: Entity,
+    /// What the mesh was generated with
+    pub generator: ComputeMeshGenerator<T>,
+    pub mesh: GeneratedMesh,
+}
+
+/// Where each attribute is in a vertex, in floats from
+/// the vertex's start, or [`VertexLayout::MISSING`] if
+/// the mesh doesn't have it.
+#[derive(ShaderType, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexLayout {
+    /// Floats from one vertex to the next
+    pub stride: u32,
+    pub position: u32,
+    pub normal: u32,
+    /// [`Mesh::ATTRIBUTE_UV_0`]
+    pub uv: u32,
+    pub tangent: u32,
+    pub color: u32,
+}
+
+impl VertexLayout {
+    pub const MISSING: u32 = u32::MAX;
+
+    /// Where `layout` puts each attribute, as in the
+    /// mesh allocator's slabs and
+    /// [`Mesh::create_packed_vertex_buffer_data`].
+    pub fn new(layout: &MeshVertexBufferLayout) -> Self {
+        let offset = |attribute: MeshVertexAttribute| {
+            layout
+                .attribute_ids()
+                .iter()
+                .zip(&layout.layout().attributes)
+                .find(|(id, _)| **id == attribute.id)
+                .map_or(Self::MISSING, |(_, vertex)| {
+                    vertex.offset as u32 / 4
+                })
+        };
+        Self {
+            stride: layout.layout().array_stride as u32 / 4,
+            position: offset(Mesh::ATTRIBUTE_POSITION),
+            normal: offset(Mesh::ATTRIBUTE_NORMAL),
+            uv: offset(Mesh::ATTRIBUTE_UV_0),
+            tangent: offset(Mesh::ATTRIBUTE_TANGENT),
+            color: offset(Mesh::ATTRIBUTE_COLOR),
+        }
+    }
+}
+
+/// A generated mesh's vertices and indices, read back
+/// from the gpu or generated on the cpu.
+#[derive(Clone, Debug)]
+pub struct GeneratedMesh {
+    pub vertex_layout: VertexLayout,
+    /// [`VertexLayout::stride`] floats per vertex
+    pub vertices: Vec<f32>,
+    pub indices: Vec<u32>,
+}
+
+impl GeneratedMesh {
+    /// Each vertex's floats
+    pub fn vertices(&self) -> impl Iterator<Item = &[f32]> {
+        self.vertices.chunks_exact(self.stride())
+    }
+
+    pub fn vertices_mut(
+        &mut self,
+    ) -> impl Iterator<Item = &mut [f32]> {
+        let stride = self.stride();
+        self.vertices.chunks_exact_mut(stride)
+    }
+
+    /// The `N` floats at `offset` of every vertex, or
+    /// `None` for a [`VertexLayout::MISSING`] attribute.
+    pub fn attribute<const N: usize>(
+        &self,
+        offset: u32,
+    ) -> Option<Vec<[f32; N]>> {
+        if offset == VertexLayout::MISSING {
+            return None;
+        }
+        let offset = offset as usize;
+        Some(
+            self.vertices()
+                .map(|vertex| {
+                    std::array::from_fn(|i| {
+                        vertex[offset + i]
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// A main world mesh with the attributes of
+    /// [`VertexLayout`] this mesh has.
+    pub fn to_mesh(&self) -> Mesh {
+        let layout = self.vertex_layout;
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::MAIN_WORLD,
+        )
+        .with_inserted_indices(Indices::U32(
+            self.indices.clone(),
+        ));
+        if let Some(positions) =
+            self.attribute::<3>(layout.position)
+        {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                positions,
+            );
+        }
+        if let Some(normals) =
+            self.attribute::<3>(layout.normal)
+        {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_NORMAL,
+                normals,
+            );
+        }
+        if let Some(uvs) = self.attribute::<2>(layout.uv) {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_UV_0,
+                uvs,
+            );
+        }
+        if let Some(tangents) =
+            self.attribute::<4>(layout.tangent)
+        {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_TANGENT,
+                tangents,
+            );
+        }
+        if let Some(colors) =
+            self.attribute::<4>(layout.color)
+        {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_COLOR,
+                colors,
+            );
+        }
+        mesh
+    }
+
+    fn stride(&self) -> usize {
+        // every attribute takes at least one float
+        (self.vertex_layout.stride as usize).max(1)
+    }
+}
+
+/// A render world mesh of `vertex_count` vertices with
+/// `attributes`, and `index_count` indices, all zeros,
+/// for a generator to fill.
+///
+/// # Panics
+///
+/// If an attribute isn't made of f32s.
+pub fn empty_mesh(
+    attributes: &[MeshVertexAttribute],
+    vertex_count: usize,
+    index_count: usize,
+) -> Mesh {
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_indices(Indices::U32(vec![
+        0;
+        index_count
+    ]));
+    for &attribute in attributes {
+        let values = match attribute.format {
+            VertexFormat::Float32 => {
+                VertexAttributeValues::Float32(vec![0.; vertex_count])
+            }
+            VertexFormat::Float32x2 => {
+                VertexAttributeValues::Float32x2(vec![[0.; 2]; vertex_count])
+            }
+            VertexFormat::Float32x3 => {
+                VertexAttributeValues::Float32x3(vec![[0.; 3]; vertex_count])
+            }
+            VertexFormat::Float32x4 => {
+                VertexAttributeValues::Float32x4(vec![[0.; 4]; vertex_count])
+            }
+            format => panic!(
+                "{} is {format:?}, generated attributes are f32s",
+                attribute.name
+            ),
+        };
+        mesh.insert_attribute(attribute, values);
+    }
+    mesh
+}
+
+#[derive(Resource)]
+struct MeshGeneratedSender<T: MeshGeneratorParams>(
+    mpsc::Sender<MeshGenerated<T>>,
+);
+
+#[derive(Resource)]
+struct MeshGeneratedReceiver<T: MeshGeneratorParams>(
+    Mutex<mpsc::Receiver<MeshGenerated<T>>>,
+);
+
+/// Triggers [`MeshGenerated`] for the meshes that were
+/// read back.
+fn receive_generated_meshes<T: MeshGeneratorParams>(
+    mut commands: Commands,
+    receiver: Res<MeshGeneratedReceiver<T>>,
+    generators: Query<&ComputeMeshGenerator<T>>,
+) {
+    let receiver = receiver.0.lock().unwrap();
+    for generated in receiver.try_iter() {
+        // generators that were despawned or changed
+        // since will be, or were, generated again
+        if generators.get(generated.entity)
+            == Ok(&generated.generator)
+        {
+            commands.trigger(generated);
+        }
+    }
+}
+
+/// Generates the meshes that are read back with
+/// [`MeshGeneratorParams::generate_on_cpu`], for apps
+/// without a renderer.
+fn generate_on_cpu<T: MeshGeneratorParams>(
+    generators: Query<(
+        Entity,
+        Ref<ComputeMeshGenerator<T>>,
+    )>,
+    mut mesh_events: MessageReader<AssetEvent<Mesh>>,
+    meshes: Res<Assets<Mesh>>,
+    sender: Res<MeshGeneratedSender<T>>,
+    mut layouts: Local<MeshVertexBufferLayouts>,
+) {
+    // new meshes come with a changed generator
+    let modified_meshes: HashSet<AssetId<Mesh>> =
+        mesh_events
+            .read()
+            .filter_map(|event| match event {
+                AssetEvent::Modified { id } => Some(*id),
+                _ => None,
+            })
+            .collect();
+    for (entity, generator) in &generators {
+        if !generator.readback
+            || !(generator.is_changed()
+                || modified_meshes
+                    .contains(&generator.mesh.id()))
+        {
+            continue;
+        }
+        let Some(mesh) = meshes.get(&generator.mesh) else {
+            continue;
+        };
+        // the same layout as in the mesh allocator's slabs
+        let mut generated = GeneratedMesh {
+            vertex_layout: VertexLayout::new(
+                &mesh
+                    .get_mesh_vertex_buffer_layout(
+                        &mut layouts,
+                    )
+                    .0,
+            ),
+            vertices: mesh
+                .create_packed_vertex_buffer_data()
+                .chunks_exact(4)
+                .map(|bytes| {
+                    f32::from_le_bytes(
+                        bytes.try_into().unwrap(),
+                    )
+                })
+                .collect(),
+            indices: mesh
+                .indices()
+                .map(|indices| {
+                    indices
+                        .iter()
+                        .map(|i| i as u32)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        if generator.params.generate_on_cpu(&mut generated)
+        {
+            let _ = sender.0.send(MeshGenerated {
+                entity,
+                generator: generator.clone(),
+                mesh: generated,
+            });
+        }
+    }
+}
+
+fn add_compute_render_graph_node<T: MeshGeneratorParams>(
+    mut render_graph: ResMut<RenderGraph>,
+) {
+    let label = ComputeNodeLabel(type_name::<T>());
+    render_graph.add_node(
+        label.clone(),
+        ComputeNode::<T>(PhantomData),
+    );
+    // add_node_edge guarantees that ComputeNodeLabel will run before CameraDriverLabel
+    render_graph.add_node_edge(
+        label,
+        bevy::render::graph::CameraDriverLabel,
+    );
+}
+
+/// The generators to run this frame. It only exists in
+/// the render world.
+#[derive(Resource)]
+struct GeneratorsToRun<T: MeshGeneratorParams>(
+    Vec<GeneratorToRun<T>>,
+);
+
+impl<T: MeshGeneratorParams> Default
+    for GeneratorsToRun<T>
+{
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+struct GeneratorToRun<T: MeshGeneratorParams> {
+    entity: Entity,
+    generator: ComputeMeshGenerator<T>,
+    mesh: ComputeMesh,
+    /// Where the generated vertices, then indices, are
+    /// copied to be read back
+    readback: Option<Buffer>,
+}
+
+/// `processed` holds the generator each mesh was last
+/// generated with. A mesh is only generated again when
+/// its generator changes, or when the mesh itself was
+/// uploaded again, which overwrites what the shader
+/// wrote, instead of every frame. Meshes of despawned
+/// generators are forgotten.
+fn prepare_generators<T: MeshGeneratorParams>(
+    generators: Query<(
+        &MainEntity,
+        &ComputeMeshGenerator<T>,
+    )>,
+    mut to_run: ResMut<GeneratorsToRun<T>>,
+    pipeline_cache: Res<PipelineCache>,
+    pipeline: Res<ComputePipeline<T>>,
+    mesh_allocator: Res<MeshAllocator>,
+    render_meshes: Res<RenderAssets<RenderMesh>>,
+    extracted_meshes: Res<ExtractedAssets<RenderMesh>>,
+    render_device: Res<RenderDevice>,
+    mut processed: Local<
+        HashMap<AssetId<Mesh>, ComputeMeshGenerator<T>>,
+    >,
+) {
+    to_run.0.clear();
+
+    let alive: HashSet<AssetId<Mesh>> = generators
+        .iter()
+        .map(|(_, generator)| generator.mesh.id())
+        .collect();
+    processed.retain(|id, _| alive.contains(id));
+
+    // If the pipeline isn't ready, then meshes
+    // won't be processed. So we want to wait until
+    // the pipeline is ready before considering any mesh processed.
+    if pipeline_cache
+        .get_compute_pipeline(pipeline.pipeline)
+        .is_none()
+    {
+        return;
+    }
+
+    for (entity, generator) in &generators {
+        let id = generator.mesh.id();
+        let uploaded = extracted_meshes.added.contains(&id)
+            || extracted_meshes.modified.contains(&id);
+        if processed.get(&id) == Some(generator)
+            && !uploaded
+        {
+            continue;
+        }
+        // A new mesh may not be in the mesh_allocator's slabs
+        // yet, in which case it's picked up next frame.
+        processed.remove(&id);
+        let (
+            Some(render_mesh),
+            Some(vertex_slice),
+            Some(index_slice),
+        ) = (
+            render_meshes.get(id),
+            mesh_allocator.mesh_vertex_slice(&id),
+            mesh_allocator.mesh_index_slice(&id),
+        )
+        else {
+            continue;
+        };
+        let RenderMeshBufferInfo::Indexed {
+            count: index_count,
+            index_format: IndexFormat::Uint32,
+        } = render_mesh.buffer_info
+        else {
+            warn_once!(
+                "Only meshes with u32 indices can be generated"
+            );
+            continue;
+        };
+        let mesh = ComputeMesh::new(
+            &vertex_slice,
+            &index_slice,
+            render_mesh.vertex_count,
+            index_count,
+            VertexLayout::new(&render_mesh.layout.0),
+        );
+        let readback = generator.readback.then(|| {
+            render_device.create_buffer(&BufferDescriptor {
+                label: Some("Mesh generation readback"),
+                size: mesh.vertex_bytes()
+                    + mesh.index_bytes(),
+                usage: BufferUsages::MAP_READ
+                    | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+        processed.insert(id, generator.clone());
+        to_run.0.push(GeneratorToRun {
+            entity: entity.id(),
+            generator: generator.clone(),
+            mesh,
+            readback,
+        });
+    }
+}
+
+#[derive(Resource)]
+struct ComputePipeline<T: MeshGeneratorParams> {
+    layout: BindGroupLayoutDescriptor,
+    pipeline: CachedComputePipelineId,
+    marker: PhantomData<T>,
+}
+
+// init only happens once
+fn init_compute_pipeline<T: MeshGeneratorParams>(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = BindGroupLayoutDescriptor::new(
+        "",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::COMPUTE,
+            (
+                // where the mesh is in the slabs
+                uniform_buffer::<ComputeMesh>(false),
+                // vertices
+                storage_buffer::<Vec<u32>>(false),
+                // indices
+                storage_buffer::<Vec<u32>>(false),
+                // the generator's parameters
+                uniform_buffer::<T>(false),
+            ),
+        ),
+    );
+    let shader = match T::shader() {
+        ShaderRef::Handle(handle) => handle,
+        ShaderRef::Path(path) => asset_server.load(path),
+        ShaderRef::Default => {
+            panic!("{} has no shader", type_name::<T>())
+        }
+    };
+    let pipeline = pipeline_cache.queue_compute_pipeline(
+        ComputePipelineDescriptor {
+            label: Some(
+                "Mesh generation compute shader".into(),
+            ),
+            layout: vec![layout.clone()],
+            shader,
+            entry_point: Some(T::entry_point().into()),
+            ..default()
+        },
+    );
+    commands.insert_resource(ComputePipeline::<T> {
+        layout,
+        pipeline,
+        marker: PhantomData,
+    });
+}
+
+/// Label to identify each generator's node in the
+/// render graph, by its [`MeshGeneratorParams`] type
+#[derive(
+    Debug, Hash, PartialEq, Eq, Clone, RenderLabel,
+)]
+struct ComputeNodeLabel(&'static str);
+
+/// The node that will execute the compute shader
+struct ComputeNode<T>(PhantomData<T>);
+
+/// Where the mesh being generated is in the
+/// mesh_allocator's slabs, and its layout.
+#[derive(ShaderType, Clone, Copy)]
+struct ComputeMesh {
+    /// In floats, where the vertex slice's range is in
+    /// vertices
+    vertex_start: u32,
+    vertex_count: u32,
+    /// In indices, like the index slice's range
+    index_start: u32,
+    index_count: u32,
+    vertex_layout: VertexLayout,
+}
+
+impl ComputeMesh {
+    fn new(
+        vertex_slice: &MeshBufferSlice,
+        index_slice: &MeshBufferSlice,
+        vertex_count: u32,
+        index_count: u32,
+        vertex_layout: VertexLayout,
+    ) -> Self {
+        Self {
+            vertex_start: vertex_slice.range.start
+                * vertex_layout.stride,
+            vertex_count,
+            index_start: index_slice.range.start,
+            index_count,
+            vertex_layout,
+        }
+    }
+
+    fn vertex_bytes(&self) -> u64 {
+        self.vertex_count as u64
+            * self.vertex_layout.stride as u64
+            * 4
+    }
+
+    fn index_bytes(&self) -> u64 {
+        self.index_count as u64 * 4
+    }
+}
+
+impl<T: MeshGeneratorParams> render_graph::Node
+    for ComputeNode<T>
+{
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let to_run = world.resource::<GeneratorsToRun<T>>();
+        let mesh_allocator =
+            world.resource::<MeshAllocator>();
+        let pipeline_cache =
+            world.resource::<PipelineCache>();
+        let pipeline =
+            world.resource::<ComputePipeline<T>>();
+        let Some(init_pipeline) = pipeline_cache
+            .get_compute_pipeline(pipeline.pipeline)
+        else {
+            return Ok(());
+        };
+
+        for GeneratorToRun {
+            generator,
+            mesh,
+            readback,
+            ..
+        } in &to_run.0
+        {
+            // the mesh_allocator holds slabs of meshes, so the buffers we get here
+            // can contain more data than just the mesh we're asking for.
+            // `mesh` has where it is, and compute_mesh.wgsl stays inside it.
+            let mesh_id = generator.mesh.id();
+            let (Some(vertex_slice), Some(index_slice)) = (
+                mesh_allocator.mesh_vertex_slice(&mesh_id),
+                mesh_allocator.mesh_index_slice(&mesh_id),
+            ) else {
+                continue;
+            };
+
+            let mut uniforms = UniformBuffer::from(*mesh);
+            uniforms.write_buffer(
+                render_context.render_device(),
+                world.resource::<RenderQueue>(),
+            );
+            let mut params = UniformBuffer::from(
+                generator.params.clone(),
+            );
+            params.write_buffer(
+                render_context.render_device(),
+                world.resource::<RenderQueue>(),
+            );
+
+            // pass in the full mesh_allocator slabs as well as the first index
+            // offsets for the vertex and index buffers
+            let bind_group = render_context
+                .render_device()
+                .create_bind_group(
+                    None,
+                    &pipeline_cache.get_bind_group_layout(
+                        &pipeline.layout,
+                    ),
+                    &BindGroupEntries::sequential((
+                        &uniforms,
+                        vertex_slice
+                            .buffer
+                            .as_entire_buffer_binding(),
+                        index_slice
+                            .buffer
+                            .as_entire_buffer_binding(),
+                        &params,
+                    )),
+                );
+
+            let mut pass = render_context
+                .command_encoder()
+                .begin_compute_pass(
+                    &ComputePassDescriptor {
+                        label: Some(
+                            "Mesh generation compute pass",
+                        ),
+                        ..default()
+                    },
+                );
+            pass.push_debug_group("compute_mesh");
+
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.set_pipeline(init_pipeline);
+            let workgroups = generator.params.workgroups(
+                mesh.vertex_count,
+                mesh.index_count,
+            );
+            pass.dispatch_workgroups(
+                workgroups.x,
+                workgroups.y,
+                workgroups.z,
+            );
+
+            pass.pop_debug_group();
+            drop(pass);
+
+            // copy this mesh's part of the slabs out,
+            // after the shader wrote to it
+            if let Some(readback) = readback {
+                let encoder =
+                    render_context.command_encoder();
+                encoder.copy_buffer_to_buffer(
+                    vertex_slice.buffer,
+                    mesh.vertex_start as u64 * 4,
+                    readback,
+                    0,
+                    mesh.vertex_bytes(),
+                );
+                encoder.copy_buffer_to_buffer(
+                    index_slice.buffer,
+                    mesh.index_start as u64 * 4,
+                    readback,
+                    mesh.vertex_bytes(),
+                    mesh.index_bytes(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps the readback buffers copied to this frame, and
+/// sends their data to the main world once the gpu is
+/// done with them.
+fn map_readbacks<T: MeshGeneratorParams>(
+    to_run: Res<GeneratorsToRun<T>>,
+    sender: Res<MeshGeneratedSender<T>>,
+) {
+    for run in &to_run.0 {
+        let Some(buffer) = run.readback.clone() else {
+            continue;
+        };
+        let entity = run.entity;
+        let generator = run.generator.clone();
+        let vertex_layout = run.mesh.vertex_layout;
+        let vertex_bytes = run.mesh.vertex_bytes() as usize;
+        let sender = sender.0.clone();
+        buffer.clone().slice(..).map_async(
+            MapMode::Read,
+            move |result| {
+                if let Err(error) = result {
+                    warn!("Failed to read back a generated mesh: {error}");
+                    return;
+                }
+                let data =
+                    buffer.slice(..).get_mapped_range().to_vec();
+                buffer.unmap();
+                let (vertices, indices) =
+                    data.split_at(vertex_bytes);
+                // the main world may be gone when the app
+                // is closing
+                let _ = sender.send(MeshGenerated {
+                    entity,
+                    generator,
+                    mesh: GeneratedMesh {
+                        vertex_layout,
+                        vertices: vertices
+                            .chunks_exact(4)
+                            .map(|bytes| {
+                                f32::from_le_bytes(
+                                    bytes.try_into().unwrap(),
+                                )
+                            })
+                            .collect(),
+                        indices: indices
+                            .chunks_exact(4)
+                            .map(|bytes| {
+                                u32::from_le_bytes(
+                                    bytes.try_into().unwrap(),
+                                )
+                            })
+                            .collect(),
+                    },
+                });
+            },
+        );
+    }
+}
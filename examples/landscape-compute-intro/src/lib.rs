@@ -0,0 +1,4 @@
+pub mod compute;
+pub mod readback;
+pub mod reference;
+pub mod terrain;
@@ -1,14 +1,12 @@
-use std::ops::Not;
-
 use bevy::{
     anti_alias::fxaa::Fxaa,
-    asset::RenderAssetUsages,
     camera::Exposure,
     camera_controller::free_camera::{
         FreeCamera, FreeCameraPlugin,
     },
     color::palettes::tailwind::GREEN_800,
     core_pipeline::tonemapping::Tonemapping,
+    input::common_conditions::input_just_pressed,
     light::{
         light_consts::lux, AtmosphereEnvironmentMapLight,
         CascadeShadowConfigBuilder, VolumetricFog,
@@ -18,28 +16,13 @@ use bevy::{
         Atmosphere, AtmosphereSettings, ScatteringMedium,
         ScreenSpaceReflections,
     },
-    platform::collections::HashSet,
     post_process::bloom::Bloom,
     prelude::*,
-    render::{
-        extract_component::{
-            ExtractComponent, ExtractComponentPlugin,
-        },
-        mesh::allocator::MeshAllocator,
-        render_graph::{self, RenderGraph, RenderLabel},
-        render_resource::{
-            binding_types::{
-                storage_buffer, uniform_buffer,
-            },
-            *,
-        },
-        renderer::{RenderContext, RenderQueue},
-        Render, RenderApp, RenderStartup,
-    },
 };
 use bevy_shader_utils::ShaderUtilsPlugin;
-
-const SHADER_ASSET_PATH: &str = "landscape.wgsl";
+use landscape_compute_intro::terrain::{
+    Terrain, TerrainFocus, TerrainPlugin,
+};
 
 fn main() {
     App::new()
@@ -47,119 +30,35 @@ fn main() {
         .add_plugins((
             DefaultPlugins,
             FreeCameraPlugin,
-            ComputeShaderMeshGeneratorPlugin,
+            TerrainPlugin,
             ShaderUtilsPlugin,
-            ExtractComponentPlugin::<GenerateMesh>::default(
-            ),
         ))
         .insert_resource(ClearColor(Color::BLACK))
         .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            reseed_terrain
+                .run_if(input_just_pressed(KeyCode::KeyR)),
+        )
         .run();
 }
 
-// We need a plugin to organize all the systems and render node required for this example
-struct ComputeShaderMeshGeneratorPlugin;
-impl Plugin for ComputeShaderMeshGeneratorPlugin {
-    fn build(&self, app: &mut App) {
-        let Some(render_app) =
-            app.get_sub_app_mut(RenderApp)
-        else {
-            return;
-        };
-
-        render_app
-            .init_resource::<ChunksToProcess>()
-            .add_systems(
-                RenderStartup,
-                (
-                    init_compute_pipeline,
-                    add_compute_render_graph_node,
-                ),
-            )
-            .add_systems(Render, prepare_chunks);
-    }
-    fn finish(&self, app: &mut App) {
-        let Some(render_app) =
-            app.get_sub_app_mut(RenderApp)
-        else {
-            return;
-        };
-        render_app
-            .world_mut()
-            .resource_mut::<MeshAllocator>()
-            // This allows using the mesh allocator slabs as
-            // storage buffers directly in the compute shader.
-            // Which means that we can write from our compute
-            // shader directly to the allocated mesh slabs.
-            .extra_buffer_usages = BufferUsages::STORAGE;
-    }
-}
-
-/// Holds a handle to the empty mesh that should be filled
-/// by the compute shader.
-#[derive(Component, ExtractComponent, Clone)]
-struct GenerateMesh {
-    num_vertices: u32,
-    mesh_handle: Handle<Mesh>,
-}
-
 fn setup(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut scattering_mediums: ResMut<
         Assets<ScatteringMedium>,
     >,
 ) {
-    let empty_mesh = {
-        let mut mesh =
-            Plane3d::new(Vec3::Y, Vec2::splat(200.))
-                .mesh()
-                .subdivisions(100)
-                .build();
-        mesh.generate_tangents().unwrap();
-        // let mut mesh = Mesh::new(
-        //     PrimitiveTopology::TriangleList,
-        //     RenderAssetUsages::RENDER_WORLD,
-        // )
-        // .with_inserted_attribute(
-        //     Mesh::ATTRIBUTE_POSITION,
-        //     vec![[0.; 3]; 50],
-        // )
-        // .with_inserted_attribute(
-        //     Mesh::ATTRIBUTE_NORMAL,
-        //     vec![[0.; 3]; 50],
-        // )
-        // .with_inserted_attribute(
-        //     Mesh::ATTRIBUTE_UV_0,
-        //     vec![[0.; 2]; 50],
-        // )
-        // .with_inserted_indices(Indices::U32(vec![0; 50]));
-
-        mesh.asset_usage = RenderAssetUsages::RENDER_WORLD;
-        mesh
-    };
-    // for attr in empty_mesh.attributes() {
-    //     dbg!(attr);
-    // }
-    dbg!(empty_mesh.indices().unwrap().len());
-    let num_vertices = empty_mesh.indices().unwrap().len();
-    let handle = meshes.add(empty_mesh);
-
-    commands.spawn((
-        GenerateMesh {
-            num_vertices: num_vertices as u32,
-            mesh_handle: handle.clone(),
-        },
-        Mesh3d(handle.clone()),
-        MeshMaterial3d(materials.add(StandardMaterial {
+    commands.insert_resource(Terrain {
+        material: materials.add(StandardMaterial {
             base_color: GREEN_800.into(),
             perceptual_roughness: 0.,
             reflectance: 0.,
             ..default()
-        })),
-        Transform::from_xyz(0., 0., 0.),
-    ));
+        }),
+        ..default()
+    });
 
     // camera
     commands.spawn((
@@ -185,6 +84,7 @@ fn setup(
         // Enables the atmosphere to drive reflections and ambient lighting (IBL) for this view
         AtmosphereEnvironmentMapLight::default(),
         FreeCamera::default(),
+        TerrainFocus,
         VolumetricFog {
             ambient_intensity: 0.0,
             ..default()
@@ -221,236 +121,8 @@ fn setup(
     ));
 }
 
-fn add_compute_render_graph_node(
-    mut render_graph: ResMut<RenderGraph>,
-) {
-    render_graph
-        .add_node(ComputeNodeLabel, ComputeNode::default());
-    // add_node_edge guarantees that ComputeNodeLabel will run before CameraDriverLabel
-    render_graph.add_node_edge(
-        ComputeNodeLabel,
-        bevy::render::graph::CameraDriverLabel,
-    );
-}
-
-/// This is called `ChunksToProcess` because this example originated
-/// from a use case of generating chunks of landscape or voxels
-/// It only exists in the render world.
-#[derive(Resource, Default)]
-struct ChunksToProcess(Vec<(u32, AssetId<Mesh>)>);
-
-/// `processed` is a `HashSet` contains the `AssetId`s that have been
-/// processed. We use that to remove `AssetId`s that have already
-/// been processed, which means each unique `GenerateMesh` will result
-/// in one compute shader mesh generation process instead of generating
-/// the mesh every frame.
-fn prepare_chunks(
-    meshes_to_generate: Query<&GenerateMesh>,
-    mut chunks: ResMut<ChunksToProcess>,
-    pipeline_cache: Res<PipelineCache>,
-    pipeline: Res<ComputePipeline>,
-    mut processed: Local<HashSet<(u32, AssetId<Mesh>)>>,
-) {
-    // If the pipeline isn't ready, then meshes
-    // won't be processed. So we want to wait until
-    // the pipeline is ready before considering any mesh processed.
-    if pipeline_cache
-        .get_compute_pipeline(pipeline.pipeline)
-        .is_some()
-    {
-        // get the AssetId for each Handle<Mesh>
-        // which we'll use later to get the relevant buffers
-        // from the mesh_allocator
-        let chunk_data: Vec<(u32, AssetId<Mesh>)> =
-            meshes_to_generate
-                .iter()
-                .filter_map(|gmesh| {
-                    let id = (
-                        gmesh.num_vertices,
-                        gmesh.mesh_handle.id(),
-                    );
-                    // Some(id)
-                    processed
-                        .contains(&id)
-                        .not()
-                        .then_some(id)
-                })
-                .collect();
-
-        // Cache any meshes we're going to process this frame
-        for id in &chunk_data {
-            processed.insert(*id);
-        }
-
-        chunks.0 = chunk_data;
-    }
-}
-
-#[derive(Resource)]
-struct ComputePipeline {
-    layout: BindGroupLayoutDescriptor,
-    pipeline: CachedComputePipelineId,
-}
-
-// init only happens once
-fn init_compute_pipeline(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    pipeline_cache: Res<PipelineCache>,
-) {
-    let layout = BindGroupLayoutDescriptor::new(
-        "",
-        &BindGroupLayoutEntries::sequential(
-            ShaderStages::COMPUTE,
-            (
-                // offsets
-                uniform_buffer::<DataRanges>(false),
-                // vertices
-                storage_buffer::<Vec<u32>>(false),
-                // indices
-                storage_buffer::<Vec<u32>>(false),
-            ),
-        ),
-    );
-    let shader = asset_server.load(SHADER_ASSET_PATH);
-    let pipeline = pipeline_cache.queue_compute_pipeline(
-        ComputePipelineDescriptor {
-            label: Some(
-                "Mesh generation compute shader".into(),
-            ),
-            layout: vec![layout.clone()],
-            shader: shader.clone(),
-            ..default()
-        },
-    );
-    commands.insert_resource(ComputePipeline {
-        layout,
-        pipeline,
-    });
-}
-
-/// Label to identify the node in the render graph
-#[derive(
-    Debug, Hash, PartialEq, Eq, Clone, RenderLabel,
-)]
-struct ComputeNodeLabel;
-
-/// The node that will execute the compute shader
-#[derive(Default)]
-struct ComputeNode {}
-
-// A uniform that holds the vertex and index offsets
-// for the vertex/index mesh_allocator buffer slabs
-#[derive(ShaderType)]
-struct DataRanges {
-    num_vertices: u32,
-    vertex_start: u32,
-    vertex_end: u32,
-    index_start: u32,
-    index_end: u32,
-}
-
-impl render_graph::Node for ComputeNode {
-    fn run(
-        &self,
-        _graph: &mut render_graph::RenderGraphContext,
-        render_context: &mut RenderContext,
-        world: &World,
-    ) -> Result<(), render_graph::NodeRunError> {
-        let chunks = world.resource::<ChunksToProcess>();
-        let mesh_allocator =
-            world.resource::<MeshAllocator>();
-
-        for mesh_id in &chunks.0 {
-            // info!(?mesh_id, "processing mesh");
-            let pipeline_cache =
-                world.resource::<PipelineCache>();
-            let pipeline =
-                world.resource::<ComputePipeline>();
-
-            if let Some(init_pipeline) = pipeline_cache
-                .get_compute_pipeline(pipeline.pipeline)
-            {
-                // the mesh_allocator holds slabs of meshes, so the buffers we get here
-                // can contain more data than just the mesh we're asking for.
-                // That's why there is a range field.
-                // You should *not* touch data in these buffers that is outside of the range.
-                let vertex_buffer_slice = mesh_allocator
-                    .mesh_vertex_slice(&mesh_id.1)
-                    .unwrap();
-                let index_buffer_slice = mesh_allocator
-                    .mesh_index_slice(&mesh_id.1)
-                    .unwrap();
-
-                let first = DataRanges {
-                    num_vertices: mesh_id.0,
-                    // there are 12 vertex data values (pos, normal, uv, tangent) per vertex
-                    // and the vertex_buffer_slice.range.start is in "vertex elements"
-                    // which includes all of that data, so each index is worth 8 indices
-                    // to our shader code.
-                    vertex_start: vertex_buffer_slice
-                        .range
-                        .start
-                        * 12,
-                    vertex_end: vertex_buffer_slice
-                        .range
-                        .end
-                        * 12,
-                    // but each vertex index is a single value, so the index of the
-                    // vertex indices is exactly what the value is
-                    index_start: index_buffer_slice
-                        .range
-                        .start,
-                    index_end: index_buffer_slice.range.end,
-                };
-
-                let mut uniforms =
-                    UniformBuffer::from(first);
-                uniforms.write_buffer(
-                    render_context.render_device(),
-                    world.resource::<RenderQueue>(),
-                );
-
-                // pass in the full mesh_allocator slabs as well as the first index
-                // offsets for the vertex and index buffers
-                let bind_group = render_context
-                    .render_device()
-                    .create_bind_group(
-                        None,
-                        &pipeline_cache
-                            .get_bind_group_layout(
-                                &pipeline.layout,
-                            ),
-                        &BindGroupEntries::sequential((
-                            &uniforms,
-                            vertex_buffer_slice
-                                .buffer
-                                .as_entire_buffer_binding(),
-                            index_buffer_slice
-                                .buffer
-                                .as_entire_buffer_binding(),
-                        )),
-                    );
-
-                let mut pass =
-                    render_context
-                        .command_encoder()
-                        .begin_compute_pass(&ComputePassDescriptor {
-                            label: Some("Mesh generation compute pass"),
-                            ..default()
-                        });
-                pass.push_debug_group("compute_mesh");
-
-                pass.set_bind_group(0, &bind_group, &[]);
-                pass.set_pipeline(init_pipeline);
-                // we only dispatch 1,1,1 workgroup here, but a real compute shader
-                // would take advantage of more and larger size workgroups
-                pass.dispatch_workgroups(1, 1, 1);
-
-                pass.pop_debug_group();
-            }
-        }
-
-        Ok(())
-    }
+/// Moves the noise to show a different terrain, which
+/// regenerates every chunk
+fn reseed_terrain(mut terrain: ResMut<Terrain>) {
+    terrain.noise.seed += Vec2::new(17.3, -9.1);
 }
@@ -0,0 +1,131 @@
+//! Generated chunks copied back to the main world, for
+//! placing objects on the terrain or building
+//! colliders.
+
+use bevy::prelude::*;
+
+use crate::{
+    compute::MeshGenerated,
+    terrain::{ChunkParams, Terrain, TerrainChunk},
+};
+
+/// What each chunk is copied back into, once it's
+/// generated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TerrainReadback {
+    /// Chunks only exist on the gpu
+    #[default]
+    Off,
+    /// A [`Heightfield`] of the chunk's surface
+    Heightfield,
+    /// A copy of the chunk's mesh, skirt included
+    Mesh,
+}
+
+/// Triggered on a chunk when its generated mesh has
+/// been read back, every time it's generated again.
+#[derive(EntityEvent, Clone, Debug)]
+pub struct ChunkGenerated {
+    pub entity: Entity,
+    pub readback: ChunkReadback,
+}
+
+#[derive(Clone, Debug)]
+pub enum ChunkReadback {
+    Heightfield(Handle<Heightfield>),
+    /// A main world mesh, in the chunk's own space
+    Mesh(Handle<Mesh>),
+}
+
+/// The heights of a chunk's vertices, row by row along
+/// z, in the chunk's own space.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct Heightfield {
+    /// The width of the chunk, in world units
+    pub size: f32,
+    /// Quads along each side, one less than there are
+    /// heights
+    pub resolution: u32,
+    pub heights: Vec<f32>,
+}
+
+impl Heightfield {
+    /// The height at `point` on the xz plane, relative
+    /// to the chunk's center, between the vertices
+    /// around it. Points outside the chunk are clamped
+    /// to its border.
+    pub fn height_at(&self, point: Vec2) -> f32 {
+        let row = self.resolution as usize + 1;
+        let cell = ((point / self.size + 0.5)
+            * self.resolution as f32)
+            .clamp(
+                Vec2::ZERO,
+                Vec2::splat(self.resolution as f32),
+            );
+        let start = cell.floor().min(Vec2::splat(
+            self.resolution.saturating_sub(1) as f32,
+        ));
+        let t = cell - start;
+        let (x, z) = (start.x as usize, start.y as usize);
+        let height = |x: usize, z: usize| {
+            self.heights
+                .get(z * row + x)
+                .copied()
+                .unwrap_or_default()
+        };
+        let near = height(x, z).lerp(height(x + 1, z), t.x);
+        let far = height(x, z + 1)
+            .lerp(height(x + 1, z + 1), t.x);
+        near.lerp(far, t.y)
+    }
+}
+
+/// Turns chunks that were read back into
+/// [`Heightfield`]s or meshes, and triggers
+/// [`ChunkGenerated`] on them.
+pub(crate) fn read_back_chunk(
+    generated: On<MeshGenerated<ChunkParams>>,
+    mut commands: Commands,
+    chunks: Query<(), With<TerrainChunk>>,
+    terrain: Res<Terrain>,
+    mut heightfields: ResMut<Assets<Heightfield>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let entity = generated.entity;
+    if !chunks.contains(entity) {
+        return;
+    }
+    let readback = match terrain.readback {
+        TerrainReadback::Off => return,
+        TerrainReadback::Heightfield => {
+            ChunkReadback::Heightfield(heightfields.add(
+                heightfield(&generated, terrain.chunk_size),
+            ))
+        }
+        TerrainReadback::Mesh => ChunkReadback::Mesh(
+            meshes.add(generated.mesh.to_mesh()),
+        ),
+    };
+    commands.trigger(ChunkGenerated { entity, readback });
+}
+
+/// The heights of the plane's vertices, leaving out the
+/// skirt after them.
+fn heightfield(
+    generated: &MeshGenerated<ChunkParams>,
+    size: f32,
+) -> Heightfield {
+    let surface = generated.generator.params.skirt_start;
+    let y =
+        generated.mesh.vertex_layout.position as usize + 1;
+    Heightfield {
+        size,
+        resolution: surface.isqrt().saturating_sub(1),
+        heights: generated
+            .mesh
+            .vertices()
+            .take(surface as usize)
+            .map(|vertex| vertex[y])
+            .collect(),
+    }
+}
@@ -0,0 +1,194 @@
+//! The cpu version of `landscape.wgsl`, for apps
+//! without a renderer and for tests.
+//!
+//! Every function here does the same math, in the same
+//! order, as the shader it mirrors, so it generates the
+//! same vertices as the gpu up to float rounding.
+//! `tests/gpu.rs` checks that, within the tolerances it
+//! states, on machines with a gpu.
+
+use bevy::math::{
+    Mat2, Vec2, Vec2Swizzles, Vec3, Vec3Swizzles, Vec4,
+    Vec4Swizzles,
+};
+
+use crate::{
+    compute::{GeneratedMesh, VertexLayout},
+    terrain::ChunkParams,
+};
+
+/// Fills in the vertices of `mesh` for `chunk`, where
+/// the plane's x and z are already in place. Attributes
+/// the mesh doesn't have are skipped, like the shader
+/// does.
+pub fn generate_vertices(
+    chunk: &ChunkParams,
+    mesh: &mut GeneratedMesh,
+) {
+    let noise = chunk.noise;
+    let layout = mesh.vertex_layout;
+    if layout.position == VertexLayout::MISSING {
+        return;
+    }
+    let position = layout.position as usize;
+    let write = |vertex: &mut [f32],
+                 offset: u32,
+                 values: &[f32]| {
+        if offset != VertexLayout::MISSING {
+            let offset = offset as usize;
+            vertex[offset..offset + values.len()]
+                .copy_from_slice(values);
+        }
+    };
+    for (i, vertex) in mesh.vertices_mut().enumerate() {
+        let x = vertex[position] + chunk.offset.x;
+        let z = vertex[position + 2] + chunk.offset.y;
+        let p = Vec2::new(x, z) / noise.scale + noise.seed;
+        let terrain = terrain(p);
+        let height = terrain.x * noise.height;
+        let slope =
+            terrain.yz() * noise.height / noise.scale;
+        let normal =
+            Vec3::new(-slope.x, 1., -slope.y).normalize();
+
+        write(vertex, layout.normal, &normal.to_array());
+
+        let along_u = Vec3::new(1., slope.x, 0.);
+        let tangent = (along_u
+            - normal * normal.dot(along_u))
+        .normalize();
+
+        write(
+            vertex,
+            layout.tangent,
+            &tangent.extend(1.).to_array(),
+        );
+
+        let mut y = height;
+        if i as u32 >= chunk.skirt_start {
+            y -= chunk.skirt_depth;
+        }
+        vertex[position + 1] = y;
+    }
+}
+
+/// The unscaled height at a point in noise space
+pub fn terrain_height(p: Vec2) -> f32 {
+    terrain(p).x
+}
+
+/// The unscaled height at a point in noise space, and
+/// its gradient in yz
+pub fn terrain(p: Vec2) -> Vec3 {
+    fbm_gradient(p)
+}
+
+/// `bevy_shader_utils::fbm::fbm_gradient`, the fbm
+/// along with its gradient in yz
+pub fn fbm_gradient(p: Vec2) -> Vec3 {
+    let m_two = Mat2::from_cols(
+        Vec2::new(0.8, 0.6),
+        Vec2::new(-0.6, 0.8),
+    );
+    let mut q = p;
+    // how q changes with p, to bring each octave's
+    // gradient back
+    let mut jacobian = Mat2::IDENTITY;
+    let mut f = Vec3::ZERO;
+    let mut octave =
+        |amplitude: f32, q: Vec2, jacobian: Mat2| {
+            let n = simplex_noise_2d_gradient(q);
+            let gradient = jacobian.transpose() * n.yz();
+            f += amplitude
+                * Vec3::new(n.x, gradient.x, gradient.y);
+        };
+    octave(0.5000, q, jacobian);
+    q = m_two * q * 2.02;
+    jacobian = m_two * jacobian * 2.02;
+    octave(0.2500, q, jacobian);
+    q = m_two * q * 2.03;
+    jacobian = m_two * jacobian * 2.03;
+    octave(0.1250, q, jacobian);
+    q = m_two * q * 2.01;
+    jacobian = m_two * jacobian * 2.01;
+    octave(0.0625, q, jacobian);
+    f / 0.9375
+}
+
+/// `bevy_shader_utils::simplex_noise_2d::simplex_noise_2d_gradient`,
+/// the noise along with its gradient in yz
+pub fn simplex_noise_2d_gradient(v: Vec2) -> Vec3 {
+    let c = Vec4::new(
+        0.211_324_87,  // (3.0-sqrt(3.0))/6.0
+        0.366_025_42,  // 0.5*(sqrt(3.0)-1.0)
+        -0.577_350_26, // -1.0 + 2.0 * C.x
+        0.024_390_243, // 1.0 / 41.0
+    );
+    let mod289 =
+        |x: Vec3| x - (x * (1. / 289.)).floor() * 289.;
+    let permute = |x: Vec3| mod289(((x * 34.) + 1.) * x);
+    // wgsl's fract, rather than rust's, which rounds
+    // towards zero
+    let fract = |x: Vec3| x - x.floor();
+
+    // First corner
+    let mut i = (v + v.dot(c.yy())).floor();
+    let x0 = v - i + i.dot(c.xx());
+
+    // Other corners
+    let i1 = if x0.x > x0.y {
+        Vec2::new(1., 0.)
+    } else {
+        Vec2::new(0., 1.)
+    };
+    let mut x12 = x0.xyxy() + c.xxzz();
+    x12.x -= i1.x;
+    x12.y -= i1.y;
+
+    // Permutations
+    i = i - (i * (1. / 289.)).floor() * 289.;
+    let p = permute(
+        permute(i.y + Vec3::new(0., i1.y, 1.))
+            + i.x
+            + Vec3::new(0., i1.x, 1.),
+    );
+    let t = (0.5
+        - Vec3::new(
+            x0.dot(x0),
+            x12.xy().dot(x12.xy()),
+            x12.zw().dot(x12.zw()),
+        ))
+    .max(Vec3::ZERO);
+    let t2 = t * t;
+
+    // Gradients: 41 points uniformly over a line,
+    // mapped onto a diamond.
+    let x = 2. * fract(p * c.www()) - 1.;
+    let h = x.abs() - 0.5;
+    let ox = (x + 0.5).floor();
+    let a0 = x - ox;
+
+    // Normalize gradients implicitly by scaling m
+    let norm =
+        1.792_842_9 - 0.853_734_7 * (a0 * a0 + h * h);
+    let m = t2 * t2 * norm;
+
+    // Compute final noise value at P
+    let g = Vec3::new(
+        a0.x * x0.x + h.x * x0.y,
+        a0.y * x12.x + h.y * x12.y,
+        a0.z * x12.z + h.z * x12.w,
+    );
+
+    // each corner adds t^4 * dot(gradient, x), which
+    // changes by t^4 * gradient - 8 * t^3 *
+    // dot(gradient, x) * x
+    let falloff = -8. * t2 * t * norm * g;
+    let d = m.x * Vec2::new(a0.x, h.x)
+        + falloff.x * x0
+        + m.y * Vec2::new(a0.y, h.y)
+        + falloff.y * x12.xy()
+        + m.z * Vec2::new(a0.z, h.z)
+        + falloff.z * x12.zw();
+    130. * Vec3::new(m.dot(g), d.x, d.y)
+}
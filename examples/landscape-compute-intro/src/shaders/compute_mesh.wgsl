@@ -0,0 +1,103 @@
+#define_import_path landscape_compute_intro::compute_mesh
+
+// Where each attribute is in a vertex, in floats from the vertex's
+// start, or NO_ATTRIBUTE when the mesh doesn't have it
+struct VertexLayout {
+    // floats from one vertex to the next
+    stride: u32,
+    position: u32,
+    normal: u32,
+    // ATTRIBUTE_UV_0
+    uv: u32,
+    tangent: u32,
+    color: u32,
+}
+
+// The mesh being generated: `vertex_start` is where its vertices
+// start in `vertex_data`, in floats, and `index_start` where its
+// indices start in `index_data`
+struct ComputeMesh {
+    vertex_start: u32,
+    vertex_count: u32,
+    index_start: u32,
+    index_count: u32,
+    vertex_layout: VertexLayout,
+}
+
+const NO_ATTRIBUTE: u32 = 0xffffffffu;
+
+@group(0) @binding(0) var<uniform> mesh: ComputeMesh;
+// The mesh_allocator holds slabs of meshes, so these can hold more
+// than the mesh being generated. Only write through the functions
+// below, which stay inside its range.
+@group(0) @binding(1) var<storage, read_write> vertex_data: array<f32>;
+@group(0) @binding(2) var<storage, read_write> index_data: array<u32>;
+
+// where the attribute at `offset` in vertex `i` starts in `vertex_data`
+fn attribute_offset(i: u32, offset: u32) -> u32 {
+    return mesh.vertex_start + i * mesh.vertex_layout.stride + offset;
+}
+
+fn has_attribute(i: u32, offset: u32) -> bool {
+    return offset != NO_ATTRIBUTE && i < mesh.vertex_count;
+}
+
+fn read_position(i: u32) -> vec3<f32> {
+    if !has_attribute(i, mesh.vertex_layout.position) {
+        return vec3(0.);
+    }
+    let o = attribute_offset(i, mesh.vertex_layout.position);
+    return vec3(vertex_data[o], vertex_data[o + 1], vertex_data[o + 2]);
+}
+
+fn write_position(i: u32, position: vec3<f32>) {
+    write_vec3(i, mesh.vertex_layout.position, position);
+}
+
+fn write_normal(i: u32, normal: vec3<f32>) {
+    write_vec3(i, mesh.vertex_layout.normal, normal);
+}
+
+fn write_uv(i: u32, uv: vec2<f32>) {
+    if !has_attribute(i, mesh.vertex_layout.uv) {
+        return;
+    }
+    let o = attribute_offset(i, mesh.vertex_layout.uv);
+    vertex_data[o] = uv.x;
+    vertex_data[o + 1] = uv.y;
+}
+
+fn write_tangent(i: u32, tangent: vec4<f32>) {
+    write_vec4(i, mesh.vertex_layout.tangent, tangent);
+}
+
+fn write_color(i: u32, color: vec4<f32>) {
+    write_vec4(i, mesh.vertex_layout.color, color);
+}
+
+fn write_index(i: u32, index: u32) {
+    if i < mesh.index_count {
+        index_data[mesh.index_start + i] = index;
+    }
+}
+
+fn write_vec3(i: u32, offset: u32, value: vec3<f32>) {
+    if !has_attribute(i, offset) {
+        return;
+    }
+    let o = attribute_offset(i, offset);
+    vertex_data[o] = value.x;
+    vertex_data[o + 1] = value.y;
+    vertex_data[o + 2] = value.z;
+}
+
+fn write_vec4(i: u32, offset: u32, value: vec4<f32>) {
+    if !has_attribute(i, offset) {
+        return;
+    }
+    let o = attribute_offset(i, offset);
+    vertex_data[o] = value.x;
+    vertex_data[o + 1] = value.y;
+    vertex_data[o + 2] = value.z;
+    vertex_data[o + 3] = value.w;
+}
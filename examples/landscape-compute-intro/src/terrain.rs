@@ -0,0 +1,480 @@
+//! Streams terrain chunks in and out around the
+//! [`TerrainFocus`], each one a flat plane the compute
+//! shader displaces with fbm noise.
+//!
+//! Chunks further away use coarser meshes. Every level
+//! halves the quads of the one before, so the vertices
+//! along a coarse edge are also on the finer neighbor's
+//! edge, and the cracks between them are hidden by a
+//! skirt hanging down from each chunk's border.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    camera::{primitives::Aabb, visibility::NoAutoAabb},
+    mesh::{Indices, PrimitiveTopology},
+    platform::collections::HashMap,
+    prelude::*,
+    render::render_resource::ShaderType,
+    shader::ShaderRef,
+};
+
+use crate::{
+    compute::{
+        ComputeMeshGenerator, ComputeMeshGeneratorPlugin,
+        GeneratedMesh, MeshGeneratorParams,
+    },
+    readback::{
+        read_back_chunk, Heightfield, TerrainReadback,
+    },
+    reference,
+};
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ComputeMeshGeneratorPlugin::<
+            ChunkParams,
+        >::default())
+            .init_asset::<Heightfield>()
+            .init_resource::<Terrain>()
+            .init_resource::<TerrainChunks>()
+            .add_systems(Update, stream_chunks)
+            .add_observer(read_back_chunk);
+    }
+}
+
+/// Chunks are spawned around the entity with this
+/// component, usually the camera.
+#[derive(Component)]
+pub struct TerrainFocus;
+
+/// How the terrain is split into chunks and shaped.
+/// Changing the noise or skirts regenerates every
+/// chunk, changing the chunk size or resolution
+/// respawns them.
+#[derive(Resource, Clone, Debug)]
+pub struct Terrain {
+    /// The width of a chunk, in world units
+    pub chunk_size: f32,
+    /// Quads along each side of the closest chunks. A
+    /// power of two, so it can be halved for every
+    /// level of detail.
+    pub resolution: u32,
+    /// How many chunks away from the focus are kept
+    pub view_distance: u32,
+    /// The distances from the focus to a chunk's center
+    /// where each coarser level of detail starts
+    pub lod_distances: Vec<f32>,
+    /// How far past a distance a chunk has to move
+    /// before its level changes, so chunks on the edge
+    /// don't switch back and forth
+    pub lod_hysteresis: f32,
+    /// How far the skirts hang below the border of
+    /// each chunk
+    pub skirt_depth: f32,
+    pub noise: TerrainNoise,
+    /// Whether generated chunks are copied back to the
+    /// main world, triggering
+    /// [`ChunkGenerated`](crate::readback::ChunkGenerated)
+    pub readback: TerrainReadback,
+    pub material: Handle<StandardMaterial>,
+}
+
+impl Default for Terrain {
+    fn default() -> Self {
+        Self {
+            chunk_size: 200.,
+            resolution: 128,
+            view_distance: 3,
+            lod_distances: vec![250., 450.],
+            lod_hysteresis: 25.,
+            skirt_depth: 5.,
+            noise: TerrainNoise::default(),
+            readback: TerrainReadback::Off,
+            material: Handle::default(),
+        }
+    }
+}
+
+impl Terrain {
+    /// The level of detail of a chunk `distance` away
+    /// from the focus, ignoring hysteresis.
+    pub fn lod_at(&self, distance: f32) -> u32 {
+        // levels can't go below one quad per side
+        let coarsest = self.resolution.max(1).ilog2();
+        (self
+            .lod_distances
+            .iter()
+            .filter(|&&start| distance >= start)
+            .count() as u32)
+            .min(coarsest)
+    }
+
+    /// The level of detail a chunk `distance` away from
+    /// the focus should switch to from `current`.
+    pub fn lod(&self, distance: f32, current: u32) -> u32 {
+        // only move to a level that's still right
+        // `lod_hysteresis` closer to the current one
+        let coarser =
+            self.lod_at(distance - self.lod_hysteresis);
+        let finer =
+            self.lod_at(distance + self.lod_hysteresis);
+        if coarser > current {
+            coarser
+        } else if finer < current {
+            finer
+        } else {
+            current
+        }
+    }
+}
+
+/// The fbm noise the chunks are displaced by, passed
+/// to the compute shader as is.
+#[derive(Clone, Copy, Debug, PartialEq, ShaderType)]
+pub struct TerrainNoise {
+    /// World units per unit of noise. Larger values
+    /// give wider hills.
+    pub scale: f32,
+    /// How far the terrain goes above and below 0
+    pub height: f32,
+    /// Moves the noise, for a different terrain
+    pub seed: Vec2,
+}
+
+impl Default for TerrainNoise {
+    fn default() -> Self {
+        Self {
+            scale: 50.,
+            height: 10.,
+            seed: Vec2::ZERO,
+        }
+    }
+}
+
+/// Where a chunk is and how it's shaped, what
+/// `landscape.wgsl` generates it from.
+#[derive(Clone, Copy, Debug, PartialEq, ShaderType)]
+pub struct ChunkParams {
+    pub noise: TerrainNoise,
+    /// Where the center of the chunk is in the world,
+    /// on the xz plane
+    pub offset: Vec2,
+    /// The vertices from this one on are the skirt,
+    /// which the shader lowers by `skirt_depth`
+    pub skirt_start: u32,
+    pub skirt_depth: f32,
+}
+
+impl MeshGeneratorParams for ChunkParams {
+    fn shader() -> ShaderRef {
+        "landscape.wgsl".into()
+    }
+
+    fn generate_on_cpu(
+        &self,
+        mesh: &mut GeneratedMesh,
+    ) -> bool {
+        reference::generate_vertices(self, mesh);
+        true
+    }
+}
+
+/// A chunk of terrain, where it is on the chunk grid
+/// and which level of detail its mesh has.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TerrainChunk {
+    pub coord: IVec2,
+    /// 0 is the finest level
+    pub lod: u32,
+}
+
+/// The chunks that currently exist, by their
+/// coordinates on the chunk grid.
+#[derive(Resource, Default)]
+pub struct TerrainChunks {
+    pub chunks: HashMap<IVec2, Entity>,
+    /// The chunk size and resolution `templates` were
+    /// built with
+    layout: Option<(f32, u32)>,
+    /// The flat mesh of each level of detail every
+    /// chunk starts from
+    templates: Vec<ChunkTemplate>,
+}
+
+struct ChunkTemplate {
+    mesh: Mesh,
+    /// The skirt's vertices come after this many
+    skirt_start: u32,
+}
+
+fn stream_chunks(
+    mut commands: Commands,
+    terrain: Res<Terrain>,
+    mut chunks: ResMut<TerrainChunks>,
+    focus: Query<&GlobalTransform, With<TerrainFocus>>,
+    mut chunk_entities: Query<(
+        &mut TerrainChunk,
+        &mut ComputeMeshGenerator<ChunkParams>,
+        &mut Mesh3d,
+        &mut Aabb,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok(focus) = focus.single() else {
+        return;
+    };
+    let chunks = &mut *chunks;
+
+    // a new layout needs new meshes, so every chunk
+    // is respawned
+    let layout = (terrain.chunk_size, terrain.resolution);
+    if chunks.layout != Some(layout) {
+        for (_, entity) in chunks.chunks.drain() {
+            commands.entity(entity).despawn();
+        }
+        chunks.layout = Some(layout);
+        chunks.templates =
+            (0..=terrain.resolution.max(1).ilog2())
+                .map(|lod| {
+                    chunk_template(
+                        terrain.chunk_size,
+                        (terrain.resolution >> lod).max(1),
+                    )
+                })
+                .collect();
+    }
+
+    let focus = focus.translation().xz();
+    let center =
+        (focus / terrain.chunk_size).floor().as_ivec2();
+    let distance = terrain.view_distance as i32;
+    let in_view = |coord: IVec2| {
+        (coord - center).length_squared()
+            <= distance * distance
+    };
+    let chunk_center = |coord: IVec2| {
+        (coord.as_vec2() + 0.5) * terrain.chunk_size
+    };
+
+    chunks.chunks.retain(|&coord, &mut entity| {
+        let keep = in_view(coord);
+        if !keep {
+            commands.entity(entity).despawn();
+        }
+        keep
+    });
+
+    // the render world regenerates chunks whose mesh or
+    // noise changed
+    for &entity in chunks.chunks.values() {
+        let Ok((
+            mut chunk,
+            mut generator,
+            mut mesh,
+            mut aabb,
+        )) = chunk_entities.get_mut(entity)
+        else {
+            continue;
+        };
+        let lod = terrain.lod(
+            focus.distance(chunk_center(chunk.coord)),
+            chunk.lod,
+        );
+        if lod != chunk.lod {
+            let template = &chunks.templates[lod as usize];
+            let handle = meshes.add(template.mesh.clone());
+            chunk.lod = lod;
+            *generator = chunk_generator(
+                template,
+                handle.clone(),
+                chunk.coord,
+                &terrain,
+            );
+            mesh.0 = handle;
+        } else if terrain.is_changed() {
+            generator.params.noise = terrain.noise;
+            generator.params.skirt_depth =
+                terrain.skirt_depth;
+            generator.readback =
+                terrain.readback != TerrainReadback::Off;
+        }
+        if terrain.is_changed() {
+            *aabb = chunk_aabb(&terrain);
+        }
+    }
+
+    for y in -distance..=distance {
+        for x in -distance..=distance {
+            let coord = center + IVec2::new(x, y);
+            if !in_view(coord)
+                || chunks.chunks.contains_key(&coord)
+            {
+                continue;
+            }
+            let offset = chunk_center(coord);
+            // new chunks start at whatever level their
+            // distance asks for
+            let lod =
+                terrain.lod_at(focus.distance(offset));
+            let template = &chunks.templates[lod as usize];
+            let handle = meshes.add(template.mesh.clone());
+            let entity = commands
+                .spawn((
+                    TerrainChunk { coord, lod },
+                    chunk_generator(
+                        template,
+                        handle.clone(),
+                        coord,
+                        &terrain,
+                    ),
+                    Mesh3d(handle),
+                    MeshMaterial3d(
+                        terrain.material.clone(),
+                    ),
+                    Transform::from_xyz(
+                        offset.x, 0., offset.y,
+                    ),
+                    // the mesh is flat until the compute
+                    // shader runs, and only exists in the
+                    // render world after that
+                    NoAutoAabb,
+                    chunk_aabb(&terrain),
+                ))
+                .id();
+            chunks.chunks.insert(coord, entity);
+        }
+    }
+}
+
+/// Generates the chunk at `coord` into `mesh`, a copy
+/// of `template`.
+fn chunk_generator(
+    template: &ChunkTemplate,
+    mesh: Handle<Mesh>,
+    coord: IVec2,
+    terrain: &Terrain,
+) -> ComputeMeshGenerator<ChunkParams> {
+    ComputeMeshGenerator {
+        mesh,
+        params: ChunkParams {
+            noise: terrain.noise,
+            offset: (coord.as_vec2() + 0.5)
+                * terrain.chunk_size,
+            skirt_start: template.skirt_start,
+            skirt_depth: terrain.skirt_depth,
+        },
+        readback: terrain.readback != TerrainReadback::Off,
+    }
+}
+
+/// The bounds of a chunk, as high as the noise goes
+/// and as low as the skirts hang.
+fn chunk_aabb(terrain: &Terrain) -> Aabb {
+    let half = terrain.chunk_size / 2.;
+    let height = terrain.noise.height;
+    Aabb::from_min_max(
+        Vec3::new(
+            -half,
+            -height - terrain.skirt_depth,
+            -half,
+        ),
+        Vec3::new(half, height, half),
+    )
+}
+
+/// A flat plane of `quads` by `quads` with the
+/// attributes the compute shader writes: position,
+/// normal, uv and tangent.
+///
+/// The skirt's vertices come after the plane's, one
+/// under each border vertex. They start at the same
+/// position, and the shader lowers them by their index.
+fn chunk_template(size: f32, quads: u32) -> ChunkTemplate {
+    let row = quads + 1;
+    let mut positions = vec![];
+    let mut uvs = vec![];
+    for z in 0..row {
+        for x in 0..row {
+            let uv = Vec2::new(x as f32, z as f32)
+                / quads as f32;
+            positions.push([
+                (uv.x - 0.5) * size,
+                0.,
+                (uv.y - 0.5) * size,
+            ]);
+            uvs.push(uv.to_array());
+        }
+    }
+
+    let mut indices = vec![];
+    for z in 0..quads {
+        for x in 0..quads {
+            let quad = z * row + x;
+            indices.extend([
+                quad + row + 1,
+                quad + 1,
+                quad + row,
+                quad,
+                quad + row,
+                quad + 1,
+            ]);
+        }
+    }
+
+    // the border, going around so the skirt faces
+    // outwards: along the +z edge towards +x, then back
+    // along the +x, -z and -x edges
+    let last = row - 1;
+    let border: Vec<u32> = (0..last)
+        .map(|x| last * row + x)
+        .chain((0..last).map(|z| (last - z) * row + last))
+        .chain((0..last).map(|x| last - x))
+        .chain((0..last).map(|z| z * row))
+        .collect();
+    let skirt_start = positions.len() as u32;
+    for &vertex in &border {
+        positions.push(positions[vertex as usize]);
+        uvs.push(uvs[vertex as usize]);
+    }
+    for (i, &top) in border.iter().enumerate() {
+        let next = (i + 1) % border.len();
+        let (bottom, next_bottom) = (
+            skirt_start + i as u32,
+            skirt_start + next as u32,
+        );
+        let next_top = border[next];
+        indices.extend([
+            top,
+            bottom,
+            next_top,
+            next_top,
+            bottom,
+            next_bottom,
+        ]);
+    }
+
+    let count = positions.len();
+    let mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        positions,
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0., 1., 0.]; count],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    // the shader writes the real tangents, this only
+    // makes room for them
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_TANGENT,
+        vec![[1., 0., 0., 1.]; count],
+    );
+    ChunkTemplate { mesh, skirt_start }
+}
@@ -0,0 +1,242 @@
+use bevy::{
+    mesh::{
+        MeshVertexBufferLayouts, VertexAttributeValues,
+    },
+    prelude::*,
+    render::render_resource::ShaderType,
+    shader::ShaderRef,
+};
+use landscape_compute_intro::compute::{
+    empty_mesh, ComputeMeshGenerator,
+    ComputeMeshGeneratorPlugin, GeneratedMesh,
+    MeshGenerated, MeshGeneratorParams, VertexLayout,
+};
+
+/// A flat fan of triangles around the origin, with
+/// colors instead of normals and uvs.
+#[derive(Clone, Copy, Debug, PartialEq, ShaderType)]
+struct Fan {
+    sides: u32,
+    radius: f32,
+}
+
+impl MeshGeneratorParams for Fan {
+    // only generated on the cpu here
+    fn shader() -> ShaderRef {
+        ShaderRef::Default
+    }
+
+    fn generate_on_cpu(
+        &self,
+        mesh: &mut GeneratedMesh,
+    ) -> bool {
+        let layout = mesh.vertex_layout;
+        let (position, color) = (
+            layout.position as usize,
+            layout.color as usize,
+        );
+        for (i, vertex) in mesh.vertices_mut().enumerate() {
+            // the center, then around the rim
+            let point = if i == 0 {
+                Vec2::ZERO
+            } else {
+                Vec2::from_angle(
+                    i as f32 / self.sides as f32
+                        * std::f32::consts::TAU,
+                ) * self.radius
+            };
+            vertex[position..position + 3]
+                .copy_from_slice(&[point.x, 0., point.y]);
+            vertex[color..color + 4]
+                .copy_from_slice(&[1., 0., 0., 1.]);
+        }
+        for (side, triangle) in
+            mesh.indices.chunks_exact_mut(3).enumerate()
+        {
+            let side = side as u32;
+            triangle.copy_from_slice(&[
+                0,
+                side % self.sides + 1,
+                (side + 1) % self.sides + 1,
+            ]);
+        }
+        true
+    }
+}
+
+#[derive(Resource, Default)]
+struct Generated(Vec<MeshGenerated<Fan>>);
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        ComputeMeshGeneratorPlugin::<Fan>::default(),
+    ))
+    .init_asset::<Mesh>()
+    .init_resource::<Generated>()
+    .add_observer(
+        |generated: On<MeshGenerated<Fan>>,
+         mut log: ResMut<Generated>| {
+            log.0.push(generated.event().clone());
+        },
+    );
+    app
+}
+
+fn fan_mesh(app: &mut App, sides: usize) -> Handle<Mesh> {
+    app.world_mut().resource_mut::<Assets<Mesh>>().add(
+        empty_mesh(
+            &[
+                Mesh::ATTRIBUTE_POSITION,
+                Mesh::ATTRIBUTE_COLOR,
+            ],
+            sides + 1,
+            sides * 3,
+        ),
+    )
+}
+
+fn take_generated(
+    app: &mut App,
+) -> Vec<MeshGenerated<Fan>> {
+    std::mem::take(
+        &mut app.world_mut().resource_mut::<Generated>().0,
+    )
+}
+
+#[test]
+fn layouts_come_from_the_mesh() {
+    let mut layouts = MeshVertexBufferLayouts::default();
+    let mut layout = |mesh: Mesh| {
+        VertexLayout::new(
+            &mesh
+                .get_mesh_vertex_buffer_layout(&mut layouts)
+                .0,
+        )
+    };
+
+    let plane = Plane3d::default()
+        .mesh()
+        .build()
+        .with_generated_tangents()
+        .unwrap();
+    assert_eq!(
+        layout(plane),
+        VertexLayout {
+            stride: 12,
+            position: 0,
+            normal: 3,
+            uv: 6,
+            tangent: 8,
+            color: VertexLayout::MISSING,
+        }
+    );
+
+    let fan = empty_mesh(
+        &[Mesh::ATTRIBUTE_COLOR, Mesh::ATTRIBUTE_POSITION],
+        3,
+        3,
+    );
+    assert_eq!(
+        layout(fan),
+        VertexLayout {
+            stride: 7,
+            position: 0,
+            normal: VertexLayout::MISSING,
+            uv: VertexLayout::MISSING,
+            tangent: VertexLayout::MISSING,
+            color: 3,
+        }
+    );
+}
+
+#[test]
+fn vertex_and_index_counts_follow_the_mesh() {
+    let mut app = app();
+    let mesh = fan_mesh(&mut app, 3);
+    let entity = app
+        .world_mut()
+        .spawn(ComputeMeshGenerator {
+            mesh,
+            params: Fan {
+                sides: 3,
+                radius: 2.,
+            },
+            readback: true,
+        })
+        .id();
+    app.update();
+    app.update();
+
+    let generated = take_generated(&mut app);
+    assert_eq!(generated.len(), 1);
+    assert_eq!(generated[0].entity, entity);
+    let mesh = generated[0].mesh.to_mesh();
+    assert_eq!(mesh.count_vertices(), 4);
+    assert_eq!(mesh.indices().unwrap().len(), 9);
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        panic!("expected positions");
+    };
+    for position in &positions[1..] {
+        let length = Vec3::from(*position).length();
+        assert!((length - 2.).abs() < 1e-5);
+    }
+    assert!(mesh
+        .attribute(Mesh::ATTRIBUTE_COLOR)
+        .is_some());
+    assert!(mesh
+        .attribute(Mesh::ATTRIBUTE_NORMAL)
+        .is_none());
+
+    // more sides need a bigger mesh
+    let bigger = fan_mesh(&mut app, 8);
+    let mut generator = app
+        .world_mut()
+        .get_mut::<ComputeMeshGenerator<Fan>>(entity)
+        .unwrap();
+    generator.mesh = bigger;
+    generator.params.sides = 8;
+    app.update();
+    app.update();
+
+    let generated = take_generated(&mut app);
+    assert_eq!(generated.len(), 1);
+    let mesh = &generated[0].mesh;
+    assert_eq!(mesh.vertices().count(), 9);
+    assert_eq!(mesh.indices.len(), 24);
+    assert_eq!(&mesh.indices[21..], &[0, 8, 1]);
+}
+
+#[test]
+fn stale_meshes_are_not_read_back() {
+    let mut app = app();
+    let mesh = fan_mesh(&mut app, 3);
+    let entity = app
+        .world_mut()
+        .spawn(ComputeMeshGenerator {
+            mesh,
+            params: Fan {
+                sides: 3,
+                radius: 1.,
+            },
+            readback: true,
+        })
+        .id();
+    app.update();
+    // changed before the first one was received
+    app.world_mut()
+        .get_mut::<ComputeMeshGenerator<Fan>>(entity)
+        .unwrap()
+        .params
+        .radius = 3.;
+    app.update();
+    app.update();
+
+    let generated = take_generated(&mut app);
+    assert_eq!(generated.len(), 1);
+    assert_eq!(generated[0].generator.params.radius, 3.);
+}
@@ -0,0 +1,173 @@
+//! The compute shader against `reference`, on a real
+//! gpu. Every chunk is generated by `landscape.wgsl`,
+//! copied back through the `ComputeNode` and
+//! `map_readbacks`, and compared to what `reference`
+//! generates from the same flat plane.
+//!
+//! Needs an adapter, so it's ignored by default. Run it
+//! with `cargo test --test gpu -- --ignored`.
+
+use std::time::Duration;
+
+use bevy::{
+    app::PluginsState,
+    prelude::*,
+    tasks::tick_global_task_pools_on_main_thread,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use bevy_shader_utils::ShaderUtilsPlugin;
+use landscape_compute_intro::{
+    compute::{GeneratedMesh, MeshGenerated},
+    readback::TerrainReadback,
+    reference,
+    terrain::{
+        ChunkParams, Terrain, TerrainFocus, TerrainPlugin,
+    },
+};
+
+/// How far apart the gpu's and the cpu's positions can
+/// be, in world units, with hills 10 units high. f32
+/// noise on either side is good to about 1e-6 of that,
+/// this leaves room for the gpu's fused and
+/// approximated math.
+const POSITION_TOLERANCE: f32 = 1e-3;
+/// The same for each component of the unit normals and
+/// tangents.
+const DIRECTION_TOLERANCE: f32 = 1e-3;
+
+/// The center chunk and its four neighbors.
+const CHUNKS: usize = 5;
+
+#[derive(Resource, Default)]
+struct Generated(Vec<(ChunkParams, GeneratedMesh)>);
+
+/// A renderer without a window, generating small
+/// chunks at full detail around a focus in the middle
+/// of chunk (0, 0).
+fn gpu_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+        TerrainPlugin,
+        ShaderUtilsPlugin,
+    ))
+    .init_resource::<Generated>()
+    .insert_resource(Terrain {
+        chunk_size: 100.,
+        resolution: 8,
+        view_distance: 1,
+        lod_distances: vec![],
+        readback: TerrainReadback::Mesh,
+        ..default()
+    })
+    .add_observer(
+        |generated: On<MeshGenerated<ChunkParams>>,
+         mut log: ResMut<Generated>| {
+            log.0.push((
+                generated.generator.params,
+                generated.mesh.clone(),
+            ));
+        },
+    );
+
+    // the renderer is created in the background
+    while app.plugins_state() == PluginsState::Adding {
+        tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+
+    app.world_mut().spawn((
+        Transform::from_xyz(50., 0., 50.),
+        TerrainFocus,
+    ));
+    app
+}
+
+fn assert_close<const N: usize>(
+    name: &str,
+    offset: u32,
+    gpu: &GeneratedMesh,
+    cpu: &GeneratedMesh,
+    tolerance: f32,
+) {
+    let (Some(gpu), Some(cpu)) =
+        (gpu.attribute::<N>(offset), cpu.attribute::<N>(offset))
+    else {
+        panic!("chunks have {name}s");
+    };
+    for (i, (gpu, cpu)) in gpu.iter().zip(&cpu).enumerate() {
+        assert!(
+            gpu.iter()
+                .zip(cpu)
+                .all(|(gpu, cpu)| (gpu - cpu).abs()
+                    <= tolerance),
+            "{name} {i} is {gpu:?} on the gpu, {cpu:?} on the cpu"
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs a gpu adapter"]
+fn gpu_chunks_match_the_reference() {
+    let mut app = gpu_app();
+
+    // pipelines compile in the background, and copies
+    // are mapped a frame or more after they're
+    // submitted
+    for _ in 0..1000 {
+        app.update();
+        if app.world().resource::<Generated>().0.len()
+            >= CHUNKS
+        {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    let generated = std::mem::take(
+        &mut app.world_mut().resource_mut::<Generated>().0,
+    );
+    assert_eq!(
+        generated.len(),
+        CHUNKS,
+        "every chunk is read back"
+    );
+
+    for (params, gpu) in generated {
+        // `reference` only reads the plane's x and z,
+        // which the shader leaves as they are
+        let mut cpu = gpu.clone();
+        reference::generate_vertices(&params, &mut cpu);
+
+        let layout = gpu.vertex_layout;
+        assert_close::<3>(
+            "position",
+            layout.position,
+            &gpu,
+            &cpu,
+            POSITION_TOLERANCE,
+        );
+        assert_close::<3>(
+            "normal",
+            layout.normal,
+            &gpu,
+            &cpu,
+            DIRECTION_TOLERANCE,
+        );
+        assert_close::<4>(
+            "tangent",
+            layout.tangent,
+            &gpu,
+            &cpu,
+            DIRECTION_TOLERANCE,
+        );
+        assert_eq!(gpu.indices, cpu.indices);
+    }
+}
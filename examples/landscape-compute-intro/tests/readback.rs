@@ -0,0 +1,422 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{
+        Indices, PrimitiveTopology, VertexAttributeValues,
+    },
+    prelude::*,
+};
+use landscape_compute_intro::{
+    readback::{
+        ChunkGenerated, ChunkReadback, Heightfield,
+        TerrainReadback,
+    },
+    reference,
+    terrain::{
+        Terrain, TerrainChunk, TerrainFocus, TerrainNoise,
+        TerrainPlugin,
+    },
+};
+
+#[derive(Resource, Default)]
+struct Generated(Vec<ChunkGenerated>);
+
+/// Small chunks, the focus's and its four neighbors,
+/// all at full detail.
+fn terrain() -> Terrain {
+    Terrain {
+        chunk_size: 100.,
+        resolution: 8,
+        view_distance: 1,
+        lod_distances: vec![],
+        ..default()
+    }
+}
+
+/// A headless app, so chunks are generated by
+/// `reference`, with the focus in the middle of chunk
+/// (0, 0).
+fn app(terrain: Terrain) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        TerrainPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_resource::<Generated>()
+    .insert_resource(terrain)
+    .add_observer(
+        |generated: On<ChunkGenerated>,
+         mut log: ResMut<Generated>| {
+            log.0.push(generated.event().clone());
+        },
+    );
+    app.world_mut().spawn((
+        Transform::from_xyz(50., 0., 50.),
+        TerrainFocus,
+    ));
+    // chunks are spawned and generated in the first
+    // frame, and read back in the next
+    app.update();
+    app.update();
+    app
+}
+
+fn chunk(app: &mut App, entity: Entity) -> TerrainChunk {
+    *app.world().get::<TerrainChunk>(entity).unwrap()
+}
+
+fn heightfields(
+    app: &mut App,
+) -> Vec<(TerrainChunk, Heightfield)> {
+    let generated = std::mem::take(
+        &mut app.world_mut().resource_mut::<Generated>().0,
+    );
+    generated
+        .into_iter()
+        .map(|generated| {
+            let ChunkReadback::Heightfield(handle) =
+                generated.readback
+            else {
+                panic!("expected a heightfield");
+            };
+            let heightfield = app
+                .world()
+                .resource::<Assets<Heightfield>>()
+                .get(&handle)
+                .unwrap()
+                .clone();
+            (chunk(app, generated.entity), heightfield)
+        })
+        .collect()
+}
+
+/// The height `reference` gives the point `local` of
+/// the chunk at `coord`.
+fn expected_height(
+    terrain: &Terrain,
+    coord: IVec2,
+    local: Vec2,
+) -> f32 {
+    let world = local
+        + (coord.as_vec2() + 0.5) * terrain.chunk_size;
+    let noise = terrain.noise;
+    reference::terrain_height(
+        world / noise.scale + noise.seed,
+    ) * noise.height
+}
+
+fn grid(resolution: u32, size: f32) -> Vec<Vec2> {
+    let row = 0..=resolution;
+    row.clone()
+        .flat_map(|z| {
+            row.clone().map(move |x| {
+                (Vec2::new(x as f32, z as f32)
+                    / resolution as f32
+                    - 0.5)
+                    * size
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn every_chunk_is_read_back_as_a_heightfield() {
+    let mut app = app(Terrain {
+        readback: TerrainReadback::Heightfield,
+        ..terrain()
+    });
+    let terrain = app.world().resource::<Terrain>().clone();
+    let heightfields = heightfields(&mut app);
+
+    // the center chunk and its four neighbors
+    assert_eq!(heightfields.len(), 5);
+    for (chunk, heightfield) in heightfields {
+        assert_eq!(heightfield.resolution, 8);
+        assert_eq!(heightfield.heights.len(), 81);
+        for point in grid(8, 100.) {
+            let expected = expected_height(
+                &terrain,
+                chunk.coord,
+                point,
+            );
+            let height = heightfield.height_at(point);
+            assert!(
+                (height - expected).abs() < 1e-4,
+                "{height} != {expected} at {point} of {:?}",
+                chunk.coord
+            );
+        }
+    }
+}
+
+#[test]
+fn neighboring_chunks_share_their_edges() {
+    let mut app = app(Terrain {
+        readback: TerrainReadback::Heightfield,
+        // a finer center chunk, coarser neighbors
+        lod_distances: vec![60.],
+        ..terrain()
+    });
+    let heightfields = heightfields(&mut app);
+    let at = |coord: IVec2| {
+        heightfields
+            .iter()
+            .find(|(chunk, _)| chunk.coord == coord)
+            .unwrap()
+    };
+
+    let (center, fine) = at(IVec2::ZERO);
+    assert_eq!(center.lod, 0);
+    for (neighbor, edge) in [
+        (IVec2::X, Vec2::X),
+        (IVec2::NEG_X, Vec2::NEG_X),
+        (IVec2::Y, Vec2::Y),
+        (IVec2::NEG_Y, Vec2::NEG_Y),
+    ] {
+        let (chunk, coarse) = at(neighbor);
+        assert_eq!(chunk.lod, 1);
+        assert_eq!(coarse.resolution, 4);
+        // the coarse chunk's vertices along the shared
+        // edge are also vertices of the fine one, up to
+        // rounding in their world positions
+        for along in grid(4, 100.).into_iter().take(5) {
+            let along = Vec2::new(along.x, 0.);
+            let along = if edge.x == 0. {
+                along
+            } else {
+                along.yx()
+            };
+            let fine_side = edge * 50. + along;
+            let coarse_side = -edge * 50. + along;
+            let fine = fine.height_at(fine_side);
+            let coarse = coarse.height_at(coarse_side);
+            assert!(
+                (fine - coarse).abs() < 1e-4,
+                "{fine} != {coarse} at {fine_side} towards {neighbor}"
+            );
+        }
+    }
+}
+
+#[test]
+fn skirts_hang_below_the_border() {
+    let mut app = app(Terrain {
+        readback: TerrainReadback::Mesh,
+        skirt_depth: 3.,
+        ..terrain()
+    });
+    let generated = std::mem::take(
+        &mut app.world_mut().resource_mut::<Generated>().0,
+    );
+    assert_eq!(generated.len(), 5);
+
+    for generated in generated {
+        let ChunkReadback::Mesh(handle) =
+            generated.readback
+        else {
+            panic!("expected a mesh");
+        };
+        let meshes = app.world().resource::<Assets<Mesh>>();
+        let mesh = meshes.get(&handle).unwrap();
+        let Some(VertexAttributeValues::Float32x3(
+            positions,
+        )) = mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("expected positions");
+        };
+        // 9 by 9 vertices, then 32 around the border
+        assert_eq!(positions.len(), 81 + 32);
+        let (surface, skirt) = positions.split_at(81);
+        for &[x, y, z] in skirt {
+            let [_, top, _] = surface
+                .iter()
+                .find(|&&[sx, _, sz]| sx == x && sz == z)
+                .unwrap();
+            assert_eq!(y, top - 3.);
+        }
+    }
+}
+
+#[test]
+fn changed_noise_is_read_back_again() {
+    let mut app = app(Terrain {
+        readback: TerrainReadback::Heightfield,
+        ..terrain()
+    });
+    let before = heightfields(&mut app);
+
+    app.world_mut().resource_mut::<Terrain>().noise =
+        TerrainNoise {
+            seed: Vec2::new(4., 2.),
+            ..default()
+        };
+    app.update();
+    app.update();
+    let terrain = app.world().resource::<Terrain>().clone();
+    let after = heightfields(&mut app);
+
+    assert_eq!(after.len(), before.len());
+    let point = Vec2::new(12.5, -25.);
+    for (chunk, heightfield) in after {
+        let (_, old) = before
+            .iter()
+            .find(|(old, _)| old.coord == chunk.coord)
+            .unwrap();
+        assert_ne!(heightfield.heights, old.heights);
+        assert!(
+            (heightfield.height_at(point)
+                - expected_height(
+                    &terrain,
+                    chunk.coord,
+                    point
+                ))
+            .abs()
+                < 1e-4
+        );
+    }
+}
+
+#[test]
+fn heightfield_interpolates_between_vertices() {
+    let heightfield = Heightfield {
+        size: 2.,
+        resolution: 1,
+        heights: vec![0., 1., 2., 3.],
+    };
+    assert_eq!(
+        heightfield.height_at(Vec2::new(-1., -1.)),
+        0.
+    );
+    assert_eq!(
+        heightfield.height_at(Vec2::new(1., -1.)),
+        1.
+    );
+    assert_eq!(
+        heightfield.height_at(Vec2::new(-1., 1.)),
+        2.
+    );
+    assert_eq!(heightfield.height_at(Vec2::ZERO), 1.5);
+    // outside the chunk, the border's height
+    assert_eq!(
+        heightfield.height_at(Vec2::new(5., 5.)),
+        3.
+    );
+}
+
+#[test]
+fn normals_and_tangents_match_the_generated_surface() {
+    let mut app = app(Terrain {
+        readback: TerrainReadback::Mesh,
+        // fine enough for the triangles to follow the
+        // noise's smallest octave
+        chunk_size: 25.,
+        resolution: 128,
+        view_distance: 0,
+        ..terrain()
+    });
+    let generated = std::mem::take(
+        &mut app.world_mut().resource_mut::<Generated>().0,
+    );
+    let ChunkReadback::Mesh(handle) =
+        &generated[0].readback
+    else {
+        panic!("expected a mesh");
+    };
+    let meshes = app.world().resource::<Assets<Mesh>>();
+    let generated = meshes.get(handle).unwrap();
+
+    // the same surface, without the skirt, with normals
+    // and tangents from its triangles instead
+    let row = 129;
+    let surface = row * row;
+    let attribute = |id| {
+        let mut values =
+            generated.attribute(id).unwrap().clone();
+        match &mut values {
+            VertexAttributeValues::Float32x2(values) => {
+                values.truncate(surface)
+            }
+            VertexAttributeValues::Float32x3(values) => {
+                values.truncate(surface)
+            }
+            _ => unreachable!(),
+        }
+        values
+    };
+    let Some(Indices::U32(indices)) = generated.indices()
+    else {
+        panic!("expected u32 indices");
+    };
+    let indices = indices
+        .chunks_exact(3)
+        .filter(|triangle| {
+            triangle.iter().all(|&i| (i as usize) < surface)
+        })
+        .flatten()
+        .copied()
+        .collect();
+    let mut computed = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::MAIN_WORLD,
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        attribute(Mesh::ATTRIBUTE_POSITION.id),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        attribute(Mesh::ATTRIBUTE_UV_0.id),
+    )
+    .with_inserted_indices(Indices::U32(indices));
+    computed.compute_normals();
+    computed.generate_tangents().unwrap();
+
+    let vec3s = |mesh: &Mesh, id| match mesh.attribute(id) {
+        Some(VertexAttributeValues::Float32x3(values)) => {
+            values.iter().map(|&v| Vec3::from(v)).collect()
+        }
+        _ => Vec::new(),
+    };
+    let vec4s = |mesh: &Mesh| match mesh
+        .attribute(Mesh::ATTRIBUTE_TANGENT)
+    {
+        Some(VertexAttributeValues::Float32x4(values)) => {
+            values.iter().map(|&v| Vec4::from(v)).collect()
+        }
+        _ => Vec::<Vec4>::new(),
+    };
+    let normals: Vec<Vec3> =
+        vec3s(generated, Mesh::ATTRIBUTE_NORMAL.id);
+    let expected_normals: Vec<Vec3> =
+        vec3s(&computed, Mesh::ATTRIBUTE_NORMAL.id);
+    let tangents = vec4s(generated);
+    let expected_tangents = vec4s(&computed);
+
+    // the border's triangles are all on one side, so
+    // only the vertices inside it are compared
+    for z in 1..row - 1 {
+        for x in 1..row - 1 {
+            let i = z * row + x;
+            let (normal, tangent) =
+                (normals[i], tangents[i]);
+            assert!(normal.is_normalized());
+            assert!(tangent.xyz().is_normalized());
+            assert!(
+                normal.dot(expected_normals[i]) > 0.9995,
+                "{normal} != {} at {x}, {z}",
+                expected_normals[i]
+            );
+            assert!(
+                tangent
+                    .xyz()
+                    .dot(expected_tangents[i].xyz())
+                    > 0.9995,
+                "{tangent} != {} at {x}, {z}",
+                expected_tangents[i]
+            );
+            assert_eq!(tangent.w, expected_tangents[i].w);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! Composes the terrain generator through naga_oil, the same way the
+//! `PipelineCache` does at runtime. Runs on the CPU only.
+
+use bevy_shader_validation::{Platform, ShaderLibrary, assert_no_failures, compose_all};
+
+fn library() -> ShaderLibrary {
+    ShaderLibrary::new(env!("CARGO_MANIFEST_DIR"))
+        .with_crates(&["bevy_shader_utils"])
+        .with_dir("src/shaders")
+        .with_dir("assets")
+}
+
+/// `landscape.wgsl` with the `compute_mesh` module and the
+/// `bevy_shader_utils` noise it imports.
+#[test]
+fn landscape_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    for platform in Platform::ALL {
+        for path in ["src/shaders/compute_mesh.wgsl", "assets/landscape.wgsl"] {
+            failures.extend(compose_all(
+                &mut library,
+                path,
+                [platform.global_shader_defs()],
+            ));
+        }
+    }
+
+    assert_no_failures(&failures);
+}
@@ -1,25 +1,19 @@
 //! Loads and renders a glTF file as a scene.
 
 use bevy::{
-    pbr::wireframe::WireframePlugin,
-    prelude::*,
-    render::{
-        mesh::VertexAttributeValues,
-        render_resource::{AsBindGroup, ShaderRef},
-    },
+    mesh::VertexAttributeValues, pbr::wireframe::WireframePlugin, prelude::*,
+    render::render_resource::AsBindGroup, shader::ShaderRef,
 };
 use bevy_shader_utils::ShaderUtilsPlugin;
 use noise::{BasicMulti, NoiseFn};
-use spacecraft_noiseland::post_process::{
-    PostProcessPlugin, PostProcessSettings,
-};
+use spacecraft_noiseland::post_process::{PostProcessPlugin, PostProcessSettings};
 
 #[derive(Resource)]
 struct MyNoise(BasicMulti);
 
 fn main() {
     App::new()
-        .insert_resource(AmbientLight {
+        .insert_resource(GlobalAmbientLight {
             color: Color::WHITE,
             brightness: 1.0 / 5.0f32,
             ..default()
@@ -27,26 +21,17 @@ fn main() {
         // .insert_resource(ClearColor(
         //     Srgba::hex("071f3c").unwrap().into(),
         // ))
-        .insert_resource(ClearColor(
-            Srgba::hex("590059").unwrap().into(),
-        ))
+        .insert_resource(ClearColor(Srgba::hex("590059").unwrap().into()))
         .insert_resource(MyNoise(BasicMulti::new()))
         .add_plugins((
             DefaultPlugins,
-            WireframePlugin,
+            WireframePlugin::default(),
             ShaderUtilsPlugin,
             MaterialPlugin::<LandMaterial>::default(),
             PostProcessPlugin,
         ))
         .add_systems(Startup, setup)
-        .add_systems(
-            Update,
-            (
-                animate_light_direction,
-                movement,
-                change_position,
-            ),
-        )
+        .add_systems(Update, (animate_light_direction, movement, change_position))
         .run();
 }
 
@@ -61,12 +46,8 @@ fn setup(
 ) {
     commands.spawn((
         Camera3d::default(),
-        Transform::from_xyz(0.0, 1.5, 2.0)
-            .looking_at(Vec3::new(0.0, 1.5, 0.0), Vec3::Y),
-        PostProcessSettings {
-            intensity: 0.02,
-            ..default()
-        },
+        Transform::from_xyz(0.0, 1.5, 2.0).looking_at(Vec3::new(0.0, 1.5, 0.0), Vec3::Y),
+        PostProcessSettings::new(0.02),
         Movable,
     ));
 
@@ -77,25 +58,14 @@ fn setup(
         size: Vec2::splat(1000.0),
         subdivisions: 1000,
     });
-    if let Some(VertexAttributeValues::Float32x3(
-        positions,
-    )) = land.attribute(Mesh::ATTRIBUTE_POSITION)
+    if let Some(VertexAttributeValues::Float32x3(positions)) =
+        land.attribute(Mesh::ATTRIBUTE_POSITION)
     {
         let colors: Vec<[f32; 4]> = positions
             .iter()
-            .map(|[r, g, b]| {
-                [
-                    (1. - *r) / 2.,
-                    (1. - *g) / 2.,
-                    (1. - *b) / 2.,
-                    1.,
-                ]
-            })
+            .map(|[r, g, b]| [(1. - *r) / 2., (1. - *g) / 2., (1. - *b) / 2., 1.])
             .collect();
-        land.insert_attribute(
-            Mesh::ATTRIBUTE_COLOR,
-            colors,
-        );
+        land.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
     }
 
     commands.spawn((
@@ -108,12 +78,8 @@ fn setup(
     // .insert(Wireframe);
 
     commands.spawn((
-        SceneRoot(
-            asset_server
-                .load("craft/craft_miner.glb#Scene0"),
-        ),
-        Transform::from_xyz(-2.0 as f32, 1.0, 0.0 as f32)
-            .with_scale(Vec3::splat(0.2)),
+        SceneRoot(asset_server.load("craft/craft_miner.glb#Scene0")),
+        Transform::from_xyz(-2.0, 1.0, 0.0).with_scale(Vec3::splat(0.2)),
         Ship,
         Movable,
     ));
@@ -121,18 +87,13 @@ fn setup(
 
 fn animate_light_direction(
     time: Res<Time>,
-    mut query: Query<
-        &mut Transform,
-        With<DirectionalLight>,
-    >,
+    mut query: Query<&mut Transform, With<DirectionalLight>>,
 ) {
     for mut transform in &mut query {
         transform.rotation = Quat::from_euler(
             EulerRot::ZYX,
             0.0,
-            time.elapsed_secs() as f32
-                * std::f32::consts::TAU
-                / 10.0,
+            time.elapsed_secs() * std::f32::consts::TAU / 10.0,
             -std::f32::consts::FRAC_PI_4,
         );
     }
@@ -209,7 +170,6 @@ fn movement(
             direction.x += 1.0;
         }
 
-        transform.translation +=
-            time.delta_secs() * 2.0 * direction;
+        transform.translation += time.delta_secs() * 2.0 * direction;
     }
 }
@@ -1,4 +1,4 @@
-// This shader computes the chromatic aberration effect
+// This shader darkens the scene through a fog of war
 
 // Since post processing is a fullscreen effect, we use the fullscreen vertex shader provided by bevy.
 // This will import a vertex shader that renders a single fullscreen triangle.
@@ -25,40 +25,33 @@
 @group(0) @binding(0) var screen_texture: texture_2d<f32>;
 @group(0) @binding(1) var screen_sampler: sampler;
 struct PostProcessSettings {
-    width: u32,
-    height: u32
+    world_from_clip: mat4x4<f32>,
+    fog_min: vec2<f32>,
+    fog_size: vec2<f32>,
 }
 @group(0) @binding(2) var<uniform> settings: PostProcessSettings;
-@group(0) @binding(3) var visibility_mesh_texture: texture_2d<f32>;
-@group(0) @binding(4) var visibility_mesh_sampler: sampler;
+// red is visible now, green explored
+@group(0) @binding(3) var fog_texture: texture_2d<f32>;
+@group(0) @binding(4) var fog_sampler: sampler;
+
+// how bright explored areas out of view are
+const EXPLORED_BRIGHTNESS: f32 = 0.35;
 
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
-    // // Chromatic aberration strength
-    // let offset_strength = settings.intensity;
+    // uvs go down the screen, clip space goes up
+    let clip = vec4(in.uv.x * 2. - 1., 1. - in.uv.y * 2., 0., 1.);
+    let world = (settings.world_from_clip * clip).xy;
+    // the fog texture's rows go down from the top too
+    let fog_uv = (world - settings.fog_min) / settings.fog_size;
+    let fog = textureSample(fog_texture, fog_sampler, vec2(fog_uv.x, 1. - fog_uv.y));
+
+    // outside of the fog nothing has been explored
+    let inside = all(fog_uv >= vec2(0.)) && all(fog_uv <= vec2(1.));
+    let visible = select(0., fog.r, inside);
+    let explored = select(0., fog.g, inside);
 
-    // Sample each color channel with an arbitrary shift
-    // return vec4<f32>(
-    //     textureSample(screen_texture, texture_sampler, in.uv + vec2<f32>(offset_strength, -offset_strength)).r,
-    //     textureSample(screen_texture, texture_sampler, in.uv + vec2<f32>(-offset_strength, 0.0)).g,
-    //     textureSample(screen_texture, texture_sampler, in.uv + vec2<f32>(0.0, offset_strength)).b,
-    //     1.0
-    // );
-    // return vec4<f32>(
-    //     textureSample(screen_texture, screen_sampler, in.uv),
-    // );
-    // return vec4<f32>(
-    //     textureSample(visibility_mesh_texture, visibility_mesh_sampler, in.uv),
-    // );
-    // return 
-    // texture_load
-    // let width = view.viewport.z;
-    // let height = view.viewport.w;
-    let coord = vec2u(in.uv * vec2(f32(settings.width) / 2., f32(settings.height) / 2.));
-    let threshold = step(1. - textureLoad(visibility_mesh_texture, coord, 0).r, 0.5);
-    return mix(
-        textureSample(screen_texture, screen_sampler, in.uv),
-        vec4(1. - threshold),
-        vec4(threshold)
-    );
+    let color = textureSample(screen_texture, screen_sampler, in.uv);
+    let brightness = mix(explored * EXPLORED_BRIGHTNESS, 1., visible);
+    return vec4(color.rgb * brightness, color.a);
 }
@@ -2,7 +2,6 @@ use std::f32::consts::FRAC_PI_3;
 
 use bevy::{
     asset::RenderAssetUsages,
-    camera::{RenderTarget, visibility::RenderLayers},
     color::palettes::tailwind::{GREEN_400, RED_400, SKY_400, YELLOW_400},
     gltf::{GltfMesh, GltfPrimitive},
     input::common_conditions::input_just_pressed,
@@ -10,17 +9,13 @@ use bevy::{
     math::FloatOrd,
     mesh::{PrimitiveTopology, VertexAttributeValues},
     prelude::*,
-    render::render_resource::{Extent3d, TextureFormat},
     scene::SceneInstanceReady,
     sprite_render::{Wireframe2dConfig, Wireframe2dPlugin},
-    window::PrimaryWindow,
 };
 use bevy_skein::SkeinPlugin;
+use bevy_visibility2d::{FogOfWar, InViewGroup, Obstacle, Viewer, Visibility2dPlugin};
 use itertools::Itertools;
-use visibility_2d_mesh::{
-    Obstacle, Player, VisibilityMesh,
-    post_processing::{PostProcessPlugin, PostProcessSettings, VisibilityTexture},
-};
+use visibility_2d_mesh::post_processing::{FogTexture, PostProcessPlugin, PostProcessSettings};
 
 fn fmt_layer(_app: &mut App) -> Option<bevy::log::BoxedFmtLayer> {
     Some(Box::new(
@@ -33,38 +28,37 @@ fn fmt_layer(_app: &mut App) -> Option<bevy::log::BoxedFmtLayer> {
 
 fn main() {
     let mut app = App::new();
-    app.insert_resource(visibility_2d_mesh::RayIndex(0))
-        .add_plugins((
-            DefaultPlugins.set(bevy::log::LogPlugin {
-                fmt_layer,
-                ..default()
-            }),
-            SkeinPlugin::default(),
-            Wireframe2dPlugin::default(),
-            MeshPickingPlugin::default(),
-            visibility_2d_mesh::VisibilityMeshPlugin::default(),
-            PostProcessPlugin,
-        ))
-        .add_systems(Startup, setup)
-        .add_systems(
-            Update,
-            (
-                toggle_wireframe.run_if(input_just_pressed(KeyCode::Space)),
-                visibility_2d_mesh::set_ray_index.run_if(
-                    input_just_pressed(KeyCode::ArrowLeft)
-                        .or(input_just_pressed(KeyCode::ArrowRight)),
-                ),
-                update_visibility_texture,
-                load_gltf,
-            ),
-        )
-        .add_observer(on_drag);
+    app.add_plugins((
+        DefaultPlugins.set(bevy::log::LogPlugin {
+            fmt_layer,
+            ..default()
+        }),
+        SkeinPlugin::default(),
+        Wireframe2dPlugin::default(),
+        MeshPickingPlugin::default(),
+        Visibility2dPlugin,
+        PostProcessPlugin::default(),
+    ))
+    .add_systems(Startup, setup)
+    .add_systems(
+        Update,
+        (
+            toggle_wireframe.run_if(input_just_pressed(KeyCode::Space)),
+            update_fog_settings,
+            load_gltf,
+        ),
+    )
+    .add_observer(on_drag);
     app.run();
 }
 
 #[derive(Resource)]
 struct Level(Handle<Gltf>);
 
+/// The viewer that can be dragged around
+#[derive(Component)]
+struct Player;
+
 fn load_gltf(
     mut reader: MessageReader<AssetEvent<Gltf>>,
     gltf_assets: Res<Assets<Gltf>>,
@@ -139,7 +133,6 @@ fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut images: ResMut<Assets<Image>>,
     asset_server: Res<AssetServer>,
 ) {
     // gltf example loaded here
@@ -147,15 +140,6 @@ fn setup(
     //     asset_server.load("Untitled.gltf"),
     // ));
 
-    let image = Image::new_target_texture(
-        512,
-        512,
-        TextureFormat::bevy_default(),
-        Some(TextureFormat::bevy_default()),
-    );
-
-    let image_handle = images.add(image);
-
     commands.spawn((
         Camera2d::default(),
         // Projection::Orthographic(OrthographicProjection {
@@ -165,12 +149,9 @@ fn setup(
         //         },
         //     ..OrthographicProjection::default_2d()
         // }),
-        PostProcessSettings {
-            width: 512,
-            height: 512,
-            ..default()
-        },
-        VisibilityTexture(image_handle.clone()),
+        PostProcessSettings::default(),
+        // the fog's image is set once it's spawned
+        FogTexture::new([Handle::default()]),
     ));
 
     // let shapes = [
@@ -251,40 +232,25 @@ fn setup(
         Obstacle,
     ));
 
-    let empty_vec: Vec<[f32; 3]> = vec![];
-    let vismesh = meshes.add(
-        Mesh::new(
-            PrimitiveTopology::TriangleList,
-            RenderAssetUsages::default(),
+    // the room, a texel per 4 units, revealed by the
+    // player
+    let fog = commands
+        .spawn(
+            FogOfWar::new(
+                Rect::from_center_size(Vec2::ZERO, Vec2::new(1200., 800.)),
+                UVec2::new(300, 200),
+            )
+            .with_softness(2),
         )
-        // Add 4 vertices, each with its own position
-        // attribute (coordinate in 3D space), for
-        // each of the corners of the parallelogram.
-        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, empty_vec),
-    );
-    commands.insert_resource(VisibilityMesh(vismesh.clone()));
-
-    commands.spawn((
-        Camera2d::default(),
-        Camera {
-            order: -1,
-            clear_color: Color::WHITE.into(),
-            ..default()
-        },
-        RenderTarget::Image(image_handle.into()),
-        RenderLayers::layer(1),
-    ));
+        .id();
 
     commands.spawn((
         Mesh2d(meshes.add(Circle::new(10.0))),
         MeshMaterial2d(materials.add(Color::from(SKY_400))),
         Transform::from_xyz(0., -40., 1.),
         Player,
-        children!((
-            Mesh2d(vismesh),
-            MeshMaterial2d(materials.add(Color::BLACK),),
-            RenderLayers::layer(1),
-        )),
+        Viewer::default(),
+        InViewGroup(fog),
     ));
     // commands.spawn((
     //     Mesh2d(vismesh),
@@ -367,24 +333,24 @@ fn build_rhombus(size: Vec2) -> Mesh {
     .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
 }
 
-fn update_visibility_texture(
-    mut query: Query<(&mut VisibilityTexture, &Camera, &mut PostProcessSettings)>,
-    window: Single<&Window, With<PrimaryWindow>>,
-    mut images: ResMut<Assets<Image>>,
+/// Points the post processing at the fog, and tells it
+/// where the camera is looking
+fn update_fog_settings(
+    mut cameras: Query<(
+        &mut FogTexture,
+        &mut PostProcessSettings,
+        &Camera,
+        &GlobalTransform,
+    )>,
+    fog: Single<&FogOfWar>,
 ) {
-    let size = window.physical_size();
-    for (texture, camera, mut settings) in &mut query {
-        let Some(image) = images.get_mut(&texture.0) else {
-            continue;
-        };
-
-        settings.width = size.x;
-        settings.height = size.y;
-
-        image.resize(Extent3d {
-            width: size.x / 2, // TODO: this is the scale_factor, but how is this supposed to be handled?
-            height: size.y / 2,
-            depth_or_array_layers: 1,
-        });
+    let bounds = fog.mask.bounds();
+    for (mut texture, mut settings, camera, transform) in &mut cameras {
+        if texture.images[0] != fog.image {
+            texture.images[0] = fog.image.clone();
+        }
+        settings.world_from_clip = transform.to_matrix() * camera.clip_from_view().inverse();
+        settings.fog_min = bounds.min;
+        settings.fog_size = bounds.size();
     }
 }
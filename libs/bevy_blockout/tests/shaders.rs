@@ -0,0 +1,33 @@
+//! Composes the blockout material through naga_oil, the same way the
+//! `PipelineCache` does at runtime, for both of the passes it's drawn in
+//! and every `StandardMaterial` key it extends. Runs on the CPU only.
+
+use bevy_shader_validation::{
+    BEVY_SHADER_CRATES, ShaderLibrary, assert_no_failures, compose_all,
+    defs::{self, PBR_TEXTURE_FEATURES, Pass, Platform, STANDARD_MATERIAL_KEYS},
+};
+
+#[test]
+fn blockout_composes() {
+    let mut library = ShaderLibrary::new(env!("CARGO_MANIFEST_DIR"))
+        .with_crates(&BEVY_SHADER_CRATES)
+        .with_dir("src");
+    let mut failures = vec![];
+
+    let mut optional = STANDARD_MATERIAL_KEYS.to_vec();
+    optional.push("MAY_DISCARD");
+    // `fragment_shader` and `deferred_fragment_shader` are the same file
+    for pass in [Pass::Forward, Pass::Deferred] {
+        for platform in Platform::ALL {
+            let mut base = defs::mesh_pipeline(pass, platform);
+            base.extend(PBR_TEXTURE_FEATURES.map(Into::into));
+            failures.extend(compose_all(
+                &mut library,
+                "src/materials/blockout.wgsl",
+                defs::permutations(&base, &optional),
+            ));
+        }
+    }
+
+    assert_no_failures(&failures);
+}
@@ -0,0 +1,564 @@
+//! Fullscreen post-processing effects.
+//!
+//! An effect is a settings component implementing
+//! [`FullscreenEffect`] plus a WGSL fragment shader.
+//! [`FullscreenEffectPlugin`] extracts the settings of
+//! every camera that has them, uploads them as a
+//! uniform and runs the shader over the camera's
+//! output after tonemapping, in 2d and 3d.
+//! A [`PostProcessStack`] orders a camera's effects.
+//!
+//! ```ignore
+//! #[derive(Component, Clone, Copy, ShaderType)]
+//! struct ChromaticAberration {
+//!     intensity: f32,
+//! }
+//!
+//! impl FullscreenEffect for ChromaticAberration {
+//!     fn fragment_shader() -> ShaderRef {
+//!         "shaders/chromatic_aberration.wgsl".into()
+//!     }
+//! }
+//!
+//! App::new().add_plugins((
+//!     DefaultPlugins,
+//!     FullscreenEffectPlugin::<ChromaticAberration>::default(),
+//! ));
+//! ```
+//!
+//! The shader gets the fullscreen triangle's uvs and
+//! these bindings:
+//!
+//! ```wgsl
+//! #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+//!
+//! @group(0) @binding(0) var screen_texture: texture_2d<f32>;
+//! @group(0) @binding(1) var screen_sampler: sampler;
+//! @group(0) @binding(2) var<uniform> settings: ChromaticAberration;
+//! // one texture and sampler per `FullscreenEffect::TEXTURES`
+//! @group(0) @binding(3) var extra_texture: texture_2d<f32>;
+//! @group(0) @binding(4) var extra_sampler: sampler;
+//!
+//! @fragment
+//! fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> { .. }
+//! ```
+use std::{
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use bevy::{
+    core_pipeline::{
+        FullscreenShader,
+        blit::BlitPipeline,
+        core_2d::graph::{Core2d, Node2d},
+        core_3d::graph::{Core3d, Node3d},
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        Extract, ExtractSchedule, Render, RenderApp, RenderSystems,
+        extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
+        render_asset::RenderAssets,
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            encase::internal::WriteInto,
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        sync_world::RenderEntity,
+        texture::GpuImage,
+        view::ViewTarget,
+    },
+    shader::ShaderRef,
+};
+
+mod stack;
+
+pub use stack::{PostProcessStack, StackEntry, StackLabel};
+use stack::{PostProcessStackPlugin, StackBlitPipeline, StackEffects, blend_constant};
+
+/// A fullscreen effect's settings. Add them to a
+/// camera to run the effect on it.
+///
+/// The settings are the shader's uniform, so they need
+/// to follow WGSL's layout rules, including the 16 byte
+/// alignment on WebGL2.
+pub trait FullscreenEffect: Component + ShaderType + WriteInto + Clone {
+    /// How many images from [`EffectTextures`] the
+    /// shader samples, after the settings.
+    const TEXTURES: usize = 0;
+
+    /// The fragment shader, with a `fragment` entry
+    /// point.
+    fn fragment_shader() -> ShaderRef;
+}
+
+/// The images a [`FullscreenEffect`] samples besides
+/// the screen, in binding order. Add them to the
+/// camera next to the effect.
+#[derive(Component)]
+pub struct EffectTextures<T> {
+    pub images: Vec<Handle<Image>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> EffectTextures<T> {
+    pub fn new(images: impl IntoIterator<Item = Handle<Image>>) -> Self {
+        Self {
+            images: images.into_iter().collect(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for EffectTextures<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.images.iter().cloned())
+    }
+}
+
+/// Runs the [`FullscreenEffect`] `T` on every camera
+/// with a `T`.
+pub struct FullscreenEffectPlugin<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for FullscreenEffectPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: FullscreenEffect> Plugin for FullscreenEffectPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(UniformComponentPlugin::<T>::default());
+        if !app.is_plugin_added::<PostProcessStackPlugin>() {
+            app.add_plugins(PostProcessStackPlugin);
+        }
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .world_mut()
+            .resource_mut::<StackEffects>()
+            .register::<T>();
+        render_app
+            .init_resource::<SpecializedRenderPipelines<FullscreenEffectPipeline<T>>>()
+            .add_systems(ExtractSchedule, extract_effects::<T>)
+            .add_systems(
+                Render,
+                prepare_effect_pipelines::<T>.in_set(RenderSystems::Prepare),
+            )
+            .add_render_graph_node::<ViewNodeRunner<FullscreenEffectNode<T>>>(
+                Core3d,
+                FullscreenEffectLabel::<T>::default(),
+            )
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    FullscreenEffectLabel::<T>::default(),
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<FullscreenEffectNode<T>>>(
+                Core2d,
+                FullscreenEffectLabel::<T>::default(),
+            )
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::Tonemapping,
+                    FullscreenEffectLabel::<T>::default(),
+                    Node2d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<FullscreenEffectPipeline<T>>();
+    }
+}
+
+/// The render graph node of the [`FullscreenEffect`]
+/// `T`, in both `Core2d` and `Core3d`. Effects are
+/// unordered among each other, use a
+/// [`PostProcessStack`] to order them per camera.
+#[derive(RenderLabel)]
+pub struct FullscreenEffectLabel<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for FullscreenEffectLabel<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Clone for FullscreenEffectLabel<T> {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl<T> PartialEq for FullscreenEffectLabel<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for FullscreenEffectLabel<T> {}
+
+impl<T> Hash for FullscreenEffectLabel<T> {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl<T> Debug for FullscreenEffectLabel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FullscreenEffectLabel<{}>", std::any::type_name::<T>())
+    }
+}
+
+fn extract_effects<T: FullscreenEffect>(
+    mut commands: Commands,
+    cameras: Extract<
+        Query<(
+            RenderEntity,
+            &Camera,
+            Option<&T>,
+            Option<&EffectTextures<T>>,
+        )>,
+    >,
+) {
+    for (entity, camera, effect, textures) in &cameras {
+        let mut entity = commands.entity(entity);
+        match effect.filter(|_| camera.is_active) {
+            Some(effect) => entity.insert(effect.clone()),
+            None => entity.remove::<T>(),
+        };
+        match textures {
+            Some(textures) => entity.insert(textures.clone()),
+            None => entity.remove::<EffectTextures<T>>(),
+        };
+    }
+}
+
+struct FullscreenEffectNode<T>(PhantomData<fn() -> T>);
+
+impl<T> FromWorld for FullscreenEffectNode<T> {
+    fn from_world(_world: &mut World) -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: FullscreenEffect> ViewNode for FullscreenEffectNode<T> {
+    // cameras with a stack run their effects in the
+    // stack's node instead
+    type ViewQuery = (Entity, Has<PostProcessStack>);
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view, has_stack): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        if !has_stack {
+            draw_effect::<T>(world, render_context, view, 1.);
+        }
+        Ok(())
+    }
+}
+
+/// Runs the effect `T` on `view`, if it has one. Below
+/// an `intensity` of 1 the effect is blended over the
+/// unaffected image.
+fn draw_effect<T: FullscreenEffect>(
+    world: &World,
+    render_context: &mut RenderContext,
+    view: Entity,
+    intensity: f32,
+) {
+    let view = world.entity(view);
+    let (Some(view_target), Some(settings_index), Some(pipeline_ids)) = (
+        view.get::<ViewTarget>(),
+        view.get::<DynamicUniformIndex<T>>(),
+        view.get::<EffectPipelineId<T>>(),
+    ) else {
+        return;
+    };
+    if !view.contains::<T>() {
+        return;
+    }
+
+    let effect_pipeline = world.resource::<FullscreenEffectPipeline<T>>();
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let blended = intensity < 1.;
+    let pipeline_id = if blended {
+        pipeline_ids.blended
+    } else {
+        Some(pipeline_ids.opaque)
+    };
+    let Some(pipeline) = pipeline_id.and_then(|id| pipeline_cache.get_render_pipeline(id)) else {
+        return;
+    };
+    let Some(settings_binding) = world.resource::<ComponentUniforms<T>>().binding() else {
+        return;
+    };
+
+    // every texture has to be loaded before the
+    // effect can run
+    let images = view
+        .get::<EffectTextures<T>>()
+        .map_or(&[][..], |textures| &textures.images);
+    if images.len() != T::TEXTURES {
+        warn_once!(
+            "{} samples {} EffectTextures, but the camera has {}",
+            std::any::type_name::<T>(),
+            T::TEXTURES,
+            images.len()
+        );
+        return;
+    }
+    let gpu_images = world.resource::<RenderAssets<GpuImage>>();
+    let Some(images) = images
+        .iter()
+        .map(|image| gpu_images.get(image))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return;
+    };
+
+    // blending needs the unaffected image in the
+    // destination first
+    let blit = if blended {
+        let Some(blit) = view
+            .get::<StackBlitPipeline>()
+            .and_then(|blit| pipeline_cache.get_render_pipeline(blit.0))
+        else {
+            return;
+        };
+        Some(blit)
+    } else {
+        None
+    };
+
+    // The source and destination flip with every
+    // post process write, so the bind group is
+    // created while the node runs.
+    let post_process = view_target.post_process_write();
+
+    if let Some(blit) = blit {
+        let blit_pipeline = world.resource::<BlitPipeline>();
+        let bind_group = blit_pipeline.create_bind_group(
+            render_context.render_device(),
+            post_process.source,
+            pipeline_cache,
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("fullscreen_effect_blit_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(blit);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    let mut entries = DynamicBindGroupEntries::sequential((
+        post_process.source,
+        &effect_pipeline.sampler,
+        settings_binding,
+    ));
+    for image in &images {
+        entries = entries.extend_sequential((&image.texture_view, &image.sampler));
+    }
+    let bind_group = render_context.render_device().create_bind_group(
+        "fullscreen_effect_bind_group",
+        &pipeline_cache.get_bind_group_layout(&effect_pipeline.layout),
+        &entries,
+    );
+
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("fullscreen_effect_pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: post_process.destination,
+            depth_slice: None,
+            resolve_target: None,
+            ops: Operations {
+                // keep the blitted image to blend over
+                load: if blended {
+                    LoadOp::Load
+                } else {
+                    LoadOp::Clear(default())
+                },
+                store: StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    render_pass.set_render_pipeline(pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+    if blended {
+        render_pass.set_blend_constant(blend_constant(intensity));
+    }
+    render_pass.draw(0..3, 0..1);
+}
+
+#[derive(Resource)]
+struct FullscreenEffectPipeline<T> {
+    layout: BindGroupLayoutDescriptor,
+    sampler: Sampler,
+    shader: Handle<Shader>,
+    fullscreen_shader: FullscreenShader,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: FullscreenEffect> FromWorld for FullscreenEffectPipeline<T> {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let mut entries = DynamicBindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                // The screen texture
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+                // The effect's settings
+                uniform_buffer::<T>(true),
+            ),
+        );
+        for _ in 0..T::TEXTURES {
+            entries = entries.extend_sequential((
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+            ));
+        }
+        let layout =
+            BindGroupLayoutDescriptor::new("fullscreen_effect_bind_group_layout", &entries);
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        let shader = match T::fragment_shader() {
+            ShaderRef::Handle(handle) => handle,
+            ShaderRef::Path(path) => world.load_asset(path),
+            ShaderRef::Default => panic!(
+                "{} has no default shader, FullscreenEffect::fragment_shader has to name one",
+                std::any::type_name::<T>()
+            ),
+        };
+
+        let fullscreen_shader = world
+            .get_resource::<FullscreenShader>()
+            .expect("FullscreenShader Resource is required for fullscreen effects")
+            .clone();
+
+        Self {
+            layout,
+            sampler,
+            shader,
+            fullscreen_shader,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Which view target an effect's pipeline writes to,
+/// and how.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct FullscreenEffectPipelineKey {
+    /// The view target's format, HDR or not
+    format: TextureFormat,
+    /// Blends the effect over the destination by the
+    /// blend constant, for intensities below 1.
+    blended: bool,
+}
+
+impl<T: FullscreenEffect> SpecializedRenderPipeline for FullscreenEffectPipeline<T> {
+    type Key = FullscreenEffectPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let blend = key.blended.then(|| {
+            let component = BlendComponent {
+                src_factor: BlendFactor::Constant,
+                dst_factor: BlendFactor::OneMinusConstant,
+                operation: BlendOperation::Add,
+            };
+            BlendState {
+                color: component,
+                alpha: component,
+            }
+        });
+        RenderPipelineDescriptor {
+            label: Some("fullscreen_effect_pipeline".into()),
+            layout: vec![self.layout.clone()],
+            vertex: self.fullscreen_shader.to_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                entry_point: Some("fragment".into()),
+                targets: vec![Some(ColorTargetState {
+                    format: key.format,
+                    blend,
+                    write_mask: ColorWrites::ALL,
+                })],
+                ..default()
+            }),
+            ..default()
+        }
+    }
+}
+
+/// The effect's pipelines specialized for a view.
+#[derive(Component)]
+struct EffectPipelineId<T: FullscreenEffect> {
+    opaque: CachedRenderPipelineId,
+    /// Only for views with a [`PostProcessStack`] that
+    /// runs the effect below full intensity
+    blended: Option<CachedRenderPipelineId>,
+    marker: PhantomData<fn() -> T>,
+}
+
+fn prepare_effect_pipelines<T: FullscreenEffect>(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<FullscreenEffectPipeline<T>>>,
+    pipeline: Res<FullscreenEffectPipeline<T>>,
+    views: Query<(Entity, &ViewTarget, Option<&PostProcessStack>), With<T>>,
+) {
+    for (entity, view_target, stack) in &views {
+        let format = view_target.main_texture_format();
+        let mut specialize = |blended| {
+            pipelines.specialize(
+                &pipeline_cache,
+                &pipeline,
+                FullscreenEffectPipelineKey { format, blended },
+            )
+        };
+        let opaque = specialize(false);
+        let blended = stack
+            .and_then(PostProcessStack::get::<T>)
+            .is_some_and(StackEntry::is_blended)
+            .then(|| specialize(true));
+        commands.entity(entity).insert(EffectPipelineId::<T> {
+            opaque,
+            blended,
+            marker: PhantomData,
+        });
+    }
+}
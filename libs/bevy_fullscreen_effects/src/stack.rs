@@ -0,0 +1,384 @@
+use std::any::{TypeId, type_name};
+
+use bevy::{
+    core_pipeline::{
+        blit::{BlitPipeline, BlitPipelineKey},
+        core_2d::graph::{Core2d, Node2d},
+        core_3d::graph::{Core3d, Node3d},
+    },
+    ecs::query::QueryItem,
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSystems,
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{CachedRenderPipelineId, PipelineCache, SpecializedRenderPipelines},
+        renderer::RenderContext,
+        view::ViewTarget,
+    },
+};
+
+use crate::{FullscreenEffect, draw_effect};
+
+/// Runs a camera's [`FullscreenEffect`]s one after the
+/// other, in the order of its entries. Each effect reads
+/// what the one before it wrote.
+///
+/// Only the effects listed here run on the camera, and
+/// only while they're enabled. Their settings stay on
+/// the camera as usual.
+///
+/// ```ignore
+/// commands.spawn((
+///     Camera3d::default(),
+///     Vignette::default(),
+///     ChromaticAberration::default(),
+///     PostProcessStack::default()
+///         .with::<ChromaticAberration>()
+///         .with::<Vignette>(),
+/// ));
+/// ```
+#[derive(Component, Clone, Debug, Default, ExtractComponent)]
+pub struct PostProcessStack {
+    pub entries: Vec<StackEntry>,
+}
+
+impl PostProcessStack {
+    /// Appends the effect `T`, enabled at full
+    /// intensity.
+    pub fn with<T: FullscreenEffect>(mut self) -> Self {
+        self.entries.push(StackEntry::new::<T>());
+        self
+    }
+
+    pub fn get<T: FullscreenEffect>(&self) -> Option<&StackEntry> {
+        self.entries.iter().find(|entry| entry.is::<T>())
+    }
+
+    pub fn get_mut<T: FullscreenEffect>(&mut self) -> Option<&mut StackEntry> {
+        self.entries.iter_mut().find(|entry| entry.is::<T>())
+    }
+
+    /// Where the effect `T` runs, 0 being first.
+    pub fn position<T: FullscreenEffect>(&self) -> Option<usize> {
+        self.entries.iter().position(StackEntry::is::<T>)
+    }
+
+    /// Moves the effect `T` to `index`, shifting the
+    /// effects in between. Returns whether the stack
+    /// has a `T`.
+    pub fn move_to<T: FullscreenEffect>(&mut self, index: usize) -> bool {
+        let Some(position) = self.position::<T>() else {
+            return false;
+        };
+        let entry = self.entries.remove(position);
+        let index = index.min(self.entries.len());
+        self.entries.insert(index, entry);
+        true
+    }
+
+    /// The effects that run, in order, and the intensity
+    /// each runs at.
+    pub(crate) fn running(&self) -> impl Iterator<Item = (&StackEntry, f32)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| Some((entry, entry.running_intensity()?)))
+    }
+}
+
+/// One effect of a [`PostProcessStack`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StackEntry {
+    effect: TypeId,
+    name: &'static str,
+    pub enabled: bool,
+    /// How much of the effect is blended over the image
+    /// it reads, from 0 to 1. Below 1 the effect costs
+    /// an extra copy of the screen.
+    pub intensity: f32,
+}
+
+impl StackEntry {
+    pub fn new<T: FullscreenEffect>() -> Self {
+        Self {
+            effect: TypeId::of::<T>(),
+            name: type_name::<T>(),
+            enabled: true,
+            intensity: 1.,
+        }
+    }
+
+    pub fn is<T: FullscreenEffect>(&self) -> bool {
+        self.effect == TypeId::of::<T>()
+    }
+
+    /// The type name of the effect
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The intensity the effect runs at, up to 1, or
+    /// `None` while it's disabled or at 0.
+    pub(crate) fn running_intensity(&self) -> Option<f32> {
+        (self.enabled && self.intensity > 0.).then(|| self.intensity.min(1.))
+    }
+
+    /// Whether the effect runs blended over the image it
+    /// reads, rather than replacing it.
+    pub(crate) fn is_blended(&self) -> bool {
+        self.running_intensity()
+            .is_some_and(|intensity| intensity < 1.)
+    }
+}
+
+/// The blend constant an effect running at `intensity`
+/// is drawn with, mixing `intensity` of it with the rest
+/// of the image it reads.
+pub(crate) fn blend_constant(intensity: f32) -> LinearRgba {
+    LinearRgba::gray(intensity).with_alpha(intensity)
+}
+
+/// Runs [`PostProcessStack`]s. Added by the first
+/// [`FullscreenEffectPlugin`](crate::FullscreenEffectPlugin).
+pub(crate) struct PostProcessStackPlugin;
+
+impl Plugin for PostProcessStackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ExtractComponentPlugin::<PostProcessStack>::default());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<StackEffects>()
+            .add_systems(
+                Render,
+                prepare_stack_blit_pipelines.in_set(RenderSystems::Prepare),
+            )
+            .add_render_graph_node::<ViewNodeRunner<StackNode>>(Core3d, StackLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    StackLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<StackNode>>(Core2d, StackLabel)
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::Tonemapping,
+                    StackLabel,
+                    Node2d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+}
+
+/// The render graph node running every
+/// [`PostProcessStack`], in both `Core2d` and `Core3d`.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct StackLabel;
+
+/// How to draw each registered effect, by type.
+#[derive(Resource, Default)]
+pub(crate) struct StackEffects(HashMap<TypeId, fn(&World, &mut RenderContext, Entity, f32)>);
+
+impl StackEffects {
+    pub(crate) fn register<T: FullscreenEffect>(&mut self) {
+        self.0.insert(TypeId::of::<T>(), draw_effect::<T>);
+    }
+}
+
+#[derive(Default)]
+struct StackNode;
+
+impl ViewNode for StackNode {
+    type ViewQuery = (Entity, &'static PostProcessStack);
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view, stack): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let effects = world.resource::<StackEffects>();
+        for (entry, intensity) in stack.running() {
+            let Some(draw) = effects.0.get(&entry.effect) else {
+                warn_once!(
+                    "{} is in a PostProcessStack, but its FullscreenEffectPlugin wasn't added",
+                    entry.name
+                );
+                continue;
+            };
+            draw(world, render_context, view, intensity);
+        }
+        Ok(())
+    }
+}
+
+/// Copies the screen under effects blended at less
+/// than full intensity.
+#[derive(Component)]
+pub(crate) struct StackBlitPipeline(pub(crate) CachedRenderPipelineId);
+
+fn prepare_stack_blit_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<BlitPipeline>>,
+    blit_pipeline: Res<BlitPipeline>,
+    views: Query<(Entity, &ViewTarget, &PostProcessStack)>,
+) {
+    for (entity, view_target, stack) in &views {
+        if !stack.entries.iter().any(StackEntry::is_blended) {
+            commands.entity(entity).remove::<StackBlitPipeline>();
+            continue;
+        }
+        let id = pipelines.specialize(
+            &pipeline_cache,
+            &blit_pipeline,
+            BlitPipelineKey {
+                texture_format: view_target.main_texture_format(),
+                blend_state: None,
+                samples: 1,
+            },
+        );
+        commands.entity(entity).insert(StackBlitPipeline(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{render::render_resource::ShaderType, shader::ShaderRef};
+
+    use super::*;
+
+    #[derive(Component, Clone, ShaderType)]
+    struct Vignette {
+        radius: f32,
+    }
+
+    impl FullscreenEffect for Vignette {
+        fn fragment_shader() -> ShaderRef {
+            "vignette.wgsl".into()
+        }
+    }
+
+    #[derive(Component, Clone, ShaderType)]
+    struct Grain {
+        amount: f32,
+    }
+
+    impl FullscreenEffect for Grain {
+        fn fragment_shader() -> ShaderRef {
+            "grain.wgsl".into()
+        }
+    }
+
+    #[derive(Component, Clone, ShaderType)]
+    struct Bloom {
+        threshold: f32,
+    }
+
+    impl FullscreenEffect for Bloom {
+        fn fragment_shader() -> ShaderRef {
+            "bloom.wgsl".into()
+        }
+    }
+
+    fn stack() -> PostProcessStack {
+        PostProcessStack::default()
+            .with::<Vignette>()
+            .with::<Grain>()
+            .with::<Bloom>()
+    }
+
+    fn order(stack: &PostProcessStack) -> [Option<usize>; 3] {
+        [
+            stack.position::<Vignette>(),
+            stack.position::<Grain>(),
+            stack.position::<Bloom>(),
+        ]
+    }
+
+    #[test]
+    fn effects_run_in_the_order_they_were_added() {
+        let stack = stack();
+
+        assert_eq!(order(&stack), [Some(0), Some(1), Some(2)]);
+        let vignette = stack.get::<Vignette>().unwrap();
+        assert!(vignette.is::<Vignette>() && !vignette.is::<Grain>());
+        assert!(vignette.name().ends_with("Vignette"));
+        assert!(vignette.enabled);
+        assert_eq!(vignette.intensity, 1.);
+        assert_eq!(PostProcessStack::default().get::<Vignette>(), None);
+    }
+
+    #[test]
+    fn moved_effects_shift_the_ones_in_between() {
+        let mut stack = stack();
+
+        assert!(stack.move_to::<Bloom>(0));
+        assert_eq!(order(&stack), [Some(1), Some(2), Some(0)]);
+        assert!(stack.move_to::<Bloom>(1));
+        assert_eq!(order(&stack), [Some(0), Some(2), Some(1)]);
+        // past the end is the end
+        assert!(stack.move_to::<Vignette>(10));
+        assert_eq!(order(&stack), [Some(2), Some(1), Some(0)]);
+
+        let mut partial = PostProcessStack::default().with::<Grain>();
+        assert!(!partial.move_to::<Bloom>(0));
+        assert_eq!(partial.entries.len(), 1);
+    }
+
+    #[test]
+    fn disabled_and_faded_out_effects_dont_run() {
+        let mut stack = stack();
+        stack.get_mut::<Vignette>().unwrap().enabled = false;
+        stack.get_mut::<Grain>().unwrap().intensity = 0.;
+        stack.get_mut::<Bloom>().unwrap().intensity = 2.;
+
+        let running: Vec<_> = stack
+            .running()
+            .map(|(entry, intensity)| (entry.name(), intensity))
+            .collect();
+        assert_eq!(running, [(type_name::<Bloom>(), 1.)]);
+        // the order follows the entries after a move
+        stack.get_mut::<Grain>().unwrap().intensity = 0.5;
+        stack.move_to::<Grain>(2);
+        let running: Vec<_> = stack.running().map(|(_, intensity)| intensity).collect();
+        assert_eq!(running, [1., 0.5]);
+
+        stack.get_mut::<Grain>().unwrap().intensity = -1.;
+        assert_eq!(stack.get::<Grain>().unwrap().running_intensity(), None);
+        stack.get_mut::<Grain>().unwrap().intensity = f32::NAN;
+        assert_eq!(stack.get::<Grain>().unwrap().running_intensity(), None);
+    }
+
+    #[test]
+    fn only_partial_intensities_blend() {
+        let mut entry = StackEntry::new::<Vignette>();
+        assert!(!entry.is_blended());
+        entry.intensity = 0.25;
+        assert!(entry.is_blended());
+        entry.enabled = false;
+        assert!(!entry.is_blended());
+        entry.enabled = true;
+        entry.intensity = 0.;
+        assert!(!entry.is_blended());
+        entry.intensity = 3.;
+        assert!(!entry.is_blended());
+
+        assert_eq!(
+            blend_constant(0.25),
+            LinearRgba::new(0.25, 0.25, 0.25, 0.25)
+        );
+        assert_eq!(blend_constant(1.), LinearRgba::WHITE);
+    }
+}
@@ -0,0 +1,98 @@
+//! One LayeredMaterial drawn with a different layer per instance
+
+use bevy::{image::ImageLoaderSettings, prelude::*};
+use bevy_color::palettes::tailwind::*;
+use bevy_layered_materials::{
+    LayerWeights, LayeredMaterial, LayeredMaterialsPlugin, MaterialLayer,
+};
+use bevy_math::Affine2;
+
+fn main() {
+    App::new()
+        .insert_resource(ClearColor(SKY_800.into()))
+        .add_plugins((DefaultPlugins, LayeredMaterialsPlugin))
+        .add_systems(Startup, setup)
+        .add_systems(Update, blend_layers)
+        .run();
+}
+
+fn blend_layers(mut query: Query<&mut LayerWeights>, time: Res<Time>) {
+    for mut weights in &mut query {
+        weights.blend = time.elapsed_secs().sin() * 0.5 + 0.5;
+    }
+}
+
+fn repeat(settings: &mut ImageLoaderSettings) {
+    let sampler = settings.sampler.get_or_init_descriptor();
+    sampler.address_mode_u = bevy::image::ImageAddressMode::Repeat;
+    sampler.address_mode_v = bevy::image::ImageAddressMode::Repeat;
+    sampler.address_mode_w = bevy::image::ImageAddressMode::Repeat;
+}
+
+/// set up a simple 3D scene
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LayeredMaterial>>,
+    mut materials_std: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    // ground
+    commands.spawn((
+        Mesh3d(meshes.add(Plane3d::default().mesh().size(12.0, 6.0))),
+        MeshMaterial3d(materials_std.add(Color::WHITE)),
+    ));
+
+    let mut cube = Cuboid::new(1.5, 1.5, 1.5).mesh().build();
+    // tangents are required for normals to affect lighting!
+    cube.generate_tangents().unwrap();
+    let cube = meshes.add(cube);
+
+    // every cube shares this material, so they're drawn in one batch
+    let material = materials.add(LayeredMaterial {
+        base_color_texture: Some(asset_server.load_with_settings(
+            "base_color.ktx2",
+            |settings: &mut ImageLoaderSettings| {
+                settings.is_srgb = true;
+                repeat(settings);
+            },
+        )),
+        normal_map_texture: Some(asset_server.load_with_settings("normal_map.ktx2", repeat)),
+        uv_transform: Affine2::from_scale(Vec2::splat(1. / 2.)),
+        ..default()
+    });
+
+    for layer in 0..3 {
+        commands.spawn((
+            Mesh3d(cube.clone()),
+            MeshMaterial3d(material.clone()),
+            MaterialLayer(layer),
+            Transform::from_xyz(layer as f32 * 2.0 - 3.0, 0.75, 0.0),
+        ));
+    }
+    // fades between the first two layers
+    commands.spawn((
+        Mesh3d(cube),
+        MeshMaterial3d(material),
+        LayerWeights {
+            first: 0,
+            second: 1,
+            blend: 0.0,
+        },
+        Transform::from_xyz(3.0, 0.75, 0.0),
+    ));
+
+    // light
+    commands.spawn((
+        PointLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(4.0, 8.0, 4.0),
+    ));
+    // camera
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
@@ -0,0 +1,327 @@
+use bevy_asset::{Assets, Handle};
+use bevy_image::Image;
+use bevy_pbr::StandardMaterial;
+use bevy_render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
+use thiserror::Error;
+
+use crate::{LayeredMaterial, StochasticTextures, UvChannel};
+
+impl From<bevy_pbr::UvChannel> for UvChannel {
+    fn from(channel: bevy_pbr::UvChannel) -> Self {
+        match channel {
+            bevy_pbr::UvChannel::Uv0 => UvChannel::Uv0,
+            bevy_pbr::UvChannel::Uv1 => UvChannel::Uv1,
+        }
+    }
+}
+
+/// Copies every field of a [`StandardMaterial`] over to a [`LayeredMaterial`].
+///
+/// Texture handles are moved as-is, which means the images they point at
+/// still have to be viewable as 2d array textures. Use
+/// [`LayeredMaterial::from_standard_material`] to wrap regular 2d textures
+/// into one-layer arrays automatically.
+impl From<StandardMaterial> for LayeredMaterial {
+    fn from(material: StandardMaterial) -> Self {
+        LayeredMaterial {
+            base_color: material.base_color,
+            base_color_channel: material.base_color_channel.into(),
+            base_color_texture: material.base_color_texture,
+            emissive: material.emissive,
+            emissive_exposure_weight: material.emissive_exposure_weight,
+            emissive_channel: material.emissive_channel.into(),
+            emissive_texture: material.emissive_texture,
+            perceptual_roughness: material.perceptual_roughness,
+            metallic: material.metallic,
+            metallic_roughness_channel: material.metallic_roughness_channel.into(),
+            metallic_roughness_texture: material.metallic_roughness_texture,
+            reflectance: material.reflectance,
+            specular_tint: material.specular_tint,
+            diffuse_transmission: material.diffuse_transmission,
+            #[cfg(feature = "pbr_transmission_textures")]
+            diffuse_transmission_channel: material.diffuse_transmission_channel.into(),
+            #[cfg(feature = "pbr_transmission_textures")]
+            diffuse_transmission_texture: material.diffuse_transmission_texture,
+            specular_transmission: material.specular_transmission,
+            #[cfg(feature = "pbr_transmission_textures")]
+            specular_transmission_channel: material.specular_transmission_channel.into(),
+            #[cfg(feature = "pbr_transmission_textures")]
+            specular_transmission_texture: material.specular_transmission_texture,
+            thickness: material.thickness,
+            #[cfg(feature = "pbr_transmission_textures")]
+            thickness_channel: material.thickness_channel.into(),
+            #[cfg(feature = "pbr_transmission_textures")]
+            thickness_texture: material.thickness_texture,
+            ior: material.ior,
+            attenuation_distance: material.attenuation_distance,
+            attenuation_color: material.attenuation_color,
+            normal_map_channel: material.normal_map_channel.into(),
+            normal_map_texture: material.normal_map_texture,
+            flip_normal_map_y: material.flip_normal_map_y,
+            occlusion_channel: material.occlusion_channel.into(),
+            occlusion_texture: material.occlusion_texture,
+            #[cfg(feature = "pbr_specular_textures")]
+            specular_channel: material.specular_channel.into(),
+            #[cfg(feature = "pbr_specular_textures")]
+            specular_texture: material.specular_texture,
+            #[cfg(feature = "pbr_specular_textures")]
+            specular_tint_channel: material.specular_tint_channel.into(),
+            #[cfg(feature = "pbr_specular_textures")]
+            specular_tint_texture: material.specular_tint_texture,
+            clearcoat: material.clearcoat,
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            clearcoat_channel: material.clearcoat_channel.into(),
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            clearcoat_texture: material.clearcoat_texture,
+            clearcoat_perceptual_roughness: material.clearcoat_perceptual_roughness,
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            clearcoat_roughness_channel: material.clearcoat_roughness_channel.into(),
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            clearcoat_roughness_texture: material.clearcoat_roughness_texture,
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            clearcoat_normal_channel: material.clearcoat_normal_channel.into(),
+            #[cfg(feature = "pbr_multi_layer_material_textures")]
+            clearcoat_normal_texture: material.clearcoat_normal_texture,
+            anisotropy_strength: material.anisotropy_strength,
+            anisotropy_rotation: material.anisotropy_rotation,
+            #[cfg(feature = "pbr_anisotropy_texture")]
+            anisotropy_channel: material.anisotropy_channel.into(),
+            #[cfg(feature = "pbr_anisotropy_texture")]
+            anisotropy_texture: material.anisotropy_texture,
+            double_sided: material.double_sided,
+            cull_mode: material.cull_mode,
+            unlit: material.unlit,
+            fog_enabled: material.fog_enabled,
+            alpha_mode: material.alpha_mode,
+            depth_bias: material.depth_bias,
+            depth_map: material.depth_map,
+            parallax_depth_scale: material.parallax_depth_scale,
+            parallax_mapping_method: material.parallax_mapping_method,
+            max_parallax_layer_count: material.max_parallax_layer_count,
+            lightmap_exposure: material.lightmap_exposure,
+            opaque_render_method: material.opaque_render_method,
+            deferred_lighting_pass_id: material.deferred_lighting_pass_id,
+            uv_transform: material.uv_transform,
+            stochastic_textures: StochasticTextures::empty(),
+            layer_index: 0,
+        }
+    }
+}
+
+/// Lists every array texture field of a [`LayeredMaterial`] in binding order,
+/// so the shared and mutable accessors can't drift apart.
+macro_rules! array_textures {
+    ($material:ident, $($reference:tt)+) => {{
+        const WHITE: [u8; 4] = [255, 255, 255, 255];
+        const FLAT_NORMAL: [u8; 4] = [128, 128, 255, 255];
+        // a depth of 0 is flush with the surface
+        const NO_DEPTH: [u8; 4] = [0, 0, 0, 255];
+        // rg encodes an unrotated direction, b full strength
+        #[cfg(feature = "pbr_anisotropy_texture")]
+        const NO_ANISOTROPY_ROTATION: [u8; 4] = [255, 128, 255, 255];
+
+        #[allow(unused_mut)]
+        let mut textures = vec![
+            ($($reference)+ $material.base_color_texture, WHITE),
+            ($($reference)+ $material.emissive_texture, WHITE),
+            ($($reference)+ $material.metallic_roughness_texture, WHITE),
+            ($($reference)+ $material.occlusion_texture, WHITE),
+            ($($reference)+ $material.normal_map_texture, FLAT_NORMAL),
+            ($($reference)+ $material.depth_map, NO_DEPTH),
+        ];
+        #[cfg(feature = "pbr_anisotropy_texture")]
+        textures.push(($($reference)+ $material.anisotropy_texture, NO_ANISOTROPY_ROTATION));
+        #[cfg(feature = "pbr_transmission_textures")]
+        textures.extend([
+            ($($reference)+ $material.specular_transmission_texture, WHITE),
+            ($($reference)+ $material.thickness_texture, WHITE),
+            ($($reference)+ $material.diffuse_transmission_texture, WHITE),
+        ]);
+        #[cfg(feature = "pbr_multi_layer_material_textures")]
+        textures.extend([
+            ($($reference)+ $material.clearcoat_texture, WHITE),
+            ($($reference)+ $material.clearcoat_roughness_texture, WHITE),
+            ($($reference)+ $material.clearcoat_normal_texture, FLAT_NORMAL),
+        ]);
+        #[cfg(feature = "pbr_specular_textures")]
+        textures.extend([
+            ($($reference)+ $material.specular_texture, WHITE),
+            ($($reference)+ $material.specular_tint_texture, WHITE),
+        ]);
+        textures
+    }};
+}
+
+impl LayeredMaterial {
+    /// Converts a [`StandardMaterial`], wrapping each of its 2d textures
+    /// into a new one-layer array texture.
+    ///
+    /// The original images are left untouched so they can keep being
+    /// used by the [`StandardMaterial`]. Textures that aren't loaded yet
+    /// can't be wrapped and are copied over as-is.
+    pub fn from_standard_material(material: StandardMaterial, images: &mut Assets<Image>) -> Self {
+        let mut layered = LayeredMaterial::from(material);
+        for (texture, _) in layered.array_textures_mut() {
+            let Some(handle) = texture.as_mut() else {
+                continue;
+            };
+            if let Some(wrapped) = single_layer_array(images, handle) {
+                *handle = wrapped;
+            }
+        }
+        layered
+    }
+
+    /// The texture fields that are bound as `texture_2d_array`s, each
+    /// paired with a texel that leaves the material unchanged when sampled.
+    pub(crate) fn array_textures(&self) -> Vec<(&Option<Handle<Image>>, [u8; 4])> {
+        array_textures!(self, &)
+    }
+
+    /// Mutable version of [`LayeredMaterial::array_textures`].
+    pub(crate) fn array_textures_mut(&mut self) -> Vec<(&mut Option<Handle<Image>>, [u8; 4])> {
+        array_textures!(self, &mut)
+    }
+}
+
+/// Adds a copy of the image behind `handle` that is viewed as a
+/// one-layer 2d array texture.
+///
+/// Returns `None` if the image isn't loaded, or can't be viewed as an
+/// array (for example because it's a cube map or 3d texture).
+pub fn single_layer_array(
+    images: &mut Assets<Image>,
+    handle: &Handle<Image>,
+) -> Option<Handle<Image>> {
+    let image = images.get(handle)?;
+    if image.texture_descriptor.dimension != TextureDimension::D2 {
+        return None;
+    }
+    let mut image = image.clone();
+    let layers = image.texture_descriptor.size.depth_or_array_layers;
+    image.texture_view_descriptor = Some(array_view_descriptor(layers));
+    Some(images.add(image))
+}
+
+fn array_view_descriptor(layers: u32) -> TextureViewDescriptor<'static> {
+    TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        array_layer_count: Some(layers),
+        ..Default::default()
+    }
+}
+
+/// Why a set of images couldn't be stacked into an array texture.
+#[derive(Error, Debug, PartialEq)]
+pub enum LayerStackError {
+    #[error("no layers were provided")]
+    Empty,
+    #[error("layer {layer} has no CPU-side pixel data")]
+    MissingData { layer: usize },
+    #[error("layer {layer} is not a single 2d image without mips")]
+    NotSingle2d { layer: usize },
+    #[error("layer {layer} is {found:?}, expected {expected:?}")]
+    SizeMismatch {
+        layer: usize,
+        expected: Extent3d,
+        found: Extent3d,
+    },
+    #[error("layer {layer} is {found:?}, expected {expected:?}")]
+    FormatMismatch {
+        layer: usize,
+        expected: TextureFormat,
+        found: TextureFormat,
+    },
+    #[error("missing layers can only be filled for 8-bit rgba formats, not {0:?}")]
+    UnsupportedFillFormat(TextureFormat),
+}
+
+/// Stacks 2d images into a single 2d array texture, one image per layer.
+///
+/// All present images must share the same size and format and have no
+/// mip levels. `None` layers are filled with the `fill` texel, which is
+/// only supported for 8-bit rgba formats.
+///
+/// The sampler of the first image is used for the whole array.
+pub fn stack_layers(layers: &[Option<&Image>], fill: [u8; 4]) -> Result<Image, LayerStackError> {
+    let (first_index, first) = layers
+        .iter()
+        .enumerate()
+        .find_map(|(i, layer)| layer.map(|image| (i, image)))
+        .ok_or(LayerStackError::Empty)?;
+
+    let size = first.texture_descriptor.size;
+    let format = first.texture_descriptor.format;
+
+    for (layer, image) in layers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, image)| image.map(|image| (i, image)))
+    {
+        let descriptor = &image.texture_descriptor;
+        if descriptor.dimension != TextureDimension::D2
+            || descriptor.size.depth_or_array_layers != 1
+            || descriptor.mip_level_count != 1
+        {
+            return Err(LayerStackError::NotSingle2d { layer });
+        }
+        if descriptor.size != size {
+            return Err(LayerStackError::SizeMismatch {
+                layer,
+                expected: size,
+                found: descriptor.size,
+            });
+        }
+        if descriptor.format != format {
+            return Err(LayerStackError::FormatMismatch {
+                layer,
+                expected: format,
+                found: descriptor.format,
+            });
+        }
+    }
+
+    let layer_bytes = first
+        .data
+        .as_ref()
+        .ok_or(LayerStackError::MissingData { layer: first_index })?
+        .len();
+
+    let mut data = Vec::with_capacity(layer_bytes * layers.len());
+    for (layer, image) in layers.iter().enumerate() {
+        match image {
+            Some(image) => {
+                let bytes = image
+                    .data
+                    .as_ref()
+                    .ok_or(LayerStackError::MissingData { layer })?;
+                data.extend_from_slice(bytes);
+            }
+            None => {
+                if !matches!(
+                    format,
+                    TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+                ) {
+                    return Err(LayerStackError::UnsupportedFillFormat(format));
+                }
+                data.extend(fill.iter().copied().cycle().take(layer_bytes));
+            }
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            depth_or_array_layers: layers.len() as u32,
+            ..size
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        first.asset_usage,
+    );
+    image.sampler = first.sampler.clone();
+    image.texture_view_descriptor = Some(array_view_descriptor(layers.len() as u32));
+    Ok(image)
+}
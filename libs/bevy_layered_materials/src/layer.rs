@@ -0,0 +1,98 @@
+use bevy_ecs::prelude::*;
+use bevy_log::warn;
+use bevy_mesh::MeshTag;
+use bevy_platform::collections::HashSet;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+
+/// The highest layer [`MaterialLayer`] and [`LayerWeights`] can select.
+///
+/// Layers are packed into 8 bits of the instance's [`MeshTag`], with `0`
+/// reserved for "use the material's `layer_index`".
+pub const MAX_INSTANCE_LAYER: u32 = 254;
+
+/// Selects which layer of a [`LayeredMaterial`](crate::LayeredMaterial)'s
+/// array textures this instance samples from, overriding the material's
+/// `layer_index`.
+///
+/// Instances with different layers keep sharing one material, so they're
+/// still drawn in a single batch.
+///
+/// The layer is handed to the shader through the instance's [`MeshTag`],
+/// which means the entity's [`MeshTag`] is owned by this component.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Component, Default, Clone, PartialEq)]
+pub struct MaterialLayer(pub u32);
+
+/// Blends between two layers of a [`LayeredMaterial`](crate::LayeredMaterial)'s
+/// array textures for this instance. Takes precedence over [`MaterialLayer`].
+///
+/// Every texture is sampled once per layer when `blend` is between `0.0`
+/// and `1.0`. The prepass uses whichever layer has the larger weight.
+///
+/// Like [`MaterialLayer`], this owns the entity's [`MeshTag`].
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+#[reflect(Component, Default, Clone, PartialEq)]
+pub struct LayerWeights {
+    pub first: u32,
+    pub second: u32,
+    /// How much of `second` to use, from `0.0` to `1.0`.
+    pub blend: f32,
+}
+
+impl From<MaterialLayer> for LayerWeights {
+    fn from(MaterialLayer(layer): MaterialLayer) -> Self {
+        LayerWeights {
+            first: layer,
+            second: layer,
+            blend: 0.0,
+        }
+    }
+}
+
+impl LayerWeights {
+    /// Packs the weights the way `pbr_bindings::material_layers` unpacks
+    /// them: the first layer plus one in bits 0..8, the second layer plus
+    /// one in bits 8..16 and `blend` as unorm16 in bits 16..32.
+    pub fn to_mesh_tag(self) -> MeshTag {
+        let pack = |layer: u32| {
+            if layer > MAX_INSTANCE_LAYER {
+                warn!("layer {layer} is above MAX_INSTANCE_LAYER ({MAX_INSTANCE_LAYER}), clamping");
+            }
+            layer.min(MAX_INSTANCE_LAYER) + 1
+        };
+        let blend = (self.blend.clamp(0.0, 1.0) * u16::MAX as f32).round() as u32;
+        MeshTag(pack(self.first) | pack(self.second) << 8 | blend << 16)
+    }
+}
+
+/// Keeps the [`MeshTag`] of every entity with a [`MaterialLayer`] or
+/// [`LayerWeights`] in sync, so the layer reaches the render world with
+/// the rest of the mesh instance data.
+pub(crate) fn sync_layer_tags(
+    mut commands: Commands,
+    changed: Query<Entity, Or<(Changed<MaterialLayer>, Changed<LayerWeights>)>>,
+    mut removed_layers: RemovedComponents<MaterialLayer>,
+    mut removed_weights: RemovedComponents<LayerWeights>,
+    layers: Query<(Option<&MaterialLayer>, Option<&LayerWeights>)>,
+) {
+    let entities: HashSet<Entity> = changed
+        .iter()
+        .chain(removed_layers.read())
+        .chain(removed_weights.read())
+        .collect();
+
+    for entity in entities {
+        // despawned since
+        let Ok((layer, weights)) = layers.get(entity) else {
+            continue;
+        };
+        match weights.copied().or(layer.copied().map(LayerWeights::from)) {
+            Some(weights) => {
+                commands.entity(entity).insert(weights.to_mesh_tag());
+            }
+            None => {
+                commands.entity(entity).remove::<MeshTag>();
+            }
+        }
+    }
+}
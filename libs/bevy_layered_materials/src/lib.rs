@@ -1,40 +1,56 @@
 use std::path::PathBuf;
 
-use bevy_app::Plugin;
+use bevy_app::{Plugin, PostUpdate};
 use bevy_asset::AssetPath;
 use bevy_pbr::{MaterialPlugin, ParallaxMappingMethod};
 use bevy_shader::{load_shader_library, ShaderRef};
+use bevy_shader_utils::load_stochastic_shaders;
 
+mod conversion;
+mod layer;
+mod merge;
 mod pbr_material;
 
+pub use conversion::*;
+pub use layer::{LayerWeights, MaterialLayer, MAX_INSTANCE_LAYER};
+pub use merge::UseLayeredMaterial;
 pub use pbr_material::*;
 
 pub struct LayeredMaterialsPlugin;
 
 impl Plugin for LayeredMaterialsPlugin {
     fn build(&self, app: &mut bevy_app::App) {
+        // only the modules that touch the material bindings or `PbrInput`
+        // are forked. lighting, transmission, shadows, ambient, rgb9e5,
+        // the deferred types and the view transformations don't know about
+        // the material and are imported straight from bevy_pbr.
         load_shader_library!(app, "render/pbr_types.wgsl");
         load_shader_library!(app, "render/pbr_bindings.wgsl");
-        // load_shader_library!(app, "render/utils.wgsl");
-        // load_shader_library!(app, "render/clustered_forward.wgsl");
-        // load_shader_library!(app, "render/pbr_lighting.wgsl");
-        // load_shader_library!(app, "render/pbr_transmission.wgsl");
-        // load_shader_library!(app, "render/shadows.wgsl");
-        // load_shader_library!(app, "deferred/pbr_deferred_types.wgsl");
-        // load_shader_library!(app, "deferred/pbr_deferred_functions.wgsl");
-        // load_shader_library!(app, "render/shadow_sampling.wgsl");
+        load_shader_library!(app, "render/layer_sampling.wgsl");
+        load_shader_library!(app, "render/parallax_mapping.wgsl");
         load_shader_library!(app, "render/pbr_functions.wgsl");
-        // load_shader_library!(app, "render/rgb9e5.wgsl");
-        // load_shader_library!(app, "render/pbr_ambient.wgsl");
         load_shader_library!(app, "render/pbr_fragment.wgsl");
         load_shader_library!(app, "render/pbr.wgsl");
-        // load_shader_library!(app, "render/pbr_prepass_functions.wgsl");
+        load_shader_library!(app, "render/pbr_prepass_functions.wgsl");
         load_shader_library!(app, "render/pbr_prepass.wgsl");
-        // load_shader_library!(app, "render/parallax_mapping.wgsl");
-        // load_shader_library!(app, "render/view_transformations.wgsl");
+        load_shader_library!(app, "deferred/pbr_deferred_functions.wgsl");
 
         load_shader_library!(app, "decal/clustered.wgsl");
-        app.add_plugins(MaterialPlugin::<LayeredMaterial>::default());
+
+        // stochastic sampling comes from bevy_shader_utils, which works
+        // with or without `ShaderUtilsPlugin` in the app
+        load_stochastic_shaders(app);
+
+        app.add_plugins(MaterialPlugin::<LayeredMaterial>::default())
+            .init_resource::<merge::PendingLayeredMaterials>()
+            .init_resource::<merge::LayeredMaterialArrays>()
+            .register_type::<UseLayeredMaterial>()
+            .register_type::<MaterialLayer>()
+            .register_type::<LayerWeights>()
+            .add_systems(
+                PostUpdate,
+                (merge::merge_layered_materials, layer::sync_layer_tags),
+            );
     }
 }
 
@@ -0,0 +1,227 @@
+use bevy_asset::{AssetId, Assets, Handle};
+use bevy_ecs::{lifecycle::HookContext, prelude::*, world::DeferredWorld};
+use bevy_image::Image;
+use bevy_log::warn;
+use bevy_pbr::{MeshMaterial3d, StandardMaterial};
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_reflect::Reflect;
+
+use crate::{stack_layers, LayerStackError, LayeredMaterial};
+
+/// Place on glTF primitives (for example through bevy_skein, or in a
+/// scene post-process) to replace their [`StandardMaterial`] with a
+/// [`LayeredMaterial`].
+///
+/// Every distinct [`StandardMaterial`] becomes one layer of a single set
+/// of array textures, shared by every tagged primitive however late its
+/// material loads, so they end up sampling the same images with a
+/// different `layer_index`. Primitives sharing a [`StandardMaterial`]
+/// share its [`LayeredMaterial`] too. The marker can be inserted before
+/// the [`MeshMaterial3d`], the entity is converted once it has one.
+///
+/// Textures in the same slot (base color, normal map, ...) must share a
+/// size and format to be stacked. Materials without a texture in a slot
+/// get a neutral layer instead. Materials that can't be stacked with the
+/// rest are left as they are, with a warning.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+#[component(on_add = on_add_use_layered_material)]
+pub struct UseLayeredMaterial;
+
+/// Entities tagged with [`UseLayeredMaterial`] that haven't been
+/// converted yet.
+#[derive(Resource, Default)]
+pub(crate) struct PendingLayeredMaterials(Vec<Entity>);
+
+/// Everything [`UseLayeredMaterial`] has converted so far, kept across
+/// frames so materials that load later join the same arrays.
+#[derive(Resource, Default)]
+pub(crate) struct LayeredMaterialArrays {
+    /// One per layer, in `layer_index` order.
+    layers: Vec<Layer>,
+    /// The array texture of each slot, `None` until a layer has a texture
+    /// in it.
+    shared: Vec<Option<Handle<Image>>>,
+    converted: HashMap<AssetId<StandardMaterial>, Handle<LayeredMaterial>>,
+    /// Materials that couldn't be stacked with the layers before them.
+    unmerged: HashSet<AssetId<StandardMaterial>>,
+}
+
+struct Layer {
+    material: Handle<LayeredMaterial>,
+    /// The images the layer is stacked from, kept to stack them again
+    /// when a layer is added.
+    textures: Vec<Option<Handle<Image>>>,
+}
+
+fn on_add_use_layered_material(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    world
+        .resource_mut::<PendingLayeredMaterials>()
+        .0
+        .push(entity);
+}
+
+/// Stacks every slot of `layers`, which all have one texture (or none)
+/// per slot. Slots no layer has a texture in stay `None`.
+fn stack_slots(
+    layers: &[&[Option<Handle<Image>>]],
+    fills: &[[u8; 4]],
+    images: &Assets<Image>,
+) -> Result<Vec<Option<Image>>, (usize, LayerStackError)> {
+    fills
+        .iter()
+        .enumerate()
+        .map(|(slot, fill)| {
+            if layers.iter().all(|textures| textures[slot].is_none()) {
+                return Ok(None);
+            }
+            let slot_images: Vec<Option<&Image>> = layers
+                .iter()
+                .map(|textures| {
+                    textures[slot]
+                        .as_ref()
+                        .and_then(|handle| images.get(handle))
+                })
+                .collect();
+            stack_layers(&slot_images, *fill)
+                .map(Some)
+                .map_err(|error| (slot, error))
+        })
+        .collect()
+}
+
+pub(crate) fn merge_layered_materials(
+    mut commands: Commands,
+    mut pending: ResMut<PendingLayeredMaterials>,
+    mut arrays: ResMut<LayeredMaterialArrays>,
+    tagged: Query<(), With<UseLayeredMaterial>>,
+    query: Query<&MeshMaterial3d<StandardMaterial>>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut layered_materials: ResMut<Assets<LayeredMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if pending.0.is_empty() {
+        return;
+    }
+    let arrays = &mut *arrays;
+
+    // despawned entities, or entities that lost the marker, have nothing
+    // to convert. Entities that don't have their StandardMaterial yet
+    // stay pending until they do.
+    pending.0.retain(|entity| tagged.contains(*entity));
+
+    // each distinct StandardMaterial that isn't a layer yet becomes one
+    let mut candidates: Vec<AssetId<StandardMaterial>> = vec![];
+    for material in query.iter_many(&pending.0) {
+        let id = material.id();
+        if !arrays.converted.contains_key(&id)
+            && !arrays.unmerged.contains(&id)
+            && !candidates.contains(&id)
+        {
+            candidates.push(id);
+        }
+    }
+
+    // materials that are still loading, or whose textures are, are
+    // converted in a later frame. The rest are stacked with every layer
+    // so far, one at a time, so a material that doesn't fit only leaves
+    // itself out.
+    let mut added: Vec<(AssetId<StandardMaterial>, LayeredMaterial, Vec<_>)> = vec![];
+    let mut stacked = None;
+    for id in candidates {
+        let Some(material) = standard_materials.get(id) else {
+            continue;
+        };
+        let material = LayeredMaterial::from(material.clone());
+        let (textures, fills): (Vec<_>, Vec<_>) = material
+            .array_textures()
+            .into_iter()
+            .map(|(texture, fill)| (texture.clone(), fill))
+            .unzip();
+        if textures
+            .iter()
+            .flatten()
+            .any(|texture| images.get(texture).is_none())
+        {
+            continue;
+        }
+
+        let layers: Vec<&[Option<Handle<Image>>]> = arrays
+            .layers
+            .iter()
+            .map(|layer| layer.textures.as_slice())
+            .chain(added.iter().map(|(.., textures)| textures.as_slice()))
+            .chain([textures.as_slice()])
+            .collect();
+        match stack_slots(&layers, &fills, &images) {
+            Ok(slots) => {
+                stacked = Some(slots);
+                added.push((id, material, textures));
+            }
+            Err((slot, error)) => {
+                warn!(
+                    "UseLayeredMaterial: couldn't stack texture slot {slot} of {id} with the other layers, leaving it unmerged: {error}"
+                );
+                arrays.unmerged.insert(id);
+            }
+        }
+    }
+
+    if let Some(stacked) = stacked {
+        arrays.shared.resize(stacked.len(), None);
+        for (shared, image) in arrays.shared.iter_mut().zip(stacked) {
+            let Some(image) = image else {
+                continue;
+            };
+            // existing arrays grow in place, so the layers already using
+            // them keep the same handle
+            match shared {
+                Some(handle) => {
+                    images
+                        .insert(handle.id(), image)
+                        .expect("shared arrays are strong handles");
+                }
+                None => *shared = Some(images.add(image)),
+            }
+        }
+
+        for (id, mut material, textures) in added {
+            material.layer_index = arrays.layers.len() as u32;
+            let material = layered_materials.add(material);
+            arrays.converted.insert(id, material.clone());
+            arrays.layers.push(Layer { material, textures });
+        }
+
+        // slots can gain an array, and the materials have to be prepared
+        // again against the grown arrays either way
+        for layer in &arrays.layers {
+            let Some(material) = layered_materials.get_mut(&layer.material) else {
+                continue;
+            };
+            for ((texture, _), shared) in material
+                .array_textures_mut()
+                .into_iter()
+                .zip(&arrays.shared)
+            {
+                *texture = shared.clone();
+            }
+        }
+    }
+
+    pending.0.retain(|entity| {
+        let Ok(material) = query.get(*entity) else {
+            return true;
+        };
+        if arrays.unmerged.contains(&material.id()) {
+            return false;
+        }
+        let Some(converted) = arrays.converted.get(&material.id()) else {
+            return true;
+        };
+        commands
+            .entity(*entity)
+            .remove::<MeshMaterial3d<StandardMaterial>>()
+            .insert(MeshMaterial3d(converted.clone()));
+        false
+    });
+}
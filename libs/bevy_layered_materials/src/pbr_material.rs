@@ -11,7 +11,7 @@ use bevy_reflect::{std_traits::ReflectDefault, Reflect};
 use bevy_render::{
     alpha::AlphaMode, render_asset::RenderAssets, render_resource::*, texture::GpuImage,
 };
-use bevy_shader::ShaderRef;
+use bevy_shader::{ShaderDefVal, ShaderRef};
 use bitflags::bitflags;
 
 use crate::shader_ref;
@@ -245,7 +245,10 @@ pub struct LayeredMaterial {
     ///
     /// **Important:** The [`LayeredMaterial::diffuse_transmission`] property must be set to a value higher than 0.0,
     /// or this texture won't have any effect.
-    #[cfg_attr(feature = "pbr_transmission_textures", texture(19))]
+    #[cfg_attr(
+        feature = "pbr_transmission_textures",
+        texture(19, dimension = "2d_array")
+    )]
     #[cfg_attr(feature = "pbr_transmission_textures", sampler(20))]
     #[cfg(feature = "pbr_transmission_textures")]
     pub diffuse_transmission_texture: Option<Handle<Image>>,
@@ -286,7 +289,10 @@ pub struct LayeredMaterial {
     ///
     /// **Important:** The [`LayeredMaterial::specular_transmission`] property must be set to a value higher than 0.0,
     /// or this texture won't have any effect.
-    #[cfg_attr(feature = "pbr_transmission_textures", texture(15))]
+    #[cfg_attr(
+        feature = "pbr_transmission_textures",
+        texture(15, dimension = "2d_array")
+    )]
     #[cfg_attr(feature = "pbr_transmission_textures", sampler(16))]
     #[cfg(feature = "pbr_transmission_textures")]
     pub specular_transmission_texture: Option<Handle<Image>>,
@@ -315,7 +321,10 @@ pub struct LayeredMaterial {
     ///
     /// **Important:** The [`LayeredMaterial::thickness`] property must be set to a value higher than 0.0,
     /// or this texture won't have any effect.
-    #[cfg_attr(feature = "pbr_transmission_textures", texture(17))]
+    #[cfg_attr(
+        feature = "pbr_transmission_textures",
+        texture(17, dimension = "2d_array")
+    )]
     #[cfg_attr(feature = "pbr_transmission_textures", sampler(18))]
     #[cfg(feature = "pbr_transmission_textures")]
     pub thickness_texture: Option<Handle<Image>>,
@@ -469,7 +478,7 @@ pub struct LayeredMaterial {
     /// the [`LayeredMaterial::specular_tint_texture`] has no alpha value, it
     /// may be desirable to pack the values together and supply the same
     /// texture to both fields.
-    #[cfg_attr(feature = "pbr_specular_textures", texture(27))]
+    #[cfg_attr(feature = "pbr_specular_textures", texture(27, dimension = "2d_array"))]
     #[cfg_attr(feature = "pbr_specular_textures", sampler(28))]
     #[cfg(feature = "pbr_specular_textures")]
     pub specular_texture: Option<Handle<Image>>,
@@ -490,7 +499,7 @@ pub struct LayeredMaterial {
     ///
     /// Like the fixed specular tint value, this texture map isn't supported in
     /// the deferred renderer.
-    #[cfg_attr(feature = "pbr_specular_textures", texture(29))]
+    #[cfg_attr(feature = "pbr_specular_textures", texture(29, dimension = "2d_array"))]
     #[cfg_attr(feature = "pbr_specular_textures", sampler(30))]
     #[cfg(feature = "pbr_specular_textures")]
     pub specular_tint_texture: Option<Handle<Image>>,
@@ -515,7 +524,10 @@ pub struct LayeredMaterial {
     /// main [`LayeredMaterial::clearcoat`] factor.
     ///
     /// As this is a non-color map, it must not be loaded as sRGB.
-    #[cfg_attr(feature = "pbr_multi_layer_material_textures", texture(21))]
+    #[cfg_attr(
+        feature = "pbr_multi_layer_material_textures",
+        texture(21, dimension = "2d_array")
+    )]
     #[cfg_attr(feature = "pbr_multi_layer_material_textures", sampler(22))]
     #[cfg(feature = "pbr_multi_layer_material_textures")]
     pub clearcoat_texture: Option<Handle<Image>>,
@@ -540,7 +552,10 @@ pub struct LayeredMaterial {
     /// [`LayeredMaterial::clearcoat_perceptual_roughness`] factor.
     ///
     /// As this is a non-color map, it must not be loaded as sRGB.
-    #[cfg_attr(feature = "pbr_multi_layer_material_textures", texture(23))]
+    #[cfg_attr(
+        feature = "pbr_multi_layer_material_textures",
+        texture(23, dimension = "2d_array")
+    )]
     #[cfg_attr(feature = "pbr_multi_layer_material_textures", sampler(24))]
     #[cfg(feature = "pbr_multi_layer_material_textures")]
     pub clearcoat_roughness_texture: Option<Handle<Image>>,
@@ -562,7 +577,10 @@ pub struct LayeredMaterial {
     /// in both [`LayeredMaterial::normal_map_texture`] and this field.
     ///
     /// As this is a non-color map, it must not be loaded as sRGB.
-    #[cfg_attr(feature = "pbr_multi_layer_material_textures", texture(25))]
+    #[cfg_attr(
+        feature = "pbr_multi_layer_material_textures",
+        texture(25, dimension = "2d_array")
+    )]
     #[cfg_attr(feature = "pbr_multi_layer_material_textures", sampler(26))]
     #[cfg(feature = "pbr_multi_layer_material_textures")]
     pub clearcoat_normal_texture: Option<Handle<Image>>,
@@ -632,7 +650,10 @@ pub struct LayeredMaterial {
     ///
     /// [`KHR_materials_anisotropy` specification]:
     /// https://github.com/KhronosGroup/glTF/blob/main/extensions/2.0/Khronos/KHR_materials_anisotropy/README.md
-    #[cfg_attr(feature = "pbr_anisotropy_texture", texture(13))]
+    #[cfg_attr(
+        feature = "pbr_anisotropy_texture",
+        texture(13, dimension = "2d_array")
+    )]
     #[cfg_attr(feature = "pbr_anisotropy_texture", sampler(14))]
     #[cfg(feature = "pbr_anisotropy_texture")]
     pub anisotropy_texture: Option<Handle<Image>>,
@@ -736,7 +757,7 @@ pub struct LayeredMaterial {
     /// [`parallax_depth_scale`]: LayeredMaterial::parallax_depth_scale
     /// [`parallax_mapping_method`]: LayeredMaterial::parallax_mapping_method
     /// [`max_parallax_layer_count`]: LayeredMaterial::max_parallax_layer_count
-    #[texture(11)]
+    #[texture(11, dimension = "2d_array")]
     #[sampler(12)]
     #[dependency]
     pub depth_map: Option<Handle<Image>>,
@@ -785,6 +806,12 @@ pub struct LayeredMaterial {
     /// The transform applied to the UVs corresponding to `ATTRIBUTE_UV_0` on the mesh before sampling. Default is identity.
     pub uv_transform: Affine2,
 
+    /// Textures to sample with hex-tiling, which hides repetition on large
+    /// surfaces like terrain.
+    ///
+    /// Default is [`StochasticTextures::empty`].
+    pub stochastic_textures: StochasticTextures,
+
     /// TODO: bevy_layered_materials.
     /// The index to render. A temporary solution to checking
     /// if everything is going well.
@@ -944,6 +971,7 @@ impl Default for LayeredMaterial {
             opaque_render_method: OpaqueRendererMethod::Auto,
             deferred_lighting_pass_id: DEFAULT_PBR_DEFERRED_LIGHTING_PASS_ID,
             uv_transform: Affine2::IDENTITY,
+            stochastic_textures: StochasticTextures::empty(),
             layer_index: 0,
         }
     }
@@ -1016,6 +1044,36 @@ impl LayeredMaterialFlags {
     const ALPHA_MODE_SHIFT_BITS: u32 = 32 - Self::ALPHA_MODE_MASK_BITS.count_ones();
 }
 
+// NOTE: These must match the STOCHASTIC_TEXTURE bits in src/render/pbr_types.wgsl!
+bitflags::bitflags! {
+    /// The textures of a [`LayeredMaterial`] that are sampled with hex-tiling
+    /// (stochastic sampling) to hide the repetition of tiling textures.
+    ///
+    /// Each flagged texture is sampled three times instead of once. The
+    /// depth map is never hex-tiled, since parallax mapping marches through
+    /// it.
+    #[repr(transparent)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+    #[reflect(opaque)]
+    #[reflect(Clone, Debug, Default, PartialEq, Hash)]
+    pub struct StochasticTextures: u32 {
+        const BASE_COLOR                 = 1 << 0;
+        const EMISSIVE                   = 1 << 1;
+        const METALLIC_ROUGHNESS         = 1 << 2;
+        const OCCLUSION                  = 1 << 3;
+        const NORMAL_MAP                 = 1 << 4;
+        const ANISOTROPY                 = 1 << 5;
+        const DIFFUSE_TRANSMISSION       = 1 << 6;
+        const SPECULAR_TRANSMISSION      = 1 << 7;
+        const THICKNESS                  = 1 << 8;
+        const CLEARCOAT                  = 1 << 9;
+        const CLEARCOAT_ROUGHNESS        = 1 << 10;
+        const CLEARCOAT_NORMAL           = 1 << 11;
+        const SPECULAR                   = 1 << 12;
+        const SPECULAR_TINT              = 1 << 13;
+    }
+}
+
 /// The GPU representation of the uniform data of a [`LayeredMaterial`].
 #[derive(Clone, Default, ShaderType)]
 pub struct LayeredMaterialUniform {
@@ -1071,6 +1129,8 @@ pub struct LayeredMaterialUniform {
     /// ID for specifying which deferred lighting pass should be used for rendering this material, if any.
     pub deferred_lighting_pass_id: u32,
     pub layer_index: u32,
+    /// The [`StochasticTextures`] accessible in the `wgsl` shader.
+    pub stochastic_textures: u32,
 }
 
 impl AsBindGroupShaderType<LayeredMaterialUniform> for LayeredMaterial {
@@ -1218,6 +1278,7 @@ impl AsBindGroupShaderType<LayeredMaterialUniform> for LayeredMaterial {
             deferred_lighting_pass_id: self.deferred_lighting_pass_id as u32,
             uv_transform: self.uv_transform.into(),
             layer_index: self.layer_index,
+            stochastic_textures: self.stochastic_textures.bits(),
         }
     }
 }
@@ -1256,6 +1317,105 @@ bitflags! {
 
 const STANDARD_MATERIAL_KEY_DEPTH_BIAS_SHIFT: u64 = 32;
 
+impl LayeredMaterialKey {
+    /// The fragment shader defs enabled by this key.
+    ///
+    /// [`Material::specialize`] pushes these onto the fragment stage, they're
+    /// exposed separately so the shader permutations can be checked without
+    /// a [`MaterialPipeline`].
+    pub fn shader_defs(self) -> Vec<ShaderDefVal> {
+        [
+            (
+                LayeredMaterialKey::NORMAL_MAP,
+                "STANDARD_MATERIAL_NORMAL_MAP",
+            ),
+            (LayeredMaterialKey::RELIEF_MAPPING, "RELIEF_MAPPING"),
+            (
+                LayeredMaterialKey::DIFFUSE_TRANSMISSION,
+                "STANDARD_MATERIAL_DIFFUSE_TRANSMISSION",
+            ),
+            (
+                LayeredMaterialKey::SPECULAR_TRANSMISSION,
+                "STANDARD_MATERIAL_SPECULAR_TRANSMISSION",
+            ),
+            (
+                LayeredMaterialKey::DIFFUSE_TRANSMISSION
+                    | LayeredMaterialKey::SPECULAR_TRANSMISSION,
+                "STANDARD_MATERIAL_DIFFUSE_OR_SPECULAR_TRANSMISSION",
+            ),
+            (LayeredMaterialKey::CLEARCOAT, "STANDARD_MATERIAL_CLEARCOAT"),
+            (
+                LayeredMaterialKey::CLEARCOAT_NORMAL_MAP,
+                "STANDARD_MATERIAL_CLEARCOAT_NORMAL_MAP",
+            ),
+            (
+                LayeredMaterialKey::ANISOTROPY,
+                "STANDARD_MATERIAL_ANISOTROPY",
+            ),
+            (
+                LayeredMaterialKey::BASE_COLOR_UV,
+                "STANDARD_MATERIAL_BASE_COLOR_UV_B",
+            ),
+            (
+                LayeredMaterialKey::EMISSIVE_UV,
+                "STANDARD_MATERIAL_EMISSIVE_UV_B",
+            ),
+            (
+                LayeredMaterialKey::METALLIC_ROUGHNESS_UV,
+                "STANDARD_MATERIAL_METALLIC_ROUGHNESS_UV_B",
+            ),
+            (
+                LayeredMaterialKey::OCCLUSION_UV,
+                "STANDARD_MATERIAL_OCCLUSION_UV_B",
+            ),
+            (
+                LayeredMaterialKey::SPECULAR_TRANSMISSION_UV,
+                "STANDARD_MATERIAL_SPECULAR_TRANSMISSION_UV_B",
+            ),
+            (
+                LayeredMaterialKey::THICKNESS_UV,
+                "STANDARD_MATERIAL_THICKNESS_UV_B",
+            ),
+            (
+                LayeredMaterialKey::DIFFUSE_TRANSMISSION_UV,
+                "STANDARD_MATERIAL_DIFFUSE_TRANSMISSION_UV_B",
+            ),
+            (
+                LayeredMaterialKey::NORMAL_MAP_UV,
+                "STANDARD_MATERIAL_NORMAL_MAP_UV_B",
+            ),
+            (
+                LayeredMaterialKey::CLEARCOAT_UV,
+                "STANDARD_MATERIAL_CLEARCOAT_UV_B",
+            ),
+            (
+                LayeredMaterialKey::CLEARCOAT_ROUGHNESS_UV,
+                "STANDARD_MATERIAL_CLEARCOAT_ROUGHNESS_UV_B",
+            ),
+            (
+                LayeredMaterialKey::CLEARCOAT_NORMAL_UV,
+                "STANDARD_MATERIAL_CLEARCOAT_NORMAL_UV_B",
+            ),
+            (
+                LayeredMaterialKey::ANISOTROPY_UV,
+                "STANDARD_MATERIAL_ANISOTROPY_UV_B",
+            ),
+            (
+                LayeredMaterialKey::SPECULAR_UV,
+                "STANDARD_MATERIAL_SPECULAR_UV_B",
+            ),
+            (
+                LayeredMaterialKey::SPECULAR_TINT_UV,
+                "STANDARD_MATERIAL_SPECULAR_TINT_UV_B",
+            ),
+        ]
+        .into_iter()
+        .filter(|(flags, _)| self.intersects(*flags))
+        .map(|(_, shader_def)| shader_def.into())
+        .collect()
+    }
+}
+
 impl From<&LayeredMaterial> for LayeredMaterialKey {
     fn from(material: &LayeredMaterial) -> Self {
         let mut key = LayeredMaterialKey::empty();
@@ -1450,97 +1610,9 @@ impl Material for LayeredMaterial {
         key: MaterialPipelineKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
         if let Some(fragment) = descriptor.fragment.as_mut() {
-            let shader_defs = &mut fragment.shader_defs;
-
-            for (flags, shader_def) in [
-                (
-                    LayeredMaterialKey::NORMAL_MAP,
-                    "STANDARD_MATERIAL_NORMAL_MAP",
-                ),
-                (LayeredMaterialKey::RELIEF_MAPPING, "RELIEF_MAPPING"),
-                (
-                    LayeredMaterialKey::DIFFUSE_TRANSMISSION,
-                    "STANDARD_MATERIAL_DIFFUSE_TRANSMISSION",
-                ),
-                (
-                    LayeredMaterialKey::SPECULAR_TRANSMISSION,
-                    "STANDARD_MATERIAL_SPECULAR_TRANSMISSION",
-                ),
-                (
-                    LayeredMaterialKey::DIFFUSE_TRANSMISSION
-                        | LayeredMaterialKey::SPECULAR_TRANSMISSION,
-                    "STANDARD_MATERIAL_DIFFUSE_OR_SPECULAR_TRANSMISSION",
-                ),
-                (LayeredMaterialKey::CLEARCOAT, "STANDARD_MATERIAL_CLEARCOAT"),
-                (
-                    LayeredMaterialKey::CLEARCOAT_NORMAL_MAP,
-                    "STANDARD_MATERIAL_CLEARCOAT_NORMAL_MAP",
-                ),
-                (
-                    LayeredMaterialKey::ANISOTROPY,
-                    "STANDARD_MATERIAL_ANISOTROPY",
-                ),
-                (
-                    LayeredMaterialKey::BASE_COLOR_UV,
-                    "STANDARD_MATERIAL_BASE_COLOR_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::EMISSIVE_UV,
-                    "STANDARD_MATERIAL_EMISSIVE_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::METALLIC_ROUGHNESS_UV,
-                    "STANDARD_MATERIAL_METALLIC_ROUGHNESS_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::OCCLUSION_UV,
-                    "STANDARD_MATERIAL_OCCLUSION_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::SPECULAR_TRANSMISSION_UV,
-                    "STANDARD_MATERIAL_SPECULAR_TRANSMISSION_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::THICKNESS_UV,
-                    "STANDARD_MATERIAL_THICKNESS_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::DIFFUSE_TRANSMISSION_UV,
-                    "STANDARD_MATERIAL_DIFFUSE_TRANSMISSION_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::NORMAL_MAP_UV,
-                    "STANDARD_MATERIAL_NORMAL_MAP_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::CLEARCOAT_UV,
-                    "STANDARD_MATERIAL_CLEARCOAT_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::CLEARCOAT_ROUGHNESS_UV,
-                    "STANDARD_MATERIAL_CLEARCOAT_ROUGHNESS_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::CLEARCOAT_NORMAL_UV,
-                    "STANDARD_MATERIAL_CLEARCOAT_NORMAL_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::ANISOTROPY_UV,
-                    "STANDARD_MATERIAL_ANISOTROPY_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::SPECULAR_UV,
-                    "STANDARD_MATERIAL_SPECULAR_UV_B",
-                ),
-                (
-                    LayeredMaterialKey::SPECULAR_TINT_UV,
-                    "STANDARD_MATERIAL_SPECULAR_TINT_UV_B",
-                ),
-            ] {
-                if key.bind_group_data.intersects(flags) {
-                    shader_defs.push(shader_def.into());
-                }
-            }
+            fragment
+                .shader_defs
+                .extend(key.bind_group_data.shader_defs());
         }
 
         descriptor.primitive.cull_mode =
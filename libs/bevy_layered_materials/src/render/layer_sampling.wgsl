@@ -0,0 +1,44 @@
+#define_import_path bevy_layered_materials::layer_sampling
+
+#import bevy_pbr::pbr_functions::SampleBias
+#import bevy_shader_utils::stochastic::{stochastic_tiles, stochastic_blend}
+
+// How sharp the transition between stochastic tiles is, see
+// `bevy_shader_utils::stochastic::stochastic_tiles`.
+const STOCHASTIC_CONTRAST: f32 = 6.0;
+
+// Samples one layer of an array texture. With `stochastic` set the texture
+// is hex-tiled, which hides repetition at the cost of three samples.
+fn sample_layer(
+    t: texture_2d_array<f32>,
+    s: sampler,
+    uv: vec2<f32>,
+    layer_index: u32,
+    bias: SampleBias,
+    stochastic: bool,
+) -> vec4<f32> {
+#ifdef MESHLET_MESH_MATERIAL_PASS
+    let ddx_uv = bias.ddx_uv;
+    let ddy_uv = bias.ddy_uv;
+    if !stochastic {
+        return textureSampleGrad(t, s, uv, layer_index, ddx_uv, ddy_uv);
+    }
+#else   // MESHLET_MESH_MATERIAL_PASS
+    if !stochastic {
+        return textureSampleBias(t, s, uv, layer_index, bias.mip_bias);
+    }
+    // `textureSampleGrad` has no bias, so it's folded into the gradients
+    let ddx_uv = dpdx(uv) * exp2(bias.mip_bias);
+    let ddy_uv = dpdy(uv) * exp2(bias.mip_bias);
+#endif  // MESHLET_MESH_MATERIAL_PASS
+
+    // every tile is sampled with the gradients of the unoffset uv, so the
+    // mip level doesn't jump at the tile borders
+    let tiles = stochastic_tiles(uv, STOCHASTIC_CONTRAST);
+    return stochastic_blend(
+        textureSampleGrad(t, s, tiles.uv_a, layer_index, ddx_uv, ddy_uv),
+        textureSampleGrad(t, s, tiles.uv_b, layer_index, ddx_uv, ddy_uv),
+        textureSampleGrad(t, s, tiles.uv_c, layer_index, ddx_uv, ddy_uv),
+        tiles,
+    );
+}
@@ -5,17 +5,16 @@
     // decal::clustered::apply_decals,
 }
 #import bevy_layered_materials::{
-    pbr_fragment::pbr_input_from_standard_material,
+    pbr_fragment::pbr_input_from_material_layers,
     pbr_functions::alpha_discard,
 }
 #import bevy_layered_materials::pbr_types
+#import bevy_layered_materials::pbr_bindings
 #import bevy_layered_materials::decal::clustered::apply_decals
 
 #ifdef PREPASS_PIPELINE
-#import bevy_pbr::{
-    prepass_io::{VertexOutput, FragmentOutput},
-    pbr_deferred_functions::deferred_output,
-}
+#import bevy_pbr::prepass_io::{VertexOutput, FragmentOutput}
+#import bevy_layered_materials::pbr_deferred_functions::deferred_output
 #else
 #import bevy_pbr::{
     forward_io::{VertexOutput, FragmentOutput},
@@ -25,18 +24,19 @@
 }
 #import bevy_layered_materials::{
     pbr_types::STANDARD_MATERIAL_FLAGS_UNLIT_BIT,
-    pbr_bindings,
     pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
 }
 #import bevy_layered_materials::pbr_functions
 #endif
 
 #ifdef VISIBILITY_RANGE_DITHER
-#import bevy_pbr::pbr_functions::visibility_range_dither;
+#import bevy_layered_materials::pbr_functions::visibility_range_dither;
 #endif
 
 #ifdef MESHLET_MESH_MATERIAL_PASS
 #import bevy_pbr::meshlet_visibility_buffer_resolve::resolve_vertex_output
+#else
+#import bevy_pbr::mesh_bindings::mesh
 #endif
 
 #ifdef OIT_ENABLED
@@ -76,8 +76,17 @@ fn fragment(
     in.uv = forward_decal_info.uv;
 #endif
 
+#ifdef MESHLET_MESH_MATERIAL_PASS
+    // meshlets don't carry a mesh tag, so they always use the material's layer
+    let layer_index = pbr_bindings::material_layer_index(in.material_bind_group_slot);
+    let layers = pbr_bindings::MaterialLayers(layer_index, layer_index, 0.0);
+#else
+    let slot = mesh[in.instance_index].material_and_lightmap_bind_group_slot & 0xffffu;
+    let layers = pbr_bindings::material_layers(in.instance_index, slot);
+#endif
+
     // generate a PbrInput struct from the StandardMaterial bindings
-    var pbr_input = pbr_input_from_standard_material(in, is_front, pbr_bindings::material.layer_index);
+    var pbr_input = pbr_input_from_material_layers(in, is_front, layers);
 
     // alpha discard
     pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
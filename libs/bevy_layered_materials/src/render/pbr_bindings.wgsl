@@ -2,6 +2,10 @@
 
 #import bevy_layered_materials::pbr_types::StandardMaterial
 
+#ifndef MESHLET_MESH_MATERIAL_PASS
+#import bevy_pbr::mesh_functions::get_tag
+#endif  // MESHLET_MESH_MATERIAL_PASS
+
 #ifdef BINDLESS
 struct StandardMaterialBindings {
     material: u32,                      // 0
@@ -53,37 +57,77 @@ struct StandardMaterialBindings {
 @group(#{MATERIAL_BIND_GROUP}) @binding(8) var occlusion_sampler: sampler;
 @group(#{MATERIAL_BIND_GROUP}) @binding(9) var normal_map_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(10) var normal_map_sampler: sampler;
-@group(#{MATERIAL_BIND_GROUP}) @binding(11) var depth_map_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(11) var depth_map_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(12) var depth_map_sampler: sampler;
 
 #ifdef PBR_ANISOTROPY_TEXTURE_SUPPORTED
-@group(#{MATERIAL_BIND_GROUP}) @binding(13) var anisotropy_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(13) var anisotropy_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(14) var anisotropy_sampler: sampler;
 #endif  // PBR_ANISOTROPY_TEXTURE_SUPPORTED
 
 #ifdef PBR_TRANSMISSION_TEXTURES_SUPPORTED
-@group(#{MATERIAL_BIND_GROUP}) @binding(15) var specular_transmission_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(15) var specular_transmission_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(16) var specular_transmission_sampler: sampler;
-@group(#{MATERIAL_BIND_GROUP}) @binding(17) var thickness_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(17) var thickness_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(18) var thickness_sampler: sampler;
-@group(#{MATERIAL_BIND_GROUP}) @binding(19) var diffuse_transmission_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(19) var diffuse_transmission_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(20) var diffuse_transmission_sampler: sampler;
 #endif  // PBR_TRANSMISSION_TEXTURES_SUPPORTED
 
 #ifdef PBR_MULTI_LAYER_MATERIAL_TEXTURES_SUPPORTED
-@group(#{MATERIAL_BIND_GROUP}) @binding(21) var clearcoat_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(21) var clearcoat_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(22) var clearcoat_sampler: sampler;
-@group(#{MATERIAL_BIND_GROUP}) @binding(23) var clearcoat_roughness_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(23) var clearcoat_roughness_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(24) var clearcoat_roughness_sampler: sampler;
-@group(#{MATERIAL_BIND_GROUP}) @binding(25) var clearcoat_normal_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(25) var clearcoat_normal_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(26) var clearcoat_normal_sampler: sampler;
 #endif  // PBR_MULTI_LAYER_MATERIAL_TEXTURES_SUPPORTED
 
 #ifdef PBR_SPECULAR_TEXTURES_SUPPORTED
-@group(#{MATERIAL_BIND_GROUP}) @binding(27) var specular_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(27) var specular_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(28) var specular_sampler: sampler;
-@group(#{MATERIAL_BIND_GROUP}) @binding(29) var specular_tint_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(29) var specular_tint_texture: texture_2d_array<f32>;
 @group(#{MATERIAL_BIND_GROUP}) @binding(30) var specular_tint_sampler: sampler;
 #endif  // PBR_SPECULAR_TEXTURES_SUPPORTED
 
-#endif  // BINDLESS
\ No newline at end of file
+#endif  // BINDLESS
+
+// The layer of the array textures this material samples from.
+fn material_layer_index(slot: u32) -> u32 {
+#ifdef BINDLESS
+    return material_array[material_indices[slot].material].layer_index;
+#else   // BINDLESS
+    return material.layer_index;
+#endif  // BINDLESS
+}
+
+// The layers one instance samples from, set per instance by the
+// `MaterialLayer` and `LayerWeights` components.
+struct MaterialLayers {
+    first: u32,
+    second: u32,
+    // how much of `second` to use
+    blend: f32,
+}
+
+#ifndef MESHLET_MESH_MATERIAL_PASS
+// Unpacks the instance's `MeshTag`, see `LayerWeights::to_mesh_tag`. A tag
+// of 0 means the instance has no layer of its own and uses the material's.
+fn material_layers(instance_index: u32, slot: u32) -> MaterialLayers {
+    let tag = get_tag(instance_index);
+    if (tag & 0xffu) == 0u {
+        let material_layer = material_layer_index(slot);
+        return MaterialLayers(material_layer, material_layer, 0.0);
+    }
+    return MaterialLayers(
+        (tag & 0xffu) - 1u,
+        ((tag >> 8u) & 0xffu) - 1u,
+        f32(tag >> 16u) / 65535.0,
+    );
+}
+#endif  // MESHLET_MESH_MATERIAL_PASS
+
+// The layer with the larger weight, for passes that only sample once.
+fn dominant_layer(layers: MaterialLayers) -> u32 {
+    return select(layers.first, layers.second, layers.blend > 0.5);
+}
@@ -1,6 +1,6 @@
 #define_import_path bevy_layered_materials::pbr_fragment
 
-#import bevy_render::bindless::{bindless_samplers_filtering, bindless_textures_2d, bindless_textures_2d_array}
+#import bevy_render::bindless::{bindless_samplers_filtering, bindless_textures_2d_array}
 
 #import bevy_pbr::{
     pbr_functions,
@@ -11,12 +11,13 @@
     lighting,
     mesh_bindings::mesh,
     mesh_view_bindings::view,
-    parallax_mapping::parallaxed_uv,
     lightmap::lightmap,
 }
 #import bevy_layered_materials::{
     pbr_bindings,
     pbr_types,
+    parallax_mapping::parallaxed_uv,
+    layer_sampling,
 }
 
 #ifdef SCREEN_SPACE_AMBIENT_OCCLUSION
@@ -33,7 +34,7 @@
 #endif
 
 #ifdef BINDLESS
-#import bevy_pbr::pbr_bindings::material_indices
+#import bevy_layered_materials::pbr_bindings::material_indices
 #endif  // BINDLESS
 
 // prepare a basic PbrInput from the vertex stage output, mesh binding and view binding
@@ -88,11 +89,14 @@ fn pbr_input_from_standard_material(
 #endif  // MESHLET_MESH_MATERIAL_PASS
 #ifdef BINDLESS
     let flags = pbr_bindings::material_array[material_indices[slot].material].flags;
+    let stochastic_textures =
+        pbr_bindings::material_array[material_indices[slot].material].stochastic_textures;
     let base_color = pbr_bindings::material_array[material_indices[slot].material].base_color;
     let deferred_lighting_pass_id =
         pbr_bindings::material_array[material_indices[slot].material].deferred_lighting_pass_id;
 #else   // BINDLESS
     let flags = pbr_bindings::material.flags;
+    let stochastic_textures = pbr_bindings::material.stochastic_textures;
     let base_color = pbr_bindings::material.base_color;
     let deferred_lighting_pass_id = pbr_bindings::material.deferred_lighting_pass_id;
 #endif
@@ -165,6 +169,7 @@ pbr_input.material.uv_transform = uv_transform;
             // about.
             -Vt,
             slot,
+            layer_index,
         );
 #endif
 
@@ -186,6 +191,7 @@ pbr_input.material.uv_transform = uv_transform;
             // about.
             -Vt,
             slot,
+            layer_index,
         );
 #else
         uv_b = uv;
@@ -195,11 +201,7 @@ pbr_input.material.uv_transform = uv_transform;
 
     if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_BASE_COLOR_TEXTURE_BIT) != 0u) {
         pbr_input.material.base_color *=
-#ifdef MESHLET_MESH_MATERIAL_PASS
-            textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-            textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+            layer_sampling::sample_layer(
 #ifdef BINDLESS
                 bindless_textures_2d_array[material_indices[slot].base_color_texture],
                 bindless_samplers_filtering[material_indices[slot].base_color_sampler],
@@ -212,14 +214,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                 uv,
 #endif
-                // TODO: bevy_layered_materials index
                 layer_index,
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                bias.ddx_uv,
-                bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                bias,
+                (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_BASE_COLOR_BIT) != 0u,
         );
 
 #ifdef ALPHA_TO_COVERAGE
@@ -276,13 +273,9 @@ pbr_input.material.uv_transform = uv_transform;
         // Specular texture
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_SPECULAR_TEXTURE_BIT) != 0u) {
             let specular =
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
-                bindless_textures_2d[material_indices[slot].specular_texture],
+                bindless_textures_2d_array[material_indices[slot].specular_texture],
                 bindless_samplers_filtering[material_indices[slot].specular_sampler],
 #else   // BINDLESS
                 pbr_bindings::specular_texture,
@@ -293,12 +286,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else   // STANDARD_MATERIAL_SPECULAR_UV_B
                 uv,
 #endif  // STANDARD_MATERIAL_SPECULAR_UV_B
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_index,
+                bias,
+                (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_SPECULAR_BIT) != 0u,
             ).a;
             // This 0.5 factor is from the `KHR_materials_specular` specification:
             // <https://github.com/KhronosGroup/glTF/tree/main/extensions/2.0/Khronos/KHR_materials_specular#materials-with-reflectance-parameter>
@@ -308,13 +298,9 @@ pbr_input.material.uv_transform = uv_transform;
         // Specular tint texture
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_SPECULAR_TINT_TEXTURE_BIT) != 0u) {
             let specular_tint =
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
-                bindless_textures_2d[material_indices[slot].specular_tint_texture],
+                bindless_textures_2d_array[material_indices[slot].specular_tint_texture],
                 bindless_samplers_filtering[material_indices[slot].specular_tint_sampler],
 #else   // BINDLESS
                 pbr_bindings::specular_tint_texture,
@@ -325,12 +311,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else   // STANDARD_MATERIAL_SPECULAR_TINT_UV_B
                 uv,
 #endif  // STANDARD_MATERIAL_SPECULAR_TINT_UV_B
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_index,
+                bias,
+                (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_SPECULAR_TINT_BIT) != 0u,
             ).rgb;
             pbr_input.material.reflectance *= specular_tint;
         }
@@ -348,11 +331,7 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef VERTEX_UVS
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_EMISSIVE_TEXTURE_BIT) != 0u) {
             emissive = vec4<f32>(emissive.rgb *
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
                     bindless_textures_2d_array[material_indices[slot].emissive_texture],
                     bindless_samplers_filtering[material_indices[slot].emissive_sampler],
@@ -365,14 +344,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                     uv,
 #endif
-                    // TODO: bevy_layered_materials index
                     layer_index,
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_EMISSIVE_BIT) != 0u,
                 ).rgb,
             emissive.a);
         }
@@ -391,11 +365,7 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef VERTEX_UVS
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_METALLIC_ROUGHNESS_TEXTURE_BIT) != 0u) {
             let metallic_roughness =
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
                     bindless_textures_2d_array[material_indices[slot].metallic_roughness_texture],
                     bindless_samplers_filtering[material_indices[slot].metallic_roughness_sampler],
@@ -408,14 +378,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                     uv,
 #endif
-                    // TODO: bevy_layered_materials index
                     layer_index,
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_METALLIC_ROUGHNESS_BIT) != 0u,
                 );
             // Sampling from GLTF standard channels for now
             metallic *= metallic_roughness.b;
@@ -437,13 +402,9 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef PBR_MULTI_LAYER_MATERIAL_TEXTURES_SUPPORTED
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_CLEARCOAT_TEXTURE_BIT) != 0u) {
             pbr_input.material.clearcoat *=
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
-                    bindless_textures_2d[material_indices[slot].clearcoat_texture],
+                    bindless_textures_2d_array[material_indices[slot].clearcoat_texture],
                     bindless_samplers_filtering[material_indices[slot].clearcoat_sampler],
 #else   // BINDLESS
                     pbr_bindings::clearcoat_texture,
@@ -454,12 +415,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                     uv,
 #endif
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    layer_index,
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_CLEARCOAT_BIT) != 0u,
                 ).r;
         }
 #endif  // PBR_MULTI_LAYER_MATERIAL_TEXTURES_SUPPORTED
@@ -478,13 +436,9 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef PBR_MULTI_LAYER_MATERIAL_TEXTURES_SUPPORTED
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_CLEARCOAT_ROUGHNESS_TEXTURE_BIT) != 0u) {
             pbr_input.material.clearcoat_perceptual_roughness *=
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
-                    bindless_textures_2d[material_indices[slot].clearcoat_roughness_texture],
+                    bindless_textures_2d_array[material_indices[slot].clearcoat_roughness_texture],
                     bindless_samplers_filtering[material_indices[slot].clearcoat_roughness_sampler],
 #else   // BINDLESS
                     pbr_bindings::clearcoat_roughness_texture,
@@ -495,19 +449,16 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                     uv,
 #endif
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    layer_index,
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_CLEARCOAT_ROUGHNESS_BIT) != 0u,
                 ).g;
         }
 #endif  // PBR_MULTI_LAYER_MATERIAL_TEXTURES_SUPPORTED
 #endif  // VERTEX_UVS
 
 #ifdef BINDLESS
-        var specular_transmission: f32 = pbr_bindings::material_array[slot].specular_transmission;
+        var specular_transmission: f32 = pbr_bindings::material_array[material_indices[slot].material].specular_transmission;
 #else   // BINDLESS
         var specular_transmission: f32 = pbr_bindings::material.specular_transmission;
 #endif  // BINDLESS
@@ -516,13 +467,9 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef PBR_TRANSMISSION_TEXTURES_SUPPORTED
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_SPECULAR_TRANSMISSION_TEXTURE_BIT) != 0u) {
             specular_transmission *=
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
-                    bindless_textures_2d[
+                    bindless_textures_2d_array[
                         material_indices[slot].specular_transmission_texture
                     ],
                     bindless_samplers_filtering[
@@ -537,12 +484,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                     uv,
 #endif
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    layer_index,
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_SPECULAR_TRANSMISSION_BIT) != 0u,
                 ).r;
         }
 #endif
@@ -559,13 +503,9 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef PBR_TRANSMISSION_TEXTURES_SUPPORTED
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_THICKNESS_TEXTURE_BIT) != 0u) {
             thickness *=
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
-                    bindless_textures_2d[material_indices[slot].thickness_texture],
+                    bindless_textures_2d_array[material_indices[slot].thickness_texture],
                     bindless_samplers_filtering[material_indices[slot].thickness_sampler],
 #else   // BINDLESS
                     pbr_bindings::thickness_texture,
@@ -576,12 +516,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                     uv,
 #endif
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    layer_index,
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_THICKNESS_BIT) != 0u,
                 ).g;
         }
 #endif
@@ -606,13 +543,9 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef PBR_TRANSMISSION_TEXTURES_SUPPORTED
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_DIFFUSE_TRANSMISSION_TEXTURE_BIT) != 0u) {
             diffuse_transmission *=
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
-                    bindless_textures_2d[material_indices[slot].diffuse_transmission_texture],
+                    bindless_textures_2d_array[material_indices[slot].diffuse_transmission_texture],
                     bindless_samplers_filtering[material_indices[slot].diffuse_transmission_sampler],
 #else   // BINDLESS
                     pbr_bindings::diffuse_transmission_texture,
@@ -623,12 +556,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                     uv,
 #endif
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    layer_index,
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_DIFFUSE_TRANSMISSION_BIT) != 0u,
                 ).a;
         }
 #endif
@@ -640,11 +570,7 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef VERTEX_UVS
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_OCCLUSION_TEXTURE_BIT) != 0u) {
             diffuse_occlusion *=
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
                     bindless_textures_2d_array[material_indices[slot].occlusion_texture],
                     bindless_samplers_filtering[material_indices[slot].occlusion_sampler],
@@ -657,14 +583,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                     uv,
 #endif
-                    // TODO: bevy_layered_materials index
                     layer_index,
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_OCCLUSION_BIT) != 0u,
                 ).r;
         }
 #endif
@@ -694,11 +615,7 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef STANDARD_MATERIAL_NORMAL_MAP
 
         let Nt =
-#ifdef MESHLET_MESH_MATERIAL_PASS
-            textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-            textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+            layer_sampling::sample_layer(
 #ifdef BINDLESS
                 bindless_textures_2d_array[material_indices[slot].normal_map_texture],
                 bindless_samplers_filtering[material_indices[slot].normal_map_sampler],
@@ -711,14 +628,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                 uv,
 #endif
-                // TODO: bevy_layered_materials index
                 layer_index,
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                bias.ddx_uv,
-                bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                bias,
+                (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_NORMAL_MAP_BIT) != 0u,
             ).rgb;
 
         pbr_input.N = pbr_functions::apply_normal_mapping(flags, TBN, double_sided, is_front, Nt);
@@ -734,13 +646,9 @@ pbr_input.material.uv_transform = uv_transform;
 #ifdef STANDARD_MATERIAL_CLEARCOAT_NORMAL_MAP
 
         let clearcoat_Nt =
-#ifdef MESHLET_MESH_MATERIAL_PASS
-            textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-            textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+            layer_sampling::sample_layer(
 #ifdef BINDLESS
-                bindless_textures_2d[material_indices[slot].clearcoat_normal_texture],
+                bindless_textures_2d_array[material_indices[slot].clearcoat_normal_texture],
                 bindless_samplers_filtering[material_indices[slot].clearcoat_normal_sampler],
 #else   // BINDLESS
                 pbr_bindings::clearcoat_normal_texture,
@@ -751,12 +659,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else
                 uv,
 #endif
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                bias.ddx_uv,
-                bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_index,
+                bias,
+                (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_CLEARCOAT_NORMAL_BIT) != 0u,
             ).rgb;
 
         pbr_input.clearcoat_N = pbr_functions::apply_normal_mapping(
@@ -795,13 +700,9 @@ pbr_input.material.uv_transform = uv_transform;
         // Adjust based on the anisotropy map if there is one.
         if ((flags & pbr_types::STANDARD_MATERIAL_FLAGS_ANISOTROPY_TEXTURE_BIT) != 0u) {
             let anisotropy_texel =
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-                textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_sampling::sample_layer(
 #ifdef BINDLESS
-                    bindless_textures_2d[material_indices[slot].anisotropy_texture],
+                    bindless_textures_2d_array[material_indices[slot].anisotropy_texture],
                     bindless_samplers_filtering[material_indices[slot].anisotropy_sampler],
 #else   // BINDLESS
                     pbr_bindings::anisotropy_texture,
@@ -812,12 +713,9 @@ pbr_input.material.uv_transform = uv_transform;
 #else   // STANDARD_MATERIAL_ANISOTROPY_UV_B
                     uv,
 #endif  // STANDARD_MATERIAL_ANISOTROPY_UV_B
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                    bias.ddx_uv,
-                    bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                    bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                    layer_index,
+                    bias,
+                    (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_ANISOTROPY_BIT) != 0u,
                 ).rgb;
 
             let anisotropy_direction_from_texture = normalize(anisotropy_texel.rg * 2.0 - 1.0);
@@ -856,4 +754,56 @@ pbr_input.material.uv_transform = uv_transform;
     }
 
     return pbr_input;
-}
\ No newline at end of file
+}
+// Builds the PbrInput for an instance's layers, sampling every texture a
+// second time only when the instance blends in a second layer.
+fn pbr_input_from_material_layers(
+    in: VertexOutput,
+    is_front: bool,
+    layers: pbr_bindings::MaterialLayers,
+) -> pbr_types::PbrInput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front, layers.first);
+    if layers.blend > 0.0 && layers.second != layers.first {
+        let second = pbr_input_from_standard_material(in, is_front, layers.second);
+        pbr_input = mix_pbr_inputs(pbr_input, second, layers.blend);
+    }
+    return pbr_input;
+}
+
+// Mixes the members of two PbrInputs that the textures can change. Both
+// come from the same material, so everything else is identical.
+fn mix_pbr_inputs(
+    a: pbr_types::PbrInput,
+    b: pbr_types::PbrInput,
+    blend: f32,
+) -> pbr_types::PbrInput {
+    var pbr_input = a;
+
+    pbr_input.material.base_color = mix(a.material.base_color, b.material.base_color, blend);
+    pbr_input.material.emissive = mix(a.material.emissive, b.material.emissive, blend);
+    pbr_input.material.reflectance = mix(a.material.reflectance, b.material.reflectance, blend);
+    pbr_input.material.perceptual_roughness =
+        mix(a.material.perceptual_roughness, b.material.perceptual_roughness, blend);
+    pbr_input.material.metallic = mix(a.material.metallic, b.material.metallic, blend);
+    pbr_input.material.diffuse_transmission =
+        mix(a.material.diffuse_transmission, b.material.diffuse_transmission, blend);
+    pbr_input.material.specular_transmission =
+        mix(a.material.specular_transmission, b.material.specular_transmission, blend);
+    pbr_input.material.thickness = mix(a.material.thickness, b.material.thickness, blend);
+    pbr_input.material.clearcoat = mix(a.material.clearcoat, b.material.clearcoat, blend);
+    pbr_input.material.clearcoat_perceptual_roughness = mix(
+        a.material.clearcoat_perceptual_roughness,
+        b.material.clearcoat_perceptual_roughness,
+        blend,
+    );
+
+    pbr_input.diffuse_occlusion = mix(a.diffuse_occlusion, b.diffuse_occlusion, blend);
+    pbr_input.specular_occlusion = mix(a.specular_occlusion, b.specular_occlusion, blend);
+    pbr_input.N = normalize(mix(a.N, b.N, blend));
+    pbr_input.clearcoat_N = normalize(mix(a.clearcoat_N, b.clearcoat_N, blend));
+    pbr_input.anisotropy_strength = mix(a.anisotropy_strength, b.anisotropy_strength, blend);
+    pbr_input.anisotropy_T = mix(a.anisotropy_T, b.anisotropy_T, blend);
+    pbr_input.anisotropy_B = mix(a.anisotropy_B, b.anisotropy_B, blend);
+
+    return pbr_input;
+}
@@ -1,5 +1,5 @@
 #import bevy_pbr::{
-    pbr_prepass_functions,
+    //pbr_prepass_functions,
     //pbr_bindings,
     //pbr_bindings::material,
     //pbr_types,
@@ -10,7 +10,8 @@
     mesh_view_bindings::view,
 }
 #import bevy_layered_materials::{
-    //pbr_prepass_functions,
+    layer_sampling,
+    pbr_prepass_functions,
     pbr_bindings,
     pbr_bindings::material,
     pbr_types,
@@ -21,14 +22,14 @@
     //mesh_view_bindings::view,
 }
 
-#import bevy_render::bindless::{bindless_samplers_filtering, bindless_textures_2d}
+#import bevy_render::bindless::{bindless_samplers_filtering, bindless_textures_2d_array}
 
 #ifdef MESHLET_MESH_MATERIAL_PASS
 #import bevy_pbr::meshlet_visibility_buffer_resolve::resolve_vertex_output
 #endif
 
 #ifdef BINDLESS
-#import bevy_pbr::pbr_bindings::material_indices
+#import bevy_layered_materials::pbr_bindings::material_indices
 #endif  // BINDLESS
 
 #ifdef PREPASS_FRAGMENT
@@ -46,12 +47,19 @@ fn fragment(
     let is_front = true;
 #else   // MESHLET_MESH_MATERIAL_PASS
 
-#ifdef BINDLESS
     let slot = mesh[in.instance_index].material_and_lightmap_bind_group_slot & 0xffffu;
+    let layer_index = pbr_bindings::dominant_layer(
+        pbr_bindings::material_layers(in.instance_index, slot)
+    );
+
+#ifdef BINDLESS
     let flags = pbr_bindings::material_array[material_indices[slot].material].flags;
+    let stochastic_textures =
+        pbr_bindings::material_array[material_indices[slot].material].stochastic_textures;
     let uv_transform = pbr_bindings::material_array[material_indices[slot].material].uv_transform;
 #else   // BINDLESS
     let flags = pbr_bindings::material.flags;
+    let stochastic_textures = pbr_bindings::material.stochastic_textures;
     let uv_transform = pbr_bindings::material.uv_transform;
 #endif  // BINDLESS
 
@@ -61,7 +69,7 @@ fn fragment(
     pbr_functions::visibility_range_dither(in.position, in.visibility_range_dither);
 #endif  // VISIBILITY_RANGE_DITHER
 
-    pbr_prepass_functions::prepass_alpha_discard(in);
+    pbr_prepass_functions::prepass_alpha_discard(in, layer_index);
 #endif  // MESHLET_MESH_MATERIAL_PASS
 
     var out: prepass_io::FragmentOutput;
@@ -104,25 +112,18 @@ fn fragment(
 #endif  // MESHLET_MESH_MATERIAL_PASS
 
         let Nt =
-#ifdef MESHLET_MESH_MATERIAL_PASS
-            textureSampleGrad(
-#else   // MESHLET_MESH_MATERIAL_PASS
-            textureSampleBias(
-#endif  // MESHLET_MESH_MATERIAL_PASS
+            layer_sampling::sample_layer(
 #ifdef BINDLESS
-                bindless_textures_2d[material_indices[slot].normal_map_texture],
+                bindless_textures_2d_array[material_indices[slot].normal_map_texture],
                 bindless_samplers_filtering[material_indices[slot].normal_map_sampler],
 #else   // BINDLESS
                 pbr_bindings::normal_map_texture,
                 pbr_bindings::normal_map_sampler,
 #endif  // BINDLESS
                 uv,
-#ifdef MESHLET_MESH_MATERIAL_PASS
-                bias.ddx_uv,
-                bias.ddy_uv,
-#else   // MESHLET_MESH_MATERIAL_PASS
-                bias.mip_bias,
-#endif  // MESHLET_MESH_MATERIAL_PASS
+                layer_index,
+                bias,
+                (stochastic_textures & pbr_types::STOCHASTIC_TEXTURE_NORMAL_MAP_BIT) != 0u,
             ).rgb;
         let TBN = pbr_functions::calculate_tbn_mikktspace(normal, in.world_tangent);
 
@@ -157,6 +158,8 @@ fn fragment(
 #else
 @fragment
 fn fragment(in: prepass_io::VertexOutput) {
-    pbr_prepass_functions::prepass_alpha_discard(in);
+    let slot = mesh[in.instance_index].material_and_lightmap_bind_group_slot & 0xffffu;
+    let layers = pbr_bindings::material_layers(in.instance_index, slot);
+    pbr_prepass_functions::prepass_alpha_discard(in, pbr_bindings::dominant_layer(layers));
 }
 #endif // PREPASS_FRAGMENT
\ No newline at end of file
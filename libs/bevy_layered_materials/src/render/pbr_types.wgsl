@@ -29,6 +29,8 @@ struct StandardMaterial {
     /// ID for specifying which deferred lighting pass should be used for rendering this material, if any.
     deferred_lighting_pass_id: u32,
     layer_index: u32,
+    // 'stochastic_textures' is a bit field of the textures sampled with hex-tiling.
+    stochastic_textures: u32,
 };
 
 // !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
@@ -64,6 +66,23 @@ const STANDARD_MATERIAL_FLAGS_ALPHA_MODE_ADD: u32                    = 4u << 29u
 const STANDARD_MATERIAL_FLAGS_ALPHA_MODE_MULTIPLY: u32               = 5u << 29u;
 const STANDARD_MATERIAL_FLAGS_ALPHA_MODE_ALPHA_TO_COVERAGE: u32      = 6u << 29u;
 
+// NOTE: if these flags are updated or changed. Be sure to also update
+// `StochasticTextures` in src/pbr_material.rs!
+const STOCHASTIC_TEXTURE_BASE_COLOR_BIT: u32                         = 1u << 0u;
+const STOCHASTIC_TEXTURE_EMISSIVE_BIT: u32                           = 1u << 1u;
+const STOCHASTIC_TEXTURE_METALLIC_ROUGHNESS_BIT: u32                 = 1u << 2u;
+const STOCHASTIC_TEXTURE_OCCLUSION_BIT: u32                          = 1u << 3u;
+const STOCHASTIC_TEXTURE_NORMAL_MAP_BIT: u32                         = 1u << 4u;
+const STOCHASTIC_TEXTURE_ANISOTROPY_BIT: u32                         = 1u << 5u;
+const STOCHASTIC_TEXTURE_DIFFUSE_TRANSMISSION_BIT: u32               = 1u << 6u;
+const STOCHASTIC_TEXTURE_SPECULAR_TRANSMISSION_BIT: u32              = 1u << 7u;
+const STOCHASTIC_TEXTURE_THICKNESS_BIT: u32                          = 1u << 8u;
+const STOCHASTIC_TEXTURE_CLEARCOAT_BIT: u32                          = 1u << 9u;
+const STOCHASTIC_TEXTURE_CLEARCOAT_ROUGHNESS_BIT: u32                = 1u << 10u;
+const STOCHASTIC_TEXTURE_CLEARCOAT_NORMAL_BIT: u32                   = 1u << 11u;
+const STOCHASTIC_TEXTURE_SPECULAR_BIT: u32                           = 1u << 12u;
+const STOCHASTIC_TEXTURE_SPECULAR_TINT_BIT: u32                      = 1u << 13u;
+
 
 // Creates a StandardMaterial with default values
 fn standard_material_new() -> StandardMaterial {
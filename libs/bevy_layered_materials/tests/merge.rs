@@ -0,0 +1,141 @@
+//! `UseLayeredMaterial` stacking materials that load in different frames
+//! into one set of array textures.
+
+use bevy_app::{App, TaskPoolPlugin};
+use bevy_asset::{AssetApp, AssetPlugin, Assets, Handle, RenderAssetUsages};
+use bevy_ecs::prelude::*;
+use bevy_image::Image;
+use bevy_layered_materials::{LayeredMaterial, LayeredMaterialsPlugin, UseLayeredMaterial};
+use bevy_pbr::{MeshMaterial3d, StandardMaterial};
+use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_shader::Shader;
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+        .init_asset::<Shader>()
+        .init_asset::<Image>()
+        .init_asset::<StandardMaterial>()
+        .add_plugins(LayeredMaterialsPlugin);
+    app
+}
+
+/// A material with a `size` x `size` base color texture of one texel.
+fn material(app: &mut App, size: u32, texel: [u8; 4]) -> Handle<StandardMaterial> {
+    let image = Image::new_fill(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &texel,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    let image = app.world_mut().resource_mut::<Assets<Image>>().add(image);
+    app.world_mut()
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(StandardMaterial {
+            base_color_texture: Some(image),
+            ..Default::default()
+        })
+}
+
+fn tagged(app: &mut App, material: &Handle<StandardMaterial>) -> Entity {
+    let entity = app
+        .world_mut()
+        .spawn((UseLayeredMaterial, MeshMaterial3d(material.clone())))
+        .id();
+    app.update();
+    entity
+}
+
+fn layered(app: &App, entity: Entity) -> (Handle<LayeredMaterial>, LayeredMaterial) {
+    let handle = app
+        .world()
+        .get::<MeshMaterial3d<LayeredMaterial>>(entity)
+        .expect("the entity was converted")
+        .0
+        .clone();
+    let material = app
+        .world()
+        .resource::<Assets<LayeredMaterial>>()
+        .get(&handle)
+        .unwrap()
+        .clone();
+    (handle, material)
+}
+
+fn layers(app: &App, material: &LayeredMaterial) -> u32 {
+    let array = material.base_color_texture.as_ref().unwrap();
+    app.world()
+        .resource::<Assets<Image>>()
+        .get(array)
+        .unwrap()
+        .texture_descriptor
+        .size
+        .depth_or_array_layers
+}
+
+#[test]
+fn later_materials_join_the_same_arrays() {
+    let mut app = app();
+    let red = material(&mut app, 2, [255, 0, 0, 255]);
+    let blue = material(&mut app, 2, [0, 0, 255, 255]);
+
+    let first = tagged(&mut app, &red);
+    let (_, before) = layered(&app, first);
+    assert_eq!(before.layer_index, 0);
+    assert_eq!(layers(&app, &before), 1);
+
+    let second = tagged(&mut app, &blue);
+    let (_, red_layer) = layered(&app, first);
+    let (_, blue_layer) = layered(&app, second);
+    assert_eq!(blue_layer.layer_index, 1);
+    assert_eq!(red_layer.base_color_texture, blue_layer.base_color_texture);
+    // the array grew in place
+    assert_eq!(red_layer.base_color_texture, before.base_color_texture);
+    assert_eq!(layers(&app, &red_layer), 2);
+}
+
+#[test]
+fn converted_materials_are_reused() {
+    let mut app = app();
+    let red = material(&mut app, 2, [255, 0, 0, 255]);
+
+    let first = tagged(&mut app, &red);
+    // a second instance of the same scene, spawned later
+    let second = tagged(&mut app, &red);
+
+    assert_eq!(layered(&app, first).0, layered(&app, second).0);
+    assert_eq!(app.world().resource::<Assets<LayeredMaterial>>().len(), 1);
+    assert_eq!(layers(&app, &layered(&app, second).1), 1);
+}
+
+#[test]
+fn materials_that_dont_stack_are_left_alone() {
+    let mut app = app();
+    let red = material(&mut app, 2, [255, 0, 0, 255]);
+    let large = material(&mut app, 4, [0, 255, 0, 255]);
+    let blue = material(&mut app, 2, [0, 0, 255, 255]);
+
+    let first = tagged(&mut app, &red);
+    let mismatched = tagged(&mut app, &large);
+    let last = tagged(&mut app, &blue);
+
+    assert_eq!(
+        app.world()
+            .get::<MeshMaterial3d<StandardMaterial>>(mismatched)
+            .map(|material| material.id()),
+        Some(large.id())
+    );
+    assert!(app.world().get::<UseLayeredMaterial>(mismatched).is_some());
+    // the others keep their textures, and still share them
+    let (_, red_layer) = layered(&app, first);
+    let (_, blue_layer) = layered(&app, last);
+    assert!(red_layer.base_color_texture.is_some());
+    assert_eq!(red_layer.base_color_texture, blue_layer.base_color_texture);
+    assert_eq!(blue_layer.layer_index, 1);
+    assert_eq!(layers(&app, &blue_layer), 2);
+}
@@ -0,0 +1,69 @@
+//! `LayerWeights::to_mesh_tag` has to pack layers the way
+//! `pbr_bindings::material_layers` unpacks them.
+
+use bevy_layered_materials::{LayerWeights, MaterialLayer, MAX_INSTANCE_LAYER};
+
+/// The same arithmetic as `material_layers` in `pbr_bindings.wgsl`, with
+/// `None` for a tag that falls back to the material's `layer_index`.
+fn unpack(tag: u32) -> Option<(u32, u32, f32)> {
+    if tag & 0xff == 0 {
+        return None;
+    }
+    Some((
+        (tag & 0xff) - 1,
+        ((tag >> 8) & 0xff) - 1,
+        (tag >> 16) as f32 / 65535.0,
+    ))
+}
+
+fn round_trip(weights: LayerWeights) -> (u32, u32, f32) {
+    unpack(weights.to_mesh_tag().0).expect("a packed tag never falls back to the material")
+}
+
+#[test]
+fn weights_round_trip() {
+    for (first, second, blend) in [
+        (0, 0, 0.0),
+        (0, 1, 1.0),
+        (3, 7, 0.25),
+        (MAX_INSTANCE_LAYER, 0, 0.5),
+        (12, MAX_INSTANCE_LAYER, 0.75),
+    ] {
+        let (unpacked_first, unpacked_second, unpacked_blend) = round_trip(LayerWeights {
+            first,
+            second,
+            blend,
+        });
+        assert_eq!((unpacked_first, unpacked_second), (first, second));
+        assert!(
+            (unpacked_blend - blend).abs() <= 0.5 / 65535.0,
+            "{blend} came back as {unpacked_blend}"
+        );
+    }
+}
+
+#[test]
+fn material_layer_uses_one_layer() {
+    let weights = LayerWeights::from(MaterialLayer(9));
+    assert_eq!(round_trip(weights), (9, 9, 0.0));
+}
+
+#[test]
+fn out_of_range_weights_are_clamped() {
+    let (first, second, blend) = round_trip(LayerWeights {
+        first: MAX_INSTANCE_LAYER + 1,
+        second: u32::MAX,
+        blend: 1.5,
+    });
+    assert_eq!(
+        (first, second, blend),
+        (MAX_INSTANCE_LAYER, MAX_INSTANCE_LAYER, 1.0)
+    );
+
+    let (.., blend) = round_trip(LayerWeights {
+        first: 0,
+        second: 1,
+        blend: -0.5,
+    });
+    assert_eq!(blend, 0.0);
+}
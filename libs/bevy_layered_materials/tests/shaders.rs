@@ -0,0 +1,107 @@
+//! Composes the `LayeredMaterial` shaders through naga_oil, the same way the
+//! `PipelineCache` does at runtime, for every `LayeredMaterialKey`
+//! combination. Runs on the CPU only, no adapter is needed.
+
+use bevy_layered_materials::LayeredMaterialKey;
+use bevy_shader_validation::{
+    assert_no_failures, compose_all,
+    defs::{self, Pass, Platform, PBR_TEXTURE_FEATURES},
+    ShaderDefVal, ShaderLibrary, BEVY_SHADER_CRATES,
+};
+
+/// The texture defs for the cargo features this crate was built with.
+fn texture_feature_defs() -> Vec<ShaderDefVal> {
+    [
+        cfg!(feature = "pbr_transmission_textures"),
+        cfg!(feature = "pbr_multi_layer_material_textures"),
+        cfg!(feature = "pbr_anisotropy_texture"),
+        cfg!(feature = "pbr_specular_textures"),
+    ]
+    .into_iter()
+    .zip(PBR_TEXTURE_FEATURES)
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, shader_def)| shader_def.into())
+    .collect()
+}
+
+/// Every key flag on its own, all of them at once, and every combination
+/// of the flags that switch features on. The uv flags only change which
+/// uv a texture is sampled with, so they're covered on their own.
+///
+/// Flags for textures behind a disabled cargo feature are left out, since
+/// the material can't set them.
+fn key_matrix() -> Vec<LayeredMaterialKey> {
+    const FEATURES: [LayeredMaterialKey; 7] = [
+        LayeredMaterialKey::NORMAL_MAP,
+        LayeredMaterialKey::RELIEF_MAPPING,
+        LayeredMaterialKey::DIFFUSE_TRANSMISSION,
+        LayeredMaterialKey::SPECULAR_TRANSMISSION,
+        LayeredMaterialKey::CLEARCOAT,
+        LayeredMaterialKey::CLEARCOAT_NORMAL_MAP,
+        LayeredMaterialKey::ANISOTROPY,
+    ];
+
+    let mut unreachable = LayeredMaterialKey::empty();
+    if !cfg!(feature = "pbr_multi_layer_material_textures") {
+        unreachable |= LayeredMaterialKey::CLEARCOAT_NORMAL_MAP;
+    }
+
+    let combinations = (0..1u32 << FEATURES.len()).map(|combination| {
+        FEATURES
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| combination & (1 << bit) != 0)
+            .fold(LayeredMaterialKey::empty(), |key, (_, flag)| key | *flag)
+    });
+
+    let mut keys = vec![];
+    for key in LayeredMaterialKey::all()
+        .iter()
+        .chain([LayeredMaterialKey::all()])
+        .chain(combinations)
+    {
+        let key = key.difference(unreachable);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+#[test]
+fn every_key_composes() {
+    let mut library = ShaderLibrary::new(env!("CARGO_MANIFEST_DIR"))
+        .with_crates(&BEVY_SHADER_CRATES)
+        .with_crates(&["bevy_shader_utils"])
+        .with_dir("src");
+    let mut failures = vec![];
+
+    for key in key_matrix() {
+        for pass in Pass::ALL {
+            let shader = match pass {
+                Pass::Prepass => "src/render/pbr_prepass.wgsl",
+                Pass::Forward | Pass::Deferred => "src/render/pbr.wgsl",
+            };
+            for platform in Platform::ALL {
+                let mut base = defs::mesh_pipeline(pass, platform);
+                base.push("MAY_DISCARD".into());
+                base.extend(texture_feature_defs());
+                // the same defs `Material::specialize` adds
+                base.extend(key.shader_defs());
+
+                // bindless needs storage buffers
+                let bindless: &[&str] = match platform {
+                    Platform::Native => &["BINDLESS"],
+                    Platform::WebGl2 => &[],
+                };
+                failures.extend(
+                    compose_all(&mut library, shader, defs::permutations(&base, bindless))
+                        .into_iter()
+                        .map(|failure| format!("key {:#x}: {failure}", key.bits())),
+                );
+            }
+        }
+    }
+
+    assert_no_failures(&failures);
+}
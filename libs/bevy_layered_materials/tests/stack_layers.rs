@@ -0,0 +1,122 @@
+//! Stacking loaded images into the array textures `UseLayeredMaterial`
+//! shares between layers.
+
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_layered_materials::{stack_layers, LayerStackError};
+use bevy_render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDimension,
+};
+
+fn image(width: u32, height: u32, format: TextureFormat, texel: &[u8]) -> Image {
+    let size = Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    Image::new_fill(
+        size,
+        TextureDimension::D2,
+        texel,
+        format,
+        RenderAssetUsages::default(),
+    )
+}
+
+fn rgba(width: u32, height: u32, texel: [u8; 4]) -> Image {
+    image(width, height, TextureFormat::Rgba8UnormSrgb, &texel)
+}
+
+#[test]
+fn layers_are_stacked_in_order() {
+    let red = rgba(2, 2, [255, 0, 0, 255]);
+    let blue = rgba(2, 2, [0, 0, 255, 255]);
+
+    let stacked = stack_layers(&[Some(&red), None, Some(&blue)], [1, 2, 3, 4]).unwrap();
+
+    assert_eq!(stacked.texture_descriptor.size.depth_or_array_layers, 3);
+    assert_eq!(
+        stacked.texture_view_descriptor.unwrap().dimension,
+        Some(TextureViewDimension::D2Array)
+    );
+    let data = stacked.data.unwrap();
+    let layers: Vec<&[u8]> = data.chunks(16).collect();
+    assert_eq!(layers[0], red.data.as_deref().unwrap());
+    assert_eq!(layers[1], [1, 2, 3, 4].repeat(4));
+    assert_eq!(layers[2], blue.data.as_deref().unwrap());
+}
+
+#[test]
+fn no_layers_is_an_error() {
+    assert_eq!(stack_layers(&[], [0; 4]), Err(LayerStackError::Empty));
+    assert_eq!(
+        stack_layers(&[None, None], [0; 4]),
+        Err(LayerStackError::Empty)
+    );
+}
+
+#[test]
+fn sizes_must_match() {
+    let small = rgba(2, 2, [0; 4]);
+    let large = rgba(4, 2, [0; 4]);
+
+    assert_eq!(
+        stack_layers(&[Some(&small), None, Some(&large)], [0; 4]),
+        Err(LayerStackError::SizeMismatch {
+            layer: 2,
+            expected: small.texture_descriptor.size,
+            found: large.texture_descriptor.size,
+        })
+    );
+}
+
+#[test]
+fn formats_must_match() {
+    let srgb = rgba(2, 2, [0; 4]);
+    let linear = image(2, 2, TextureFormat::Rgba8Unorm, &[0; 4]);
+
+    assert_eq!(
+        stack_layers(&[Some(&srgb), Some(&linear)], [0; 4]),
+        Err(LayerStackError::FormatMismatch {
+            layer: 1,
+            expected: TextureFormat::Rgba8UnormSrgb,
+            found: TextureFormat::Rgba8Unorm,
+        })
+    );
+}
+
+#[test]
+fn layers_must_be_single_2d_images() {
+    let flat = rgba(2, 2, [0; 4]);
+    let mut array = rgba(2, 2, [0; 4]);
+    array.texture_descriptor.size.depth_or_array_layers = 2;
+
+    assert_eq!(
+        stack_layers(&[Some(&flat), Some(&array)], [0; 4]),
+        Err(LayerStackError::NotSingle2d { layer: 1 })
+    );
+}
+
+#[test]
+fn layers_need_pixel_data() {
+    let mut empty = rgba(2, 2, [0; 4]);
+    empty.data = None;
+
+    assert_eq!(
+        stack_layers(&[None, Some(&empty)], [0; 4]),
+        Err(LayerStackError::MissingData { layer: 1 })
+    );
+}
+
+#[test]
+fn only_rgba8_layers_can_be_filled() {
+    let float = image(2, 2, TextureFormat::R32Float, &1f32.to_le_bytes());
+
+    assert!(stack_layers(&[Some(&float), Some(&float)], [0; 4]).is_ok());
+    assert_eq!(
+        stack_layers(&[Some(&float), None], [0; 4]),
+        Err(LayerStackError::UnsupportedFillFormat(
+            TextureFormat::R32Float
+        ))
+    );
+}
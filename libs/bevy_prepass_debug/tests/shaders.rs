@@ -0,0 +1,53 @@
+//! Composes the prepass debug shader and the example's material through
+//! naga_oil, the same way the `PipelineCache` does at runtime. Runs on the
+//! CPU only.
+
+use bevy_shader_validation::{
+    BEVY_SHADER_CRATES, ShaderLibrary, assert_no_failures, compose_all,
+    defs::{self, Pass, Platform},
+};
+
+fn library() -> ShaderLibrary {
+    ShaderLibrary::new(env!("CARGO_MANIFEST_DIR"))
+        .with_crates(&BEVY_SHADER_CRATES)
+        .with_dir("assets/shaders")
+}
+
+/// The debug quad reads every prepass texture, so it's only drawn by
+/// cameras with all three prepasses.
+#[test]
+fn show_prepass_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    for platform in Platform::ALL {
+        let mut base = defs::mesh_pipeline(Pass::Forward, platform);
+        base.extend(["DEPTH_PREPASS", "NORMAL_PREPASS", "MOTION_VECTOR_PREPASS"].map(Into::into));
+        failures.extend(compose_all(
+            &mut library,
+            "assets/shaders/show_prepass.wgsl",
+            defs::permutations(&base, &["MULTISAMPLED"]),
+        ));
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn custom_material_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    for platform in Platform::ALL {
+        failures.extend(compose_all(
+            &mut library,
+            "assets/shaders/custom_material.wgsl",
+            defs::permutations(
+                &defs::mesh_pipeline(Pass::Forward, platform),
+                &["MAY_DISCARD"],
+            ),
+        ));
+    }
+
+    assert_no_failures(&failures);
+}
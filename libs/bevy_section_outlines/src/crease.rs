@@ -0,0 +1,221 @@
+use bevy::{
+    mesh::{Indices, MeshAccessError, PrimitiveTopology, VertexAttributeValues},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use thiserror::Error;
+
+use crate::ATTRIBUTE_SECTION_COLOR;
+
+/// How [`generate_section_colors`] splits a mesh into
+/// sections.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Default, Debug, PartialEq)]
+pub struct CreaseSections {
+    /// Neighbouring faces meeting at a sharper angle than
+    /// this, in radians, are in different sections.
+    pub crease_angle: f32,
+    /// The index of the first section's color. Meshes
+    /// outlined as one [`OutlineGroup`](crate::OutlineGroup)
+    /// can continue where the previous one stopped, so
+    /// their sections stay distinct.
+    pub first_section: u32,
+}
+
+impl Default for CreaseSections {
+    fn default() -> Self {
+        Self {
+            crease_angle: 30f32.to_radians(),
+            first_section: 0,
+        }
+    }
+}
+
+/// Why a mesh couldn't be split into sections.
+#[derive(Error, Debug, Clone)]
+pub enum SectionColorError {
+    #[error("only triangle lists can be split into sections, not {0:?}")]
+    UnsupportedTopology(PrimitiveTopology),
+    #[error("the mesh has no Float32x3 positions")]
+    MissingPositions,
+    #[error(transparent)]
+    MeshAccess(#[from] MeshAccessError),
+}
+
+/// Splits `mesh` into sections wherever neighbouring
+/// faces meet at more than
+/// [`CreaseSections::crease_angle`], and writes a distinct
+/// [`ATTRIBUTE_SECTION_COLOR`] for each section.
+///
+/// Faces are neighbours when they share an edge
+/// position, so seams in the uvs or normals don't split
+/// a section. Vertices on a crease are duplicated, one
+/// per section, and the mesh is indexed afterwards.
+/// Vertices no triangle uses are dropped.
+///
+/// Returns the number of sections.
+///
+/// ```ignore
+/// let mut mesh = Cuboid::default().mesh().build();
+/// let sections = generate_section_colors(&mut mesh, CreaseSections::default())?;
+/// assert_eq!(sections, 6);
+/// ```
+pub fn generate_section_colors(
+    mesh: &mut Mesh,
+    sections: CreaseSections,
+) -> Result<u32, SectionColorError> {
+    let topology = mesh.primitive_topology();
+    if topology != PrimitiveTopology::TriangleList {
+        return Err(SectionColorError::UnsupportedTopology(topology));
+    }
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.try_attribute_option(Mesh::ATTRIBUTE_POSITION)?
+    else {
+        return Err(SectionColorError::MissingPositions);
+    };
+    let positions: Vec<Vec3> = positions.iter().copied().map(Vec3::from).collect();
+
+    let corners: Vec<u32> = match mesh.try_indices_option()? {
+        Some(indices) => indices.iter().map(|index| index as u32).collect(),
+        None => (0..positions.len() as u32).collect(),
+    };
+    let triangles: Vec<[u32; 3]> = corners
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect();
+
+    // vertices at the same position are one vertex
+    // while looking for neighbours
+    let mut welded_ids = HashMap::new();
+    let welded: Vec<u32> = positions
+        .iter()
+        .map(|position| {
+            // +0. and -0. are the same position
+            let key = (*position + Vec3::ZERO).to_array().map(f32::to_bits);
+            let next_id = welded_ids.len() as u32;
+            *welded_ids.entry(key).or_insert(next_id)
+        })
+        .collect();
+
+    let normals: Vec<Vec3> = triangles
+        .iter()
+        .map(|&[a, b, c]| {
+            let [a, b, c] = [a, b, c].map(|index| positions[index as usize]);
+            (b - a).cross(c - a).normalize_or_zero()
+        })
+        .collect();
+
+    let mut edges: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (face, triangle) in triangles.iter().enumerate() {
+        for (start, end) in [(0, 1), (1, 2), (2, 0)] {
+            let (start, end) = (
+                welded[triangle[start] as usize],
+                welded[triangle[end] as usize],
+            );
+            if start != end {
+                edges
+                    .entry((start.min(end), start.max(end)))
+                    .or_default()
+                    .push(face);
+            }
+        }
+    }
+
+    // join every pair of neighbours that isn't creased.
+    // Degenerate faces have no normal and join anything.
+    let min_cos = sections.crease_angle.cos();
+    let mut faces = UnionFind::new(triangles.len());
+    for neighbours in edges.values() {
+        for (i, &a) in neighbours.iter().enumerate() {
+            for &b in &neighbours[i + 1..] {
+                let (normal_a, normal_b) = (normals[a], normals[b]);
+                if normal_a == Vec3::ZERO
+                    || normal_b == Vec3::ZERO
+                    || normal_a.dot(normal_b) >= min_cos
+                {
+                    faces.union(a, b);
+                }
+            }
+        }
+    }
+
+    // number the sections in the order their first
+    // face appears, so the colors are stable
+    let mut section_ids = HashMap::new();
+    let face_sections: Vec<u32> = (0..triangles.len())
+        .map(|face| {
+            let next_id = section_ids.len() as u32;
+            *section_ids.entry(faces.find(face)).or_insert(next_id)
+        })
+        .collect();
+
+    // one vertex per original vertex and section it's
+    // used in
+    let mut split_vertices: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut sources = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::with_capacity(triangles.len() * 3);
+    for (triangle, &section) in triangles.iter().zip(&face_sections) {
+        for &vertex in triangle {
+            let index = *split_vertices.entry((vertex, section)).or_insert_with(|| {
+                sources.push(vertex);
+                colors.push(section_color(sections.first_section + section));
+                sources.len() as u32 - 1
+            });
+            indices.push(index);
+        }
+    }
+
+    // duplicate_vertices gathers every attribute through
+    // the indices, so index the mesh by the source
+    // vertices first
+    mesh.try_insert_indices(Indices::U32(sources))?;
+    mesh.try_duplicate_vertices()?;
+    mesh.try_insert_attribute(ATTRIBUTE_SECTION_COLOR, colors)?;
+    let indices = if split_vertices.len() <= u16::MAX as usize {
+        Indices::U16(indices.into_iter().map(|index| index as u16).collect())
+    } else {
+        Indices::U32(indices)
+    };
+    mesh.try_insert_indices(indices)?;
+
+    Ok(section_ids.len() as u32)
+}
+
+/// The section color of the `index`th section.
+///
+/// Colors step around by the golden ratio, so the
+/// sections numbered next to each other, which are
+/// usually neighbours too, get colors far apart. They
+/// stay clear of 0., which is "no stroke".
+fn section_color(index: u32) -> [f32; 4] {
+    const GOLDEN_RATIO: f64 = 0.618_033_988_749_895;
+    let red = 0.05 + 0.95 * (f64::from(index) * GOLDEN_RATIO).fract();
+    [red as f32, 0., 0., 1.]
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, mut item: usize) -> usize {
+        while self.parents[item] != item {
+            // path halving
+            self.parents[item] = self.parents[self.parents[item]];
+            item = self.parents[item];
+        }
+        item
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
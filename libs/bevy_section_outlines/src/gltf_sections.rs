@@ -0,0 +1,158 @@
+use bevy::{
+    asset::LoadContext,
+    gltf::extensions::{GltfExtensionHandler, GltfExtensionHandlers},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{ATTRIBUTE_SECTION_COLOR, CreaseSections, generate_section_colors};
+
+/// Generates section colors for every glTF primitive
+/// without an authored `SECTION_COLOR` attribute when
+/// its scene spawns, using [`generate_section_colors`].
+///
+/// ```ignore
+/// App::new().add_plugins((
+///     DefaultPlugins,
+///     SectionOutlinePlugin,
+///     GltfCreaseSectionsPlugin {
+///         split_materials: true,
+///         ..default()
+///     },
+/// ));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct GltfCreaseSectionsPlugin {
+    /// See [`CreaseSections::crease_angle`]
+    pub crease_angle: f32,
+    /// Numbers the sections of a glTF mesh's primitives
+    /// one after the other, so there's an outline where
+    /// the material changes even when the primitives
+    /// share an [`OutlineGroup`](crate::OutlineGroup).
+    pub split_materials: bool,
+}
+
+impl Default for GltfCreaseSectionsPlugin {
+    fn default() -> Self {
+        Self {
+            crease_angle: CreaseSections::default().crease_angle,
+            split_materials: false,
+        }
+    }
+}
+
+impl Plugin for GltfCreaseSectionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GltfExtensionHandlers>()
+            .insert_resource(GltfCreaseSections(*self))
+            // after scenes are spawned
+            .add_systems(PostUpdate, generate_gltf_section_colors);
+
+        app.world_mut()
+            .resource_mut::<GltfExtensionHandlers>()
+            .0
+            .write_blocking()
+            .push(Box::new(CreaseSectionsExtension));
+    }
+}
+
+#[derive(Resource)]
+struct GltfCreaseSections(GltfCreaseSectionsPlugin);
+
+/// A glTF primitive that needs section colors, and its
+/// index in its glTF mesh.
+#[derive(Component)]
+struct PendingCreaseSections {
+    primitive: usize,
+}
+
+#[derive(Clone)]
+struct CreaseSectionsExtension;
+
+impl GltfExtensionHandler for CreaseSectionsExtension {
+    fn dyn_clone(&self) -> Box<dyn GltfExtensionHandler> {
+        Box::new(self.clone())
+    }
+
+    fn on_spawn_mesh_and_material(
+        &mut self,
+        _load_context: &mut LoadContext<'_>,
+        primitive: &gltf::Primitive,
+        _mesh: &gltf::Mesh,
+        _material: &gltf::Material,
+        entity: &mut EntityWorldMut,
+    ) {
+        // the mesh assets can't be changed while the
+        // glTF is loading, so they're marked and
+        // changed once spawned
+        if entity.contains::<Mesh3d>() {
+            entity.insert(PendingCreaseSections {
+                primitive: primitive.index(),
+            });
+        }
+    }
+}
+
+fn generate_gltf_section_colors(
+    mut commands: Commands,
+    settings: Res<GltfCreaseSections>,
+    pending: Query<(Entity, &PendingCreaseSections, &Mesh3d, Option<&ChildOf>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    // the primitives of a glTF mesh are spawned as
+    // siblings
+    let mut gltf_meshes: HashMap<Option<Entity>, Vec<(usize, Entity, AssetId<Mesh>)>> =
+        HashMap::new();
+    for (entity, pending, mesh, child_of) in &pending {
+        gltf_meshes
+            .entry(child_of.map(ChildOf::parent))
+            .or_default()
+            .push((pending.primitive, entity, mesh.id()));
+    }
+
+    let GltfCreaseSectionsPlugin {
+        crease_angle,
+        split_materials,
+    } = settings.0;
+    for mut primitives in gltf_meshes.into_values() {
+        // a primitive's first section depends on the ones
+        // before it, so they all wait for every mesh to
+        // load
+        if primitives
+            .iter()
+            .any(|&(_, _, mesh_id)| meshes.get(mesh_id).is_none())
+        {
+            continue;
+        }
+        primitives.sort_by_key(|&(primitive, ..)| primitive);
+
+        let mut first_section = 0;
+        for (_, entity, mesh_id) in primitives {
+            commands.entity(entity).remove::<PendingCreaseSections>();
+            // authored colors win, and meshes shared by
+            // several nodes are only split once. Meshes
+            // whose data is gone fail below, with a warning
+            let authored = meshes.get(mesh_id).is_some_and(|mesh| {
+                mesh.try_contains_attribute(ATTRIBUTE_SECTION_COLOR)
+                    .unwrap_or(false)
+            });
+            if authored {
+                continue;
+            }
+            let Some(mesh) = meshes.get_mut(mesh_id) else {
+                continue;
+            };
+            match generate_section_colors(
+                mesh,
+                CreaseSections {
+                    crease_angle,
+                    first_section,
+                },
+            ) {
+                Ok(sections) if split_materials => first_section += sections,
+                Ok(_) => {}
+                Err(err) => warn!("Couldn't generate section colors for a glTF primitive: {err}"),
+            }
+        }
+    }
+}
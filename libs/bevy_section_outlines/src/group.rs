@@ -0,0 +1,233 @@
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+/// Offsets the section ids of a mesh so the
+/// silhouettes of different meshes with the same
+/// section colors still produce an edge.
+///
+/// Inserted automatically for every `Mesh3d`, `Mesh2d`
+/// and `Sprite`. Meshes under an [`OutlineGroup`] share
+/// the group's id.
+///
+/// An id from [`FromWorld`] is a new one, and is
+/// recycled like the automatic ones once the entity it's
+/// inserted on is despawned. Ids set by hand are never
+/// recycled.
+#[derive(Component, Clone, Copy)]
+#[component(on_insert = claim_generated_id)]
+pub struct SectionGroupId {
+    pub id: u32,
+}
+
+impl FromWorld for SectionGroupId {
+    fn from_world(world: &mut World) -> Self {
+        let mut generator = world.resource_mut::<SectionGroupIdGenerator>();
+        let id = generator.generate_id();
+        generator.unclaimed.insert(id);
+        Self { id }
+    }
+}
+
+/// Makes the entity an id from [`FromWorld`] is inserted
+/// on its owner, so it's recycled with the others.
+fn claim_generated_id(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let Some(id) = world.get::<SectionGroupId>(entity).map(|group| group.id) else {
+        return;
+    };
+    let claimed = world
+        .get_resource_mut::<SectionGroupIdGenerator>()
+        .is_some_and(|mut generator| generator.unclaimed.remove(&id));
+    if claimed {
+        world
+            .commands()
+            .entity(entity)
+            .insert(SectionGroupIdOwner(id));
+    }
+}
+
+/// Outlines every mesh in this entity's hierarchy as
+/// one object, for example a glTF scene made of many
+/// primitives.
+///
+/// All descendant meshes share one [`SectionGroupId`],
+/// so there are no outlines between them unless their
+/// section colors differ. Children added later join the
+/// group, and children removed from it get their own
+/// id again. A nested `OutlineGroup` starts a group of
+/// its own.
+///
+/// ```ignore
+/// commands.spawn((
+///     SceneRoot(asset_server.load("ship.glb#Scene0")),
+///     OutlineGroup,
+/// ));
+/// ```
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component, Default, Clone)]
+pub struct OutlineGroup;
+
+#[derive(Resource, Reflect, Default)]
+pub(crate) struct SectionGroupIdGenerator {
+    next: u32,
+    /// Ids of despawned meshes and groups, handed out
+    /// again before new ones.
+    free: Vec<u32>,
+    /// Ids from [`FromWorld`] that aren't on an entity
+    /// yet.
+    #[reflect(ignore)]
+    unclaimed: HashSet<u32>,
+}
+
+impl SectionGroupIdGenerator {
+    fn generate_id(&mut self) -> u32 {
+        self.free.pop().unwrap_or_else(|| {
+            self.next += 1;
+            self.next
+        })
+    }
+
+    fn recycle(&mut self, id: u32) {
+        self.free.push(id);
+    }
+}
+
+/// The entity the [`SectionGroupIdGenerator`] gave this
+/// id to. The id is recycled when this is removed.
+#[derive(Component)]
+struct SectionGroupIdOwner(u32);
+
+/// The [`OutlineGroup`] a mesh got its
+/// [`SectionGroupId`] from.
+#[derive(Component)]
+struct InOutlineGroup;
+
+/// Keeps the [`SectionGroupId`] of every mesh and
+/// sprite in sync with the [`OutlineGroup`] it's in.
+pub(crate) struct SectionGroupPlugin;
+
+impl Plugin for SectionGroupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SectionGroupIdGenerator>()
+            .register_type::<OutlineGroup>()
+            // after scenes are spawned and the hierarchy
+            // is settled, before the ids are extracted
+            .add_systems(PostUpdate, sync_section_group_ids)
+            .add_observer(recycle_section_group_ids);
+    }
+}
+
+fn recycle_section_group_ids(
+    removed: On<Remove, SectionGroupIdOwner>,
+    owners: Query<&SectionGroupIdOwner>,
+    mut generator: ResMut<SectionGroupIdGenerator>,
+) {
+    if let Ok(owner) = owners.get(removed.entity) {
+        generator.recycle(owner.0);
+    }
+}
+
+/// Gives new meshes an id, and re-resolves the group of
+/// every mesh in a hierarchy that was added to, removed
+/// from, or had its `OutlineGroup` added or removed.
+#[allow(clippy::too_many_arguments)]
+fn sync_section_group_ids(
+    mut commands: Commands,
+    mut generator: ResMut<SectionGroupIdGenerator>,
+    changed: Query<
+        Entity,
+        Or<(
+            Added<Mesh3d>,
+            Added<Mesh2d>,
+            Added<Sprite>,
+            Changed<ChildOf>,
+            Added<OutlineGroup>,
+        )>,
+    >,
+    mut removed_parents: RemovedComponents<ChildOf>,
+    mut removed_groups: RemovedComponents<OutlineGroup>,
+    meshes: Query<
+        (Option<&SectionGroupId>, Has<InOutlineGroup>),
+        Or<(With<Mesh3d>, With<Mesh2d>, With<Sprite>)>,
+    >,
+    groups: Query<(Option<&SectionGroupId>, Has<InOutlineGroup>), With<OutlineGroup>>,
+    owners: Query<(), With<SectionGroupIdOwner>>,
+    parents: Query<&ChildOf>,
+    children: Query<&Children>,
+) {
+    let mut stack: Vec<Entity> = changed.iter().collect();
+    stack.extend(removed_parents.read());
+    for entity in removed_groups.read() {
+        // roots without a mesh only had an id for their
+        // group, removing it recycles the id
+        if !meshes.contains(entity) && owners.contains(entity) {
+            commands
+                .entity(entity)
+                .remove::<(SectionGroupId, SectionGroupIdOwner)>();
+        }
+        stack.push(entity);
+    }
+
+    // the id of every group root seen this run, which
+    // the queries can't see until commands are applied
+    let mut group_ids: HashMap<Entity, u32> = HashMap::default();
+    let mut visited = HashSet::new();
+
+    while let Some(entity) = stack.pop() {
+        if !visited.insert(entity) {
+            continue;
+        }
+        if let Ok(children) = children.get(entity) {
+            stack.extend(children);
+        }
+
+        let Ok((section_group_id, in_group)) = meshes.get(entity) else {
+            continue;
+        };
+
+        let root = std::iter::once(entity)
+            .chain(parents.iter_ancestors(entity))
+            .find(|&ancestor| groups.contains(ancestor));
+
+        match root {
+            Some(root) => {
+                let id = *group_ids.entry(root).or_insert_with(|| {
+                    match groups.get(root) {
+                        // the root's own id, generated or set by hand
+                        Ok((Some(root_id), false)) => root_id.id,
+                        // roots without a mesh, and roots that
+                        // were in another group, need one
+                        _ => {
+                            let id = generator.generate_id();
+                            commands
+                                .entity(root)
+                                .remove::<InOutlineGroup>()
+                                .insert((SectionGroupId { id }, SectionGroupIdOwner(id)));
+                            id
+                        }
+                    }
+                });
+                let up_to_date = section_group_id.is_some_and(|current| current.id == id);
+                if root != entity && !up_to_date {
+                    commands
+                        .entity(entity)
+                        .remove::<SectionGroupIdOwner>()
+                        .insert((SectionGroupId { id }, InOutlineGroup));
+                }
+            }
+            // new meshes, and meshes that left their group,
+            // get an id of their own. Ids set by hand are
+            // left alone.
+            None if section_group_id.is_none() || in_group => {
+                let id = generator.generate_id();
+                commands
+                    .entity(entity)
+                    .remove::<InOutlineGroup>()
+                    .insert((SectionGroupId { id }, SectionGroupIdOwner(id)));
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use bevy::{
+    core_pipeline::{
+        FullscreenShader,
+        core_2d::graph::{Core2d, Node2d},
+        core_3d::graph::{Core3d, Node3d},
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSystems,
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{texture_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::{CachedTexture, TextureCache},
+        view::ExtractedView,
+    },
+};
+
+use crate::{SectionTexture, outline::OutlineLabel, outline::OutlinePipelineKey};
+
+/// Jump floods the section texture of every camera
+/// outlining with [`OutlineMethod::JumpFlood`](crate::OutlineMethod::JumpFlood),
+/// so the outline pass knows the nearest section edge
+/// of every pixel.
+pub(crate) struct JumpFloodPlugin;
+
+impl Plugin for JumpFloodPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_systems(
+                Render,
+                prepare_jump_flood_textures.in_set(RenderSystems::PrepareResources),
+            )
+            .add_render_graph_node::<ViewNodeRunner<JumpFloodNode>>(Core3d, JumpFloodLabel)
+            .add_render_graph_edges(Core3d, (Node3d::Tonemapping, JumpFloodLabel, OutlineLabel))
+            .add_render_graph_node::<ViewNodeRunner<JumpFloodNode>>(Core2d, JumpFloodLabel)
+            .add_render_graph_edges(Core2d, (Node2d::Tonemapping, JumpFloodLabel, OutlineLabel));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<JumpFloodPipeline>();
+    }
+}
+
+/// The most flood passes a view can need, enough for a
+/// 65536 pixel wide texture.
+const MAX_PASSES: usize = 16;
+
+/// Where each pixel stores the coordinates of its
+/// nearest seed.
+const JUMP_FLOOD_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rg16Uint;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct JumpFloodLabel;
+
+/// The ping-pong textures a view is flooded in.
+#[derive(Component)]
+pub(crate) struct JumpFloodTextures {
+    textures: [CachedTexture; 2],
+    passes: usize,
+}
+
+impl JumpFloodTextures {
+    /// The texture the last flood pass wrote to.
+    pub(crate) fn result(&self) -> &CachedTexture {
+        &self.textures[self.passes % 2]
+    }
+}
+
+fn prepare_jump_flood_textures(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &ExtractedView, Option<&OutlinePipelineKey>)>,
+) {
+    for (entity, view, key) in &views {
+        if !key.is_some_and(|key| key.jump_flood) {
+            commands.entity(entity).remove::<JumpFloodTextures>();
+            continue;
+        }
+
+        let size = Extent3d {
+            width: view.viewport.z.max(1),
+            height: view.viewport.w.max(1),
+            depth_or_array_layers: 1,
+        };
+        let descriptor = TextureDescriptor {
+            label: Some("jump_flood_texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: JUMP_FLOOD_TEXTURE_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        };
+        // the texture cache hands out a different texture
+        // for each request with the same descriptor
+        let textures = [
+            texture_cache.get(&render_device, descriptor.clone()),
+            texture_cache.get(&render_device, descriptor),
+        ];
+        // steps of half the size, rounded up to a power
+        // of two, down to 1
+        let passes =
+            (size.width.max(size.height).next_power_of_two().ilog2() as usize).min(MAX_PASSES);
+
+        commands
+            .entity(entity)
+            .insert(JumpFloodTextures { textures, passes });
+    }
+}
+
+#[derive(Default)]
+struct JumpFloodNode;
+
+impl ViewNode for JumpFloodNode {
+    type ViewQuery = (&'static SectionTexture, &'static JumpFloodTextures);
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (section_texture, jump_flood_textures): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let jump_flood_pipeline = world.resource::<JumpFloodPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        let (Some(seed_pipeline), Some(flood_pipeline)) = (
+            pipeline_cache.get_render_pipeline(jump_flood_pipeline.seed_pipeline_id),
+            pipeline_cache.get_render_pipeline(jump_flood_pipeline.flood_pipeline_id),
+        ) else {
+            return Ok(());
+        };
+        let Some(steps_binding) = jump_flood_pipeline.steps.binding() else {
+            return Ok(());
+        };
+        let Some(sections) = &section_texture.sections else {
+            return Ok(());
+        };
+
+        let [first, second] = &jump_flood_textures.textures;
+
+        // seed the first texture with the section edges
+        let bind_group = render_context.render_device().create_bind_group(
+            "jump_flood_seed_bind_group",
+            &pipeline_cache.get_bind_group_layout(&jump_flood_pipeline.seed_layout),
+            &BindGroupEntries::single(&sections.texture.default_view),
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("jump_flood_seed_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &first.default_view,
+                resolve_target: None,
+                ops: Operations::default(),
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(seed_pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        drop(render_pass);
+
+        // then flood back and forth between the two
+        let passes = jump_flood_textures.passes;
+        for (pass, &offset) in jump_flood_pipeline.step_offsets[MAX_PASSES - passes..]
+            .iter()
+            .enumerate()
+        {
+            let (source, destination) = if pass % 2 == 0 {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            let bind_group = render_context.render_device().create_bind_group(
+                "jump_flood_bind_group",
+                &pipeline_cache.get_bind_group_layout(&jump_flood_pipeline.flood_layout),
+                &BindGroupEntries::sequential((&source.default_view, steps_binding.clone())),
+            );
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("jump_flood_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &destination.default_view,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_render_pipeline(flood_pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[offset]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, ShaderType)]
+struct JumpFloodStep {
+    step: u32,
+    // WebGL2 structs must be 16 byte aligned.
+    _webgl2_padding: UVec3,
+}
+
+#[derive(Resource)]
+struct JumpFloodPipeline {
+    seed_layout: BindGroupLayoutDescriptor,
+    flood_layout: BindGroupLayoutDescriptor,
+    /// Every step size, largest first, so a view that
+    /// needs fewer passes starts further in.
+    steps: DynamicUniformBuffer<JumpFloodStep>,
+    step_offsets: [u32; MAX_PASSES],
+    seed_pipeline_id: CachedRenderPipelineId,
+    flood_pipeline_id: CachedRenderPipelineId,
+}
+
+impl FromWorld for JumpFloodPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let render_queue = world.resource::<RenderQueue>();
+
+        let seed_layout = BindGroupLayoutDescriptor::new(
+            "jump_flood_seed_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                // The section texture
+                texture_2d(TextureSampleType::Float { filterable: false }),
+            ),
+        );
+        let flood_layout = BindGroupLayoutDescriptor::new(
+            "jump_flood_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    // The previous pass's seeds
+                    texture_2d(TextureSampleType::Uint),
+                    // The step size of this pass
+                    uniform_buffer::<JumpFloodStep>(true),
+                ),
+            ),
+        );
+
+        let mut steps = DynamicUniformBuffer::default();
+        let step_offsets = core::array::from_fn(|pass| {
+            steps.push(&JumpFloodStep {
+                step: 1 << (MAX_PASSES - 1 - pass),
+                _webgl2_padding: UVec3::ZERO,
+            })
+        });
+        steps.write_buffer(render_device, render_queue);
+
+        let shader = world.load_asset("embedded://bevy_section_outlines/shaders/jump_flood.wgsl");
+        let fullscreen_shader = world
+            .get_resource::<FullscreenShader>()
+            .expect("FullscreenShader Resource is required for post-process")
+            .to_vertex_state();
+
+        let descriptor =
+            |label: &'static str, entry_point: &'static str, layout| RenderPipelineDescriptor {
+                label: Some(label.into()),
+                layout: vec![layout],
+                vertex: fullscreen_shader.clone(),
+                fragment: Some(FragmentState {
+                    shader: shader.clone(),
+                    shader_defs: if entry_point == "seed" {
+                        vec!["SEED".into()]
+                    } else {
+                        vec![]
+                    },
+                    entry_point: Some(entry_point.into()),
+                    targets: vec![Some(ColorTargetState {
+                        format: JUMP_FLOOD_TEXTURE_FORMAT,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                push_constant_ranges: vec![],
+                zero_initialize_workgroup_memory: false,
+            };
+        let seed_descriptor = descriptor("jump_flood_seed_pipeline", "seed", seed_layout.clone());
+        let flood_descriptor = descriptor("jump_flood_pipeline", "flood", flood_layout.clone());
+
+        let pipeline_cache = world.resource_mut::<PipelineCache>();
+        Self {
+            seed_layout,
+            flood_layout,
+            steps,
+            step_offsets,
+            seed_pipeline_id: pipeline_cache.queue_render_pipeline(seed_descriptor),
+            flood_pipeline_id: pipeline_cache.queue_render_pipeline(flood_descriptor),
+        }
+    }
+}
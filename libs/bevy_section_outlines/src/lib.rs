@@ -0,0 +1,65 @@
+//! Artist-controlled outlines.
+//!
+//! Meshes and sprites marked with [`DrawSection`] are
+//! drawn into a section texture using their
+//! [`ATTRIBUTE_SECTION_COLOR`] vertex attribute, and
+//! cameras with [`OutlineSettings`] draw an outline
+//! wherever the section id changes. Both `Camera3d` and
+//! `Camera2d` are supported.
+//!
+//! [`generate_section_colors`] paints the attribute
+//! from a mesh's creases when it isn't authored.
+//!
+//! ```ignore
+//! App::new()
+//!     .add_plugins((DefaultPlugins, SectionOutlinePlugin));
+//!
+//! commands.spawn((
+//!     Camera3d::default(),
+//!     Msaa::Off,
+//!     OutlineSettings::default(),
+//! ));
+//! commands.spawn((Mesh3d(mesh), MeshMaterial3d(material), DrawSection));
+//! ```
+use bevy::{asset::embedded_asset, prelude::*};
+
+mod crease;
+#[cfg(feature = "gltf")]
+mod gltf_sections;
+mod group;
+mod jump_flood;
+mod outline;
+mod section_phase;
+mod section_texture;
+mod section_texture_2d;
+
+pub use crease::{CreaseSections, SectionColorError, generate_section_colors};
+#[cfg(feature = "gltf")]
+pub use gltf_sections::GltfCreaseSectionsPlugin;
+pub use group::{OutlineGroup, SectionGroupId};
+pub use outline::{EdgeDetection, EdgeSource, OutlineMethod, OutlineSettings, PostProcessDisplay};
+pub use section_texture::{
+    ATTRIBUTE_SECTION_COLOR, DrawSection, OutlineStyle, SectionTexture, SectionsPrepass,
+};
+
+/// Renders the section texture and the outlines
+/// built from it.
+pub struct SectionOutlinePlugin;
+
+impl Plugin for SectionOutlinePlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/section_texture.wgsl");
+        embedded_asset!(app, "shaders/section_texture_2d.wgsl");
+        embedded_asset!(app, "shaders/section_sprite.wgsl");
+        embedded_asset!(app, "shaders/outline.wgsl");
+        embedded_asset!(app, "shaders/jump_flood.wgsl");
+
+        app.add_plugins((
+            group::SectionGroupPlugin,
+            section_texture::SectionTexturePhasePlugin,
+            section_texture_2d::SectionTexture2dPlugin,
+            outline::OutlinePlugin,
+            jump_flood::JumpFloodPlugin,
+        ));
+    }
+}
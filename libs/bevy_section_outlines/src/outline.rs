@@ -0,0 +1,739 @@
+use bevy::{
+    core_pipeline::{
+        FullscreenShader,
+        core_2d::graph::{Core2d, Node2d},
+        core_3d::graph::{Core3d, Node3d},
+        prepass::{DepthPrepass, NormalPrepass, ViewPrepassTextures},
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSystems,
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{
+                sampler, storage_buffer_read_only_sized, texture_2d, texture_depth_2d,
+                uniform_buffer,
+            },
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        view::{Hdr, ViewTarget},
+    },
+};
+
+use crate::{
+    SectionTexture, SectionsPrepass, jump_flood::JumpFloodTextures, section_texture::OutlineStyles,
+};
+
+/// Draws outlines along the edges of the section
+/// texture, for every camera with [`OutlineSettings`].
+pub(crate) struct OutlinePlugin;
+
+impl Plugin for OutlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            // The settings will be a component that lives in the main world but will
+            // be extracted to the render world every frame.
+            // This makes it possible to control the effect from the main world.
+            // This plugin will take care of extracting it automatically.
+            // It's important to derive [`ExtractComponent`] on [`OutlineSettings`]
+            // for this plugin to work correctly.
+            ExtractComponentPlugin::<OutlineSettings>::default(),
+            // The extracted uniform is the data used in the shader.
+            // This plugin will prepare the component for the GPU by creating a uniform buffer
+            // and writing the data to that buffer every frame.
+            UniformComponentPlugin::<OutlineUniform>::default(),
+        ))
+        .register_type::<OutlineSettings>();
+
+        // We need to get the render app from the main app
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<SpecializedRenderPipelines<OutlinePipeline>>()
+            .add_systems(
+                Render,
+                prepare_outline_pipelines.in_set(RenderSystems::Prepare),
+            )
+            // Bevy's renderer uses a render graph which is a collection of nodes in a directed acyclic graph.
+            // It currently runs on each view/camera and executes each node in the specified order.
+            // It will make sure that any node that needs a dependency from another node
+            // only runs when that dependency is done.
+            //
+            // Each node can execute arbitrary work, but it generally runs at least one render pass.
+            // A node only has access to the render world, so if you need data from the main world
+            // you need to extract it manually or with the plugin like above.
+            // Add a [`Node`] to the [`RenderGraph`]
+            // The Node needs to impl FromWorld
+            //
+            // The [`ViewNodeRunner`] is a special [`Node`] that will automatically run the node for each view
+            // matching the [`ViewQuery`]
+            .add_render_graph_node::<ViewNodeRunner<OutlineNode>>(
+                // Specify the label of the graph, in this case we want the graph for 3d
+                Core3d,
+                // It also needs the label of the node
+                OutlineLabel,
+            )
+            .add_render_graph_edges(
+                Core3d,
+                // Specify the node ordering.
+                // This will automatically create all required node edges to enforce the given ordering.
+                (
+                    Node3d::Tonemapping,
+                    OutlineLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            )
+            // 2d cameras are outlined the same way
+            .add_render_graph_node::<ViewNodeRunner<OutlineNode>>(Core2d, OutlineLabel)
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::Tonemapping,
+                    OutlineLabel,
+                    Node2d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        // We need to get the render app from the main app
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            // Initialize the pipeline
+            .init_resource::<OutlinePipeline>();
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub(crate) struct OutlineLabel;
+
+// The post process node used for the render graph
+#[derive(Default)]
+struct OutlineNode;
+
+// The ViewNode trait is required by the
+// ViewNodeRunner
+impl ViewNode for OutlineNode {
+    // The node needs a query to gather data from the
+    // ECS in order to do its rendering,
+    // but it's not a normal system so we need to
+    // define it manually.
+    //
+    // This query will only run on the view entity
+    type ViewQuery = (
+        &'static ViewTarget,
+        // This makes sure the node only runs on cameras
+        // with the OutlineSettings component
+        &'static OutlinePipelineKey,
+        &'static OutlinePipelineId,
+        &'static SectionTexture,
+        // As there could be multiple post processing
+        // components sent to the GPU (one per camera),
+        // we need to get the index of the one that is
+        // associated with the current view.
+        &'static DynamicUniformIndex<OutlineUniform>,
+        Option<&'static ViewPrepassTextures>,
+        Option<&'static JumpFloodTextures>,
+    );
+
+    // Runs the node logic
+    // This is where you encode draw commands.
+    //
+    // This will run on every view on which the graph
+    // is running. If you don't want your effect
+    // to run on every camera, you'll need to make
+    // sure you have a marker component as part of
+    // [`ViewQuery`] to identify which camera(s)
+    // should run the effect.
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (
+            view_target,
+            &key,
+            pipeline_id,
+            section_texture,
+            settings_index,
+            prepass_textures,
+            jump_flood_textures,
+        ): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        // Get the pipeline resource that contains the
+        // global data we need to create the
+        // render pipeline
+        let post_process_pipeline = world.resource::<OutlinePipeline>();
+
+        // The pipeline cache is a cache of all previously
+        // created pipelines. It is required to
+        // avoid creating a new pipeline each frame,
+        // which is expensive due to shader compilation.
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        // Get the pipeline specialized for this view
+        // from the cache
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id.0) else {
+            return Ok(());
+        };
+
+        // Get the settings uniform binding
+        let settings_uniforms = world.resource::<ComponentUniforms<OutlineUniform>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        // views without a sections prepass have no section texture
+        // to outline
+        let Some(sections) = &section_texture.sections else {
+            return Ok(());
+        };
+        let section_texture_view = &sections.texture.default_view;
+
+        // This will start a new "post process write",
+        // obtaining two texture views from the
+        // view target - a `source` and a `destination`.
+        // `source` is the "current" main texture and you
+        // _must_ write into `destination` because
+        // calling `post_process_write()` on the
+        // [`ViewTarget`] will internally flip the
+        // [`ViewTarget`]'s main texture to the
+        // `destination` texture. Failing to do so will
+        // cause the current main texture
+        // information to be lost.
+        let post_process = view_target.post_process_write();
+
+        // The bind_group gets created each frame.
+        //
+        // Normally, you would create a bind_group in the
+        // Queue set, but this doesn't work with
+        // the post_process_write(). The reason it
+        // doesn't work is because each post_process_write
+        // will alternate the source/destination.
+        // The only way to have the correct
+        // source/destination for the bind_group
+        // is to make sure you get it during the node
+        // execution.
+        // It's important for this to match the
+        // BindGroupLayout defined in the
+        // OutlinePipeline
+        let mut entries = DynamicBindGroupEntries::sequential((
+            // Make sure to use the source view
+            post_process.source,
+            // Use the sampler created for the
+            // pipeline
+            &post_process_pipeline.sampler,
+            // Set the settings binding
+            settings_binding.clone(),
+            section_texture_view.into_binding(),
+            // Use the sampler created for the
+            // pipeline
+            &post_process_pipeline.vertex_id_sampler,
+        ));
+        if post_process_pipeline.styles_supported {
+            let Some(styles_binding) = world.resource::<OutlineStyles>().buffer.binding() else {
+                return Ok(());
+            };
+            // The per-entity outline styles
+            entries = entries.extend_with_indices(((5, styles_binding),));
+        }
+        if key.needs_depth() {
+            let Some(depth_view) = prepass_textures.and_then(ViewPrepassTextures::depth_view)
+            else {
+                return Ok(());
+            };
+            entries = entries.extend_with_indices(((6, depth_view),));
+        }
+        if key.normal_edges {
+            let Some(normal_view) = prepass_textures.and_then(ViewPrepassTextures::normal_view)
+            else {
+                return Ok(());
+            };
+            entries = entries.extend_with_indices(((7, normal_view),));
+        }
+        if key.jump_flood {
+            let Some(jump_flood_textures) = jump_flood_textures else {
+                return Ok(());
+            };
+            entries =
+                entries.extend_with_indices(((8, &jump_flood_textures.result().default_view),));
+        }
+        let bind_group = render_context.render_device().create_bind_group(
+            "outline_bind_group",
+            &pipeline_cache.get_bind_group_layout(&post_process_pipeline.layout(key)),
+            &entries,
+        );
+
+        // Begin the render pass
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("outline_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                // We need to specify the post
+                // process destination view here
+                // to make sure we write to the
+                // appropriate texture.
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        // This is mostly just wgpu boilerplate for
+        // drawing a fullscreen triangle,
+        // using the pipeline/bind_group created above
+        render_pass.set_render_pipeline(pipeline);
+        // By passing in the index of the post process
+        // settings on this view, we ensure
+        // that in the event that multiple settings were
+        // sent to the GPU (as would be the
+        // case with multiple cameras), we use the correct
+        // one.
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+// This contains global data used by the render
+// pipeline. This will be created once on startup.
+#[derive(Resource)]
+struct OutlinePipeline {
+    /// The bindings every specialization has, the
+    /// prepass textures are added per key.
+    entries: Vec<BindGroupLayoutEntry>,
+    sampler: Sampler,
+    vertex_id_sampler: Sampler,
+    /// Whether [`OutlineStyles`] can be bound. Without
+    /// storage buffers (WebGL2) every section is
+    /// outlined with the camera's [`OutlineSettings`].
+    styles_supported: bool,
+    shader: Handle<Shader>,
+    fullscreen_shader: FullscreenShader,
+}
+
+impl OutlinePipeline {
+    fn layout(&self, key: OutlinePipelineKey) -> BindGroupLayoutDescriptor {
+        let mut entries = self.entries.clone();
+        if key.needs_depth() {
+            // The depth prepass
+            entries.push(texture_depth_2d().build(6, ShaderStages::FRAGMENT));
+        }
+        if key.normal_edges {
+            // The normal prepass
+            entries.push(
+                texture_2d(TextureSampleType::Float { filterable: true })
+                    .build(7, ShaderStages::FRAGMENT),
+            );
+        }
+        if key.jump_flood {
+            // The nearest section edge of every pixel
+            entries.push(texture_2d(TextureSampleType::Uint).build(8, ShaderStages::FRAGMENT));
+        }
+        BindGroupLayoutDescriptor::new("outline_bind_group_layout", &entries)
+    }
+}
+
+impl FromWorld for OutlinePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let styles_supported = render_device.limits().max_storage_buffers_per_shader_stage > 0;
+
+        // We need to define the bind group layout used
+        // for our pipeline
+        let mut entries = DynamicBindGroupLayoutEntries::sequential(
+            // The layout entries will only be visible in the fragment stage
+            ShaderStages::FRAGMENT,
+            (
+                // The screen texture
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                // The sampler that will be used to sample the screen texture
+                sampler(SamplerBindingType::Filtering),
+                // The settings uniform that will control the effect
+                uniform_buffer::<OutlineUniform>(true),
+                // The vertex color id texture
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                // The sampler that will be used to sample the vertex color id texture
+                sampler(SamplerBindingType::Filtering),
+            ),
+        );
+        if styles_supported {
+            // The per-entity outline styles
+            entries =
+                entries.extend_with_indices(((5, storage_buffer_read_only_sized(false, None)),));
+        }
+        let entries = entries.to_vec();
+
+        // We can create the sampler here since it won't
+        // change at runtime and doesn't depend on the
+        // view
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+        let vertex_id_sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        // Get the shader handle
+        let shader = world.load_asset("embedded://bevy_section_outlines/shaders/outline.wgsl");
+
+        let fullscreen_shader = world
+            .get_resource::<FullscreenShader>()
+            .expect("FullscreenShader Resource is required for post-process")
+            .clone();
+
+        Self {
+            entries,
+            sampler,
+            vertex_id_sampler,
+            styles_supported,
+            shader,
+            fullscreen_shader,
+        }
+    }
+}
+
+/// Which shader defs and prepass textures a view's
+/// outline pipeline is specialized with.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutlinePipelineKey {
+    display: PostProcessDisplay,
+    depth_edges: bool,
+    normal_edges: bool,
+    thinning: bool,
+    pub(crate) jump_flood: bool,
+}
+
+impl OutlinePipelineKey {
+    fn needs_depth(self) -> bool {
+        self.display == PostProcessDisplay::DepthEdgesOnly || self.depth_edges || self.thinning
+    }
+}
+
+impl SpecializedRenderPipeline for OutlinePipeline {
+    type Key = OutlinePipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = vec![];
+        if let Some(shader_def) = key.display.shader_def() {
+            shader_defs.push(shader_def.into());
+        }
+        if key.needs_depth() {
+            shader_defs.push("DEPTH_TEXTURE".into());
+        }
+        if key.depth_edges {
+            shader_defs.push("DEPTH_EDGES".into());
+        }
+        if key.normal_edges {
+            shader_defs.extend(["NORMAL_TEXTURE".into(), "NORMAL_EDGES".into()]);
+        }
+        if key.jump_flood {
+            shader_defs.push("JUMP_FLOOD".into());
+        }
+
+        RenderPipelineDescriptor {
+            label: Some("outline_pipeline".into()),
+            layout: vec![self.layout(key)],
+            // This will setup a fullscreen triangle for the vertex state
+            vertex: self.fullscreen_shader.to_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs,
+                // Make sure this matches the entry point of your shader.
+                // It can be anything as long as it matches here and in the shader.
+                entry_point: Some("fragment".into()),
+                targets: vec![Some(ColorTargetState {
+                    format: ViewTarget::TEXTURE_FORMAT_HDR,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            // All of the following properties are not important for this effect so just use the default values.
+            // This struct doesn't have the Default trait implemented because not all fields can have a default value.
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+/// The outline pipeline specialized for a view's
+/// [`OutlinePipelineKey`].
+#[derive(Component)]
+struct OutlinePipelineId(CachedRenderPipelineId);
+
+fn prepare_outline_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<OutlinePipeline>>,
+    pipeline: Res<OutlinePipeline>,
+    views: Query<(
+        Entity,
+        &OutlinePipelineKey,
+        Has<DepthPrepass>,
+        Has<NormalPrepass>,
+    )>,
+) {
+    for (entity, &key, has_depth_prepass, has_normal_prepass) in &views {
+        if key.needs_depth() && !has_depth_prepass {
+            warn_once!(
+                "Depth edges, line thinning and PostProcessDisplay::DepthEdgesOnly need a DepthPrepass on the camera"
+            );
+            commands.entity(entity).remove::<OutlinePipelineId>();
+            continue;
+        }
+        if key.normal_edges && !has_normal_prepass {
+            warn_once!("Normal edges need a NormalPrepass on the camera");
+            commands.entity(entity).remove::<OutlinePipelineId>();
+            continue;
+        }
+        let id = pipelines.specialize(&pipeline_cache, &pipeline, key);
+        commands.entity(entity).insert(OutlinePipelineId(id));
+    }
+}
+
+/// Outlines the sections drawn by this camera.
+///
+/// Only works with HDR cameras without MSAA, so add
+/// `Msaa::Off` to the camera as well.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component, Default, Clone)]
+#[require(SectionsPrepass, Hdr)]
+pub struct OutlineSettings {
+    pub stroke_color: LinearRgba,
+    /// Width of the outline in pixels
+    pub width: u32,
+    /// Which discontinuities become lines
+    pub edges: EdgeDetection,
+    /// How section id edges are turned into lines
+    pub method: OutlineMethod,
+    /// What the post-process renders, the outlined
+    /// scene or one of the debug views.
+    pub display: PostProcessDisplay,
+}
+
+impl Default for OutlineSettings {
+    fn default() -> Self {
+        Self {
+            stroke_color: LinearRgba::WHITE,
+            width: 2,
+            edges: EdgeDetection::default(),
+            method: OutlineMethod::Sobel,
+            display: PostProcessDisplay::Default,
+        }
+    }
+}
+
+impl ExtractComponent for OutlineSettings {
+    type QueryData = (&'static Self, Option<&'static Projection>);
+    type QueryFilter = ();
+    type Out = (OutlineUniform, OutlinePipelineKey);
+
+    fn extract_component(
+        (settings, projection): QueryItem<'_, '_, Self::QueryData>,
+    ) -> Option<Self::Out> {
+        let edges = &settings.edges;
+        // thinning linearizes perspective depth, other
+        // projections keep their width
+        let (thinning_distance, near) = match (edges.thinning_distance, projection) {
+            (Some(distance), Some(Projection::Perspective(perspective))) => {
+                (distance, perspective.near)
+            }
+            _ => (0., 0.),
+        };
+        let depth = edges.depth.unwrap_or(EdgeSource::DEPTH);
+        let normal = edges.normal.unwrap_or(EdgeSource::NORMAL);
+        let glow = match settings.method {
+            OutlineMethod::Sobel => 0.,
+            OutlineMethod::JumpFlood { glow } => glow,
+        };
+
+        Some((
+            OutlineUniform {
+                stroke_color: settings.stroke_color,
+                thresholds: Vec4::new(
+                    edges.section.threshold,
+                    depth.threshold,
+                    normal.threshold,
+                    0.,
+                ),
+                weights: Vec4::new(edges.section.weight, depth.weight, normal.weight, 0.),
+                width: settings.width,
+                thinning_distance,
+                near,
+                glow,
+            },
+            OutlinePipelineKey {
+                display: settings.display,
+                depth_edges: edges.depth.is_some(),
+                normal_edges: edges.normal.is_some(),
+                thinning: thinning_distance > 0.,
+                jump_flood: matches!(settings.method, OutlineMethod::JumpFlood { .. }),
+            },
+        ))
+    }
+}
+
+/// The part of [`OutlineSettings`] that gets passed
+/// to the shader. The display and which edge sources
+/// are enabled are shader defs instead.
+#[derive(Component, Clone, Copy, ShaderType)]
+pub struct OutlineUniform {
+    stroke_color: LinearRgba,
+    /// x: section ids, y: depth, z: normals
+    thresholds: Vec4,
+    weights: Vec4,
+    width: u32,
+    thinning_distance: f32,
+    near: f32,
+    glow: f32,
+}
+
+/// How section id edges are turned into lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Default, Clone)]
+pub enum OutlineMethod {
+    /// A sobel filter sampled `width` pixels apart.
+    /// Cheap for thin lines, but wide lines skip pixels
+    /// and break up.
+    #[default]
+    Sobel,
+    /// A jump flood distance field of the section
+    /// edges. Lines of any width are smooth and
+    /// anti-aliased, for about a dozen extra fullscreen
+    /// passes however wide they are.
+    JumpFlood {
+        /// How many pixels past the line a glow fades
+        /// out over, 0. for no glow
+        glow: f32,
+    },
+}
+
+/// Which discontinuities in the section texture and
+/// the prepass become lines.
+///
+/// Section ids only outline what the artist painted.
+/// Depth and normal edges add the creases and overlaps
+/// inside a single section. Each source adds its
+/// weight where it crosses its threshold, and the sum
+/// is the line's opacity.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Default, Clone)]
+pub struct EdgeDetection {
+    pub section: EdgeSource,
+    /// Edges in the depth prepass. Needs a
+    /// [`DepthPrepass`] on the camera.
+    pub depth: Option<EdgeSource>,
+    /// Edges in the normal prepass. Needs a
+    /// [`NormalPrepass`] on the camera.
+    pub normal: Option<EdgeSource>,
+    /// Lines further than this from a perspective
+    /// camera get thinner with distance, down to a
+    /// single pixel. Needs a [`DepthPrepass`] on the
+    /// camera.
+    pub thinning_distance: Option<f32>,
+}
+
+impl Default for EdgeDetection {
+    fn default() -> Self {
+        Self {
+            section: EdgeSource::SECTION,
+            depth: None,
+            normal: None,
+            thinning_distance: None,
+        }
+    }
+}
+
+impl EdgeDetection {
+    /// Section ids, depth and normals, all at full
+    /// weight.
+    pub fn hybrid() -> Self {
+        Self {
+            section: EdgeSource::SECTION,
+            depth: Some(EdgeSource::DEPTH),
+            normal: Some(EdgeSource::NORMAL),
+            thinning_distance: None,
+        }
+    }
+}
+
+/// How one source of edges contributes to the outline.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Clone)]
+pub struct EdgeSource {
+    /// How large the difference between neighboring
+    /// pixels has to be to count as an edge
+    pub threshold: f32,
+    /// How opaque the line is where only this source
+    /// finds an edge
+    pub weight: f32,
+}
+
+impl EdgeSource {
+    /// Any change in section id.
+    pub const SECTION: Self = Self {
+        threshold: 0.001,
+        weight: 1.,
+    };
+    /// A 5% change in distance from the camera.
+    pub const DEPTH: Self = Self {
+        threshold: 0.05,
+        weight: 1.,
+    };
+    /// Normals more than ~25 degrees apart.
+    pub const NORMAL: Self = Self {
+        threshold: 0.1,
+        weight: 1.,
+    };
+}
+
+/// What should the post-process display.
+///
+/// Everything but [`PostProcessDisplay::Default`] is a
+/// debug view for tracking down outline artifacts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Default, Clone)]
+pub enum PostProcessDisplay {
+    /// The scene with outlines drawn over it.
+    #[default]
+    Default,
+    /// Just the sobel result: section edges in white,
+    /// occluded silhouettes in blue.
+    SobelOnly,
+    /// Just the section texture. The section id is in
+    /// red, green and blue show where a style was
+    /// written for visible and occluded meshes.
+    SectionTextureOnly,
+    /// Edges detected in the depth prepass, in the
+    /// stroke color, with the depth [`EdgeSource`]'s
+    /// threshold. Needs a [`DepthPrepass`] on the camera.
+    DepthEdgesOnly,
+}
+
+impl PostProcessDisplay {
+    fn shader_def(self) -> Option<&'static str> {
+        match self {
+            PostProcessDisplay::Default => None,
+            PostProcessDisplay::SobelOnly => Some("DISPLAY_SOBEL_ONLY"),
+            PostProcessDisplay::SectionTextureOnly => Some("DISPLAY_SECTION_TEXTURE_ONLY"),
+            PostProcessDisplay::DepthEdgesOnly => Some("DISPLAY_DEPTH_EDGES_ONLY"),
+        }
+    }
+}
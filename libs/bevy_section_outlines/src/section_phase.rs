@@ -0,0 +1,395 @@
+//! The parts of the section pass the 3d meshes of
+//! `section_texture` and the 2d meshes and sprites of
+//! `section_texture_2d` share: the phase item, the
+//! section texture of each view, the vertex layout and
+//! shader setup, and drawing the phase.
+
+use bevy::{
+    math::FloatOrd,
+    mesh::{MeshVertexBufferLayoutRef, VertexBufferLayout},
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+    render::{
+        Extract, Render, RenderSystems,
+        batching::gpu_preprocessing::{
+            IndirectParametersCpuMetadata, UntypedPhaseIndirectParametersBuffers,
+        },
+        camera::ExtractedCamera,
+        render_phase::{
+            CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions, PhaseItem,
+            PhaseItemExtraIndex, SortedPhaseItem, SortedRenderPhase, TrackedRenderPass,
+            ViewSortedRenderPhases, sort_phase_system,
+        },
+        render_resource::{
+            BindGroupLayoutDescriptor, BindGroupLayoutEntries, BindingType, BufferBindingType,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, Extent3d, FragmentState,
+            ShaderStages, SpecializedMeshPipelineError, TextureDescriptor, TextureDimension,
+            TextureUsages, VertexState,
+        },
+        renderer::RenderDevice,
+        sync_world::{MainEntity, RenderEntity},
+        texture::{ColorAttachment, TextureCache},
+        view::{ExtractedView, RetainedViewEntity},
+    },
+    shader::ShaderDefVal,
+};
+use nonmax::NonMaxU32;
+use std::{marker::PhantomData, ops::Range};
+
+use crate::{
+    ATTRIBUTE_SECTION_COLOR, SectionTexture, SectionsPrepass,
+    section_texture::SECTION_TEXTURE_FORMAT,
+};
+
+// This is the data required when you define a
+// custom phase in bevy. More specifically this is
+// the data required when using a
+// ViewSortedRenderPhase. This would look
+// differently if we wanted a batched render
+// phase. Sorted phase are a bit easier to
+// implement, but a batched phased would
+// look similar.
+//
+// If you want to see how a batched phase
+// implementation looks, you should look at the
+// Opaque2d phase.
+/// An item of a section phase. `G` is the render graph
+/// the phase is drawn in, so the 3d and 2d passes each
+/// get a phase of their own.
+pub(crate) struct SectionPhaseItem<G> {
+    sort_key: FloatOrd,
+    entity: (Entity, MainEntity),
+    pipeline: CachedRenderPipelineId,
+    draw_function: DrawFunctionId,
+    batch_range: Range<u32>,
+    extra_index: PhaseItemExtraIndex,
+    /// Whether the mesh in question is indexed
+    /// (uses an index buffer in addition to
+    /// its vertex buffer). Sprites never are.
+    indexed: bool,
+    marker: PhantomData<fn() -> G>,
+}
+
+impl<G> SectionPhaseItem<G> {
+    pub(crate) fn new(
+        sort_key: f32,
+        entity: (Entity, MainEntity),
+        pipeline: CachedRenderPipelineId,
+        draw_function: DrawFunctionId,
+        indexed: bool,
+    ) -> Self {
+        Self {
+            sort_key: FloatOrd(sort_key),
+            entity,
+            pipeline,
+            draw_function,
+            // Sorted phase items aren't batched
+            batch_range: 0..1,
+            extra_index: PhaseItemExtraIndex::None,
+            indexed,
+            marker: PhantomData,
+        }
+    }
+}
+
+// For more information about writing a phase
+// item, please look at the custom_phase_item
+// example
+impl<G: 'static> PhaseItem for SectionPhaseItem<G> {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity.0
+    }
+
+    #[inline]
+    fn main_entity(&self) -> MainEntity {
+        self.entity.1
+    }
+
+    #[inline]
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+
+    #[inline]
+    fn batch_range(&self) -> &Range<u32> {
+        &self.batch_range
+    }
+
+    #[inline]
+    fn batch_range_mut(&mut self) -> &mut Range<u32> {
+        &mut self.batch_range
+    }
+
+    #[inline]
+    fn extra_index(&self) -> PhaseItemExtraIndex {
+        self.extra_index.clone()
+    }
+
+    #[inline]
+    fn batch_range_and_extra_index_mut(&mut self) -> (&mut Range<u32>, &mut PhaseItemExtraIndex) {
+        (&mut self.batch_range, &mut self.extra_index)
+    }
+}
+
+impl<G: 'static> SortedPhaseItem for SectionPhaseItem<G> {
+    type SortKey = FloatOrd;
+
+    #[inline]
+    fn sort_key(&self) -> Self::SortKey {
+        self.sort_key
+    }
+
+    #[inline]
+    fn sort(items: &mut [Self]) {
+        // bevy normally uses radsort instead of the std
+        // slice::sort_by_key radsort is a stable
+        // radix sort that performed better than
+        // `slice::sort_by_key` or
+        // `slice::sort_unstable_by_key`. Since it
+        // is not re-exported by bevy, we just use the std
+        // sort for the purpose of the example
+        items.sort_by_key(SortedPhaseItem::sort_key);
+    }
+
+    #[inline]
+    fn indexed(&self) -> bool {
+        self.indexed
+    }
+}
+
+impl<G: 'static> CachedRenderPipelinePhaseItem for SectionPhaseItem<G> {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
+/// Adds the systems every section phase `P` drawn by
+/// `C` cameras needs, whatever it draws.
+pub(crate) fn add_section_phase<P: SortedPhaseItem, C: Component>(render_app: &mut SubApp) {
+    render_app
+        .init_resource::<DrawFunctions<P>>()
+        .add_systems(ExtractSchedule, extract_camera_phases::<P, C>)
+        .add_systems(
+            Render,
+            (
+                sort_phase_system::<P>.in_set(RenderSystems::PhaseSort),
+                prepare_section_textures::<P>.in_set(RenderSystems::PrepareResources),
+            ),
+        );
+}
+
+// When defining a custom phase, we need to
+// extract it from the main world and add it to a
+// resource that will be used by the render world.
+// We need to give that resource all views that
+// will use that phase, the `C` cameras
+pub(crate) fn extract_camera_phases<P: SortedPhaseItem, C: Component>(
+    mut commands: Commands,
+    mut sections_phases: ResMut<ViewSortedRenderPhases<P>>,
+    cameras: Extract<Query<(RenderEntity, Entity, &Camera, Has<SectionsPrepass>), With<C>>>,
+    mut live_entities: Local<HashSet<RetainedViewEntity>>,
+) {
+    live_entities.clear();
+    for (render_entity, main_entity, camera, has_sections_prepass) in &cameras {
+        if !camera.is_active {
+            continue;
+        }
+        // This is the main camera, so we use the first
+        // subview index (0)
+        let retained_view_entity = RetainedViewEntity::new(main_entity.into(), None, 0);
+
+        if has_sections_prepass {
+            sections_phases.insert_or_clear(retained_view_entity);
+        } else {
+            sections_phases.remove(&retained_view_entity);
+        }
+        live_entities.insert(retained_view_entity);
+
+        commands
+            .get_entity(render_entity)
+            .expect("Camera entity wasn't synced.")
+            .insert_if(SectionsPrepass, || has_sections_prepass);
+    }
+    // Clear out all dead views.
+    sections_phases.retain(|camera_entity, _| live_entities.contains(camera_entity));
+}
+
+/// Prepares the textures used by the section pass of
+/// the views with a `P` phase
+pub(crate) fn prepare_section_textures<P: SortedPhaseItem>(
+    mut commands: Commands,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    sections_phases: Res<ViewSortedRenderPhases<P>>,
+    views: Query<(
+        Entity,
+        &ExtractedCamera,
+        &ExtractedView,
+        &Msaa,
+        Has<SectionsPrepass>,
+    )>,
+) {
+    let mut sections_textures = <HashMap<_, _>>::default();
+
+    for (entity, camera, view, msaa, sections_prepass) in &views {
+        if !sections_phases.contains_key(&view.retained_view_entity) {
+            continue;
+        };
+
+        let Some(physical_target_size) = camera.physical_target_size else {
+            continue;
+        };
+
+        let size = Extent3d {
+            depth_or_array_layers: 1,
+            width: physical_target_size.x,
+            height: physical_target_size.y,
+        };
+
+        let cached_sections_texture = sections_prepass.then(|| {
+            sections_textures
+                .entry(camera.target.clone())
+                .or_insert_with(|| {
+                    let descriptor = TextureDescriptor {
+                        label: Some("prepass_sections_texture"),
+                        size,
+                        mip_level_count: 1,
+                        sample_count: msaa.samples(),
+                        dimension: TextureDimension::D2,
+                        format: SECTION_TEXTURE_FORMAT,
+                        usage: TextureUsages::COPY_DST
+                            | TextureUsages::RENDER_ATTACHMENT
+                            | TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    };
+                    texture_cache.get(&render_device, descriptor)
+                })
+                .clone()
+        });
+
+        commands.entity(entity).insert(SectionTexture {
+            sections: cached_sections_texture
+                .map(|t| ColorAttachment::new(t, None, None, Some(LinearRgba::BLACK))),
+            size,
+        });
+    }
+}
+
+/// The layout of the `SectionUniform` bind group,
+/// shared by the 3d and 2d section pipelines.
+pub(crate) fn section_data_layout() -> BindGroupLayoutDescriptor {
+    BindGroupLayoutDescriptor::new(
+        "section_data_bind_group_layout",
+        &BindGroupLayoutEntries::single(
+            ShaderStages::VERTEX_FRAGMENT,
+            BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: None,
+            },
+        ),
+    )
+}
+
+/// The vertex buffer layout of a section pipeline: the
+/// position at location 0 and, for meshes that have
+/// them, section colors at location 1.
+pub(crate) fn section_vertex_layout(
+    layout: &MeshVertexBufferLayoutRef,
+    shader_defs: &mut Vec<ShaderDefVal>,
+) -> Result<VertexBufferLayout, SpecializedMeshPipelineError> {
+    let mut vertex_attributes = vec![Mesh::ATTRIBUTE_POSITION.at_shader_location(0)];
+    if layout.0.contains(ATTRIBUTE_SECTION_COLOR) {
+        shader_defs.push("SECTION_COLORS".into());
+        vertex_attributes.push(ATTRIBUTE_SECTION_COLOR.at_shader_location(1));
+    }
+    // This will automatically generate the correct
+    // `VertexBufferLayout` based on the vertex
+    // attributes
+    Ok(layout.0.get_layout(&vertex_attributes)?)
+}
+
+/// The `vertex` and `fragment` entry points of a
+/// section shader, writing the `write_mask` channels
+/// of the section texture.
+pub(crate) fn section_shader_stages(
+    shader: &Handle<Shader>,
+    shader_defs: Vec<ShaderDefVal>,
+    buffers: Vec<VertexBufferLayout>,
+    write_mask: ColorWrites,
+) -> (VertexState, Option<FragmentState>) {
+    let vertex = VertexState {
+        shader: shader.clone(),
+        shader_defs: shader_defs.clone(),
+        entry_point: Some("vertex".into()),
+        buffers,
+    };
+    let fragment = FragmentState {
+        shader: shader.clone(),
+        shader_defs,
+        entry_point: Some("fragment".into()),
+        targets: vec![Some(ColorTargetState {
+            // always a float texture, so the style
+            // indices survive
+            format: SECTION_TEXTURE_FORMAT,
+            blend: None,
+            write_mask,
+        })],
+    };
+    (vertex, Some(fragment))
+}
+
+/// The `GetFullBatchData::write_batch_indirect_parameters_metadata`
+/// of both section pipelines.
+pub(crate) fn write_batch_indirect_parameters_metadata(
+    indexed: bool,
+    base_output_index: u32,
+    batch_set_index: Option<NonMaxU32>,
+    indirect_parameters_buffers: &mut UntypedPhaseIndirectParametersBuffers,
+    indirect_parameters_offset: u32,
+) {
+    // Note that `IndirectParameters` covers both of
+    // these structures, even though they
+    // actually have distinct layouts. See the comment
+    // above that type for more information.
+    let indirect_parameters = IndirectParametersCpuMetadata {
+        base_output_index,
+        batch_set_index: match batch_set_index {
+            None => !0,
+            Some(batch_set_index) => u32::from(batch_set_index),
+        },
+    };
+
+    if indexed {
+        indirect_parameters_buffers
+            .indexed
+            .set(indirect_parameters_offset, indirect_parameters);
+    } else {
+        indirect_parameters_buffers
+            .non_indexed
+            .set(indirect_parameters_offset, indirect_parameters);
+    }
+}
+
+/// Draws a view's section phase into a render pass
+/// already targeting its section texture.
+pub(crate) fn draw_section_phase<'w, P: SortedPhaseItem>(
+    render_pass: &mut TrackedRenderPass<'w>,
+    camera: &ExtractedCamera,
+    section_phase: &SortedRenderPhase<P>,
+    world: &'w World,
+    view_entity: Entity,
+) {
+    if let Some(viewport) = camera.viewport.as_ref() {
+        render_pass.set_camera_viewport(viewport);
+    }
+
+    // Render the phase
+    if !section_phase.items.is_empty()
+        && let Err(err) = section_phase.render(render_pass, world, view_entity)
+    {
+        error!("Error encountered while rendering the section phase {err:?}");
+    }
+}
@@ -1,3 +1,15 @@
+//! The section pass, which draws every [`DrawSection`]
+//! mesh into its camera's [`SectionTexture`].
+//!
+//! Each mesh's `SectionUniform` goes through the
+//! section data bind group, but the [`OutlineStyle`]s
+//! themselves can't. The section pass only writes ids
+//! into the texture, and outlines are drawn later by the
+//! fullscreen outline pass, which has no per-mesh bind
+//! groups. So the section data carries a [`StyleIndex`],
+//! the texture stores it per pixel, and the outline pass
+//! looks it up in the [`OutlineStyles`] storage buffer.
+
 use bevy::{
     core_pipeline::core_3d::{
         CORE_3D_DEPTH_FORMAT,
@@ -7,22 +19,19 @@ use bevy::{
         query::QueryItem,
         system::{SystemParamItem, lifetimeless::SRes},
     },
-    math::FloatOrd,
     mesh::{MeshVertexAttribute, MeshVertexBufferLayoutRef},
     pbr::{
         DrawMesh, MeshInputUniform, MeshPipeline, MeshPipelineKey, MeshPipelineViewLayoutKey,
         MeshUniform, RenderMeshInstances, SetMeshBindGroup, SetMeshViewBindGroup,
         SetMeshViewEmptyBindGroup,
     },
-    platform::collections::{HashMap, HashSet},
     prelude::*,
     render::{
         Extract, Render, RenderApp, RenderDebugFlags, RenderSystems,
         batching::{
             GetBatchData, GetFullBatchData,
             gpu_preprocessing::{
-                IndirectParametersCpuMetadata, UntypedPhaseIndirectParametersBuffers,
-                batch_and_prepare_sorted_render_phase,
+                UntypedPhaseIndirectParametersBuffers, batch_and_prepare_sorted_render_phase,
             },
         },
         camera::ExtractedCamera,
@@ -37,33 +46,34 @@ use bevy::{
             NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
         },
         render_phase::{
-            AddRenderCommand, CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions,
-            PhaseItem, PhaseItemExtraIndex, RenderCommand, RenderCommandResult, SetItemPipeline,
-            SortedPhaseItem, SortedRenderPhasePlugin, TrackedRenderPass, ViewSortedRenderPhases,
-            sort_phase_system,
+            AddRenderCommand, DrawFunctions, PhaseItem, RenderCommand, RenderCommandResult,
+            SetItemPipeline, SortedRenderPhasePlugin, TrackedRenderPass, ViewSortedRenderPhases,
         },
+        render_resource::StorageBuffer,
         render_resource::{
-            BindGroup, BindGroupEntries, BindGroupLayoutDescriptor, BindGroupLayoutEntries,
-            BindingType, BufferBindingType, CachedRenderPipelineId, ColorTargetState, ColorWrites,
+            BindGroup, BindGroupEntries, BindGroupLayoutDescriptor, ColorWrites,
             CommandEncoderDescriptor, CompareFunction, DepthStencilState, Extent3d, Face,
-            FragmentState, FrontFace, MultisampleState, PipelineCache, PolygonMode, PrimitiveState,
-            RenderPassDescriptor, RenderPipelineDescriptor, ShaderStages, ShaderType,
-            SpecializedMeshPipeline, SpecializedMeshPipelineError, SpecializedMeshPipelines,
-            StoreOp, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
-            VertexFormat, VertexState,
+            FrontFace, MultisampleState, PipelineCache, PolygonMode, PrimitiveState,
+            RenderPassDescriptor, RenderPipelineDescriptor, ShaderType, SpecializedMeshPipeline,
+            SpecializedMeshPipelineError, SpecializedMeshPipelines, StoreOp, TextureFormat,
+            VertexFormat,
         },
+        renderer::RenderQueue,
         renderer::{RenderContext, RenderDevice},
         sync_world::{MainEntity, RenderEntity},
-        texture::{ColorAttachment, TextureCache},
-        view::{
-            ExtractedView, RenderVisibleEntities, RetainedViewEntity, ViewDepthTexture, ViewTarget,
-        },
+        texture::ColorAttachment,
+        view::{ExtractedView, RenderVisibleEntities, ViewDepthTexture, ViewTarget},
     },
 };
 use nonmax::NonMaxU32;
-use std::ops::Range;
 
-pub mod post_process;
+use crate::{
+    SectionGroupId,
+    section_phase::{
+        SectionPhaseItem, add_section_phase, draw_section_phase, section_data_layout,
+        section_shader_stages, section_vertex_layout, write_batch_indirect_parameters_metadata,
+    },
+};
 
 // A "high" random id should be used for custom
 // attributes to ensure consistent sorting and
@@ -72,96 +82,201 @@ pub mod post_process;
 pub const ATTRIBUTE_SECTION_COLOR: MeshVertexAttribute =
     MeshVertexAttribute::new("_SECTION_COLOR", 923949917, VertexFormat::Float32x4);
 
-const SHADER_ASSET_PATH: &str = "custom_phase.wgsl";
+pub(crate) const SECTION_TEXTURE_FORMAT: TextureFormat = ViewTarget::TEXTURE_FORMAT_HDR;
+
+const SHADER_ASSET_PATH: &str = "embedded://bevy_section_outlines/shaders/section_texture.wgsl";
 
+/// Marks a `Mesh3d`, `Mesh2d` or `Sprite` to be drawn
+/// into the section texture of every camera with
+/// [`SectionsPrepass`].
+///
+/// Its `ExtractComponentPlugin` makes it require
+/// `SyncToRenderWorld`, so sprites reach the render
+/// world too.
 #[derive(Component, ExtractComponent, Clone, Copy, Default)]
 pub struct DrawSection;
 
-#[derive(Component, ExtractComponent, Clone, Copy, ShaderType)]
-pub struct SectionGroupId {
-    pub id: u32,
+/// How the sections of a [`DrawSection`] mesh are
+/// outlined. Meshes without an `OutlineStyle` use the
+/// camera's [`OutlineSettings`](crate::OutlineSettings).
+///
+/// Styles are drawn in the same pass, so selected,
+/// hovered and enemy meshes can each have their own.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component, Default, Debug, PartialEq)]
+pub struct OutlineStyle {
+    pub color: Color,
+    /// Width of the outline in pixels
+    pub width: u32,
+    /// Fills the mesh with this color, under the
+    /// outline. The fill's alpha blends it with the
+    /// shaded mesh.
+    pub fill: Option<Color>,
+    /// Also draws the silhouette of the mesh where
+    /// it's hidden behind other meshes.
+    pub draw_occluded: bool,
 }
 
-impl FromWorld for SectionGroupId {
-    fn from_world(world: &mut World) -> Self {
-        let new_id = world
-            .resource_mut::<SectionGroupIdGenerator>()
-            .generate_id();
-        Self { id: new_id }
+impl Default for OutlineStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            width: 2,
+            fill: None,
+            draw_occluded: false,
+        }
     }
 }
 
-#[derive(Resource, Reflect, Default)]
-struct SectionGroupIdGenerator(u32);
+/// Which outline style a mesh's pixels in the section
+/// texture use. The texture stores it as a float, see
+/// [`StyleIndex::texture_value`]. Pixels nothing was
+/// drawn to are cleared to 0, which isn't outlined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StyleIndex {
+    /// Meshes without an [`OutlineStyle`], or past
+    /// [`MAX_STYLES`], use the camera's
+    /// [`OutlineSettings`](crate::OutlineSettings).
+    Camera,
+    /// This entry of [`OutlineStyles`].
+    Style(u32),
+}
 
-impl SectionGroupIdGenerator {
-    fn generate_id(&mut self) -> u32 {
-        self.0 += 1;
-        self.0
+impl StyleIndex {
+    /// The value written into the section texture. Has
+    /// to match the constants in `outline.wgsl`.
+    pub(crate) fn texture_value(self) -> u32 {
+        match self {
+            StyleIndex::Camera => 1,
+            StyleIndex::Style(index) => FIRST_STYLE_VALUE + index,
+        }
     }
 }
 
-fn insert_section_ids(
-    inserted: On<Insert, Mesh3d>,
-    mut commands: Commands,
-    mut generator: ResMut<SectionGroupIdGenerator>,
-    query: Query<&SectionGroupId>,
-) {
-    if query.get(inserted.entity).is_err() {
-        commands.entity(inserted.entity).insert(SectionGroupId {
-            id: generator.generate_id(),
-        });
+/// [`StyleIndex::texture_value`] of the first entry in
+/// [`OutlineStyles`].
+const FIRST_STYLE_VALUE: u32 = 2;
+/// The most styles the section texture can index,
+/// every integer up to 2048 is exact in an f16.
+const MAX_STYLES: usize = 2048 - FIRST_STYLE_VALUE as usize;
+
+/// [`OutlineStyle`] as the outline shader reads it.
+#[derive(ShaderType, Clone, Copy, Default, PartialEq)]
+pub(crate) struct GpuOutlineStyle {
+    color: Vec4,
+    fill: Vec4,
+    width: u32,
+    /// bit 0 is set when `fill` is used
+    flags: u32,
+}
+
+const STYLE_FLAG_FILL: u32 = 1;
+
+impl From<&OutlineStyle> for GpuOutlineStyle {
+    fn from(style: &OutlineStyle) -> Self {
+        Self {
+            color: style.color.to_linear().to_vec4(),
+            fill: style
+                .fill
+                .map(|fill| fill.to_linear().to_vec4())
+                .unwrap_or_default(),
+            width: style.width,
+            flags: if style.fill.is_some() {
+                STYLE_FLAG_FILL
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// The per-mesh data of the section pass.
+#[derive(Component, Clone, Copy, ShaderType)]
+pub(crate) struct SectionUniform {
+    group_id: u32,
+    /// [`StyleIndex::texture_value`] of the mesh's
+    /// style.
+    style: u32,
+}
+
+/// Render world marker for meshes with
+/// [`OutlineStyle::draw_occluded`].
+#[derive(Component)]
+pub(crate) struct DrawOccluded;
+
+/// Every distinct [`OutlineStyle`] of this frame,
+/// uploaded as one storage buffer for the outline
+/// pass to index.
+#[derive(Resource, Default)]
+pub(crate) struct OutlineStyles {
+    styles: Vec<GpuOutlineStyle>,
+    pub(crate) buffer: StorageBuffer<Vec<GpuOutlineStyle>>,
+}
+
+impl OutlineStyles {
+    /// The section texture's style index for `style`,
+    /// or `None` if the texture can't index any more.
+    fn index(&mut self, style: &OutlineStyle) -> Option<StyleIndex> {
+        // identical styles share an index, so a
+        // whole selection costs one entry
+        let gpu_style = GpuOutlineStyle::from(style);
+        let index = match self
+            .styles
+            .iter()
+            .position(|existing| *existing == gpu_style)
+        {
+            Some(index) => index,
+            None if self.styles.len() < MAX_STYLES => {
+                self.styles.push(gpu_style);
+                self.styles.len() - 1
+            }
+            None => return None,
+        };
+        Some(StyleIndex::Style(index as u32))
     }
 }
 
 #[derive(Resource, Default)]
-pub struct SectionDataBindGroups(Option<BindGroup>);
+pub(crate) struct SectionDataBindGroups(Option<BindGroup>);
 
-pub struct SectionTexturePhasePlugin;
+/// Renders every [`DrawSection`] mesh's section colors
+/// into the [`SectionTexture`] of cameras with
+/// [`SectionsPrepass`].
+pub(crate) struct SectionTexturePhasePlugin;
 
 impl Plugin for SectionTexturePhasePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<SectionGroupIdGenerator>().add_plugins((
+        app.register_type::<OutlineStyle>().add_plugins((
             ExtractComponentPlugin::<DrawSection>::default(),
-            ExtractComponentPlugin::<SectionGroupId>::default(),
-            UniformComponentPlugin::<SectionGroupId>::default(),
+            UniformComponentPlugin::<SectionUniform>::default(),
             SortedRenderPhasePlugin::<SectionTexturePhase, MeshPipeline>::new(
                 RenderDebugFlags::default(),
             ),
         ));
 
-        // TODO: one day with Constructs we can register a
-        // required component that implements
-        // FromWorld, not Default
-        // app.register_required_components::<Mesh3d,
-        // SectionGroupId>(); until then,
-        // observers.
-        app.add_observer(insert_section_ids);
-
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
         };
         render_app
             .init_resource::<SectionDataBindGroups>()
+            .init_resource::<OutlineStyles>()
             .init_resource::<SpecializedMeshPipelines<SectionTexturePipeline>>()
-            .init_resource::<DrawFunctions<SectionTexturePhase>>()
-            .add_render_command::<SectionTexturePhase, DrawMesh3dSectionTexture>()
-            .init_resource::<ViewSortedRenderPhases<SectionTexturePhase>>()
-            .add_systems(ExtractSchedule, extract_camera_phases)
+            .add_systems(ExtractSchedule, extract_sections)
             .add_systems(
                 Render,
                 (
-                    sort_phase_system::<SectionTexturePhase>.in_set(RenderSystems::PhaseSort),
                     batch_and_prepare_sorted_render_phase::<
                         SectionTexturePhase,
                         SectionTexturePipeline,
                     >
                         .in_set(RenderSystems::PrepareResources),
+                    prepare_outline_styles.in_set(RenderSystems::PrepareResources),
                     prepare_section_data_bind_group.in_set(RenderSystems::PrepareBindGroups),
                     queue_custom_meshes.in_set(RenderSystems::QueueMeshes),
-                    prepare_section_textures.in_set(RenderSystems::PrepareResources),
                 ),
             );
+        add_section_phase::<SectionTexturePhase, Camera3d>(render_app);
+        render_app.add_render_command::<SectionTexturePhase, DrawMesh3dSectionTexture>();
 
         render_app
             .add_render_graph_node::<ViewNodeRunner<CustomDrawNode>>(Core3d, CustomDrawPassLabel)
@@ -182,7 +297,7 @@ impl Plugin for SectionTexturePhasePlugin {
 }
 
 #[derive(Resource)]
-pub struct SectionTexturePipeline {
+pub(crate) struct SectionTexturePipeline {
     /// The base mesh pipeline defined by bevy
     ///
     /// Since we want to draw using an existing
@@ -204,49 +319,64 @@ impl FromWorld for SectionTexturePipeline {
         Self {
             mesh_pipeline: MeshPipeline::from_world(world),
             shader_handle: world.resource::<AssetServer>().load(SHADER_ASSET_PATH),
-            section_data_layout: BindGroupLayoutDescriptor::new(
-                "section_data_bind_group_layout",
-                &BindGroupLayoutEntries::single(
-                    ShaderStages::VERTEX_FRAGMENT,
-                    BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: true,
-                        min_binding_size: None,
-                    },
-                ),
-            ),
+            section_data_layout: section_data_layout(),
         }
     }
 }
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct SectionPipelineKey {
+    mesh_key: MeshPipelineKey,
+    /// Writes the mesh's style into the occluded
+    /// silhouette channel as well
+    draw_occluded: bool,
+    /// Draws only the parts of the mesh hidden
+    /// behind other meshes, into the occluded
+    /// silhouette channel
+    hidden: bool,
+}
+
 // For more information on how
 // SpecializedMeshPipeline work, please look at
 // the specialized_mesh_pipeline example
 impl SpecializedMeshPipeline for SectionTexturePipeline {
-    type Key = MeshPipelineKey;
+    type Key = SectionPipelineKey;
 
     fn specialize(
         &self,
         key: Self::Key,
         layout: &MeshVertexBufferLayoutRef,
     ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
-        let mut vertex_attributes = Vec::new();
         let mut shader_defs = Vec::new();
-
-        // add the position
-        if layout.0.contains(Mesh::ATTRIBUTE_POSITION) {
-            // @location(0)
-            vertex_attributes.push(Mesh::ATTRIBUTE_POSITION.at_shader_location(0));
-        }
-        // add the section color
-        if layout.0.contains(ATTRIBUTE_SECTION_COLOR) {
-            // @location(1)
-            shader_defs.push("SECTION_COLORS".into());
-            vertex_attributes.push(ATTRIBUTE_SECTION_COLOR.at_shader_location(1));
-        }
-        // This will automatically generate the correct
-        // `VertexBufferLayout` based on the vertex
-        // attributes
-        let vertex_buffer_layout = layout.0.get_layout(&vertex_attributes)?;
+        let vertex_buffer_layout = section_vertex_layout(layout, &mut shader_defs)?;
+
+        // the section texture channels are
+        // r: section id, g: style, b: occluded silhouette style
+        let (write_mask, depth_compare, depth_write_enabled) = if key.hidden {
+            shader_defs.push("HIDDEN".into());
+            // reverse-z, so anything further than the
+            // depth buffer is hidden
+            (ColorWrites::BLUE, CompareFunction::Less, false)
+        } else if key.draw_occluded {
+            (
+                ColorWrites::RED | ColorWrites::GREEN | ColorWrites::BLUE,
+                CompareFunction::GreaterEqual,
+                true,
+            )
+        } else {
+            (
+                ColorWrites::RED | ColorWrites::GREEN,
+                CompareFunction::GreaterEqual,
+                true,
+            )
+        };
+        let key = key.mesh_key;
+        let (vertex, fragment) = section_shader_stages(
+            &self.shader_handle,
+            shader_defs,
+            vec![vertex_buffer_layout],
+            write_mask,
+        );
 
         Ok(RenderPipelineDescriptor {
             label: Some("Specialized Mesh Pipeline".into()),
@@ -269,27 +399,8 @@ impl SpecializedMeshPipeline for SectionTexturePipeline {
                 self.section_data_layout.clone(),
             ],
             push_constant_ranges: vec![],
-            vertex: VertexState {
-                shader: self.shader_handle.clone(),
-                shader_defs: shader_defs.clone(),
-                entry_point: Some("vertex".into()),
-                buffers: vec![vertex_buffer_layout],
-            },
-            fragment: Some(FragmentState {
-                shader: self.shader_handle.clone(),
-                shader_defs,
-                entry_point: Some("fragment".into()),
-                targets: vec![Some(ColorTargetState {
-                    format: if key.contains(MeshPipelineKey::HDR) {
-                        ViewTarget::TEXTURE_FORMAT_HDR
-                    } else {
-                        TextureFormat::bevy_default()
-                    },
-                    // format: TextureFormat::bevy_default(),
-                    blend: None,
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
+            vertex,
+            fragment,
             primitive: PrimitiveState {
                 topology: key.primitive_topology(),
                 front_face: FrontFace::Ccw,
@@ -299,8 +410,8 @@ impl SpecializedMeshPipeline for SectionTexturePipeline {
             },
             depth_stencil: Some(DepthStencilState {
                 format: CORE_3D_DEPTH_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: CompareFunction::GreaterEqual,
+                depth_write_enabled,
+                depth_compare,
                 stencil: default(),
                 bias: default(),
             }),
@@ -329,103 +440,8 @@ type DrawMesh3dSectionTexture = (
     DrawMesh,
 );
 
-// This is the data required when you define a
-// custom phase in bevy. More specifically this is
-// the data required when using a
-// ViewSortedRenderPhase. This would look
-// differently if we wanted a batched render
-// phase. Sorted phase are a bit easier to
-// implement, but a batched phased would
-// look similar.
-//
-// If you want to see how a batched phase
-// implementation looks, you should look at the
-// Opaque2d phase.
-struct SectionTexturePhase {
-    pub sort_key: FloatOrd,
-    pub entity: (Entity, MainEntity),
-    pub pipeline: CachedRenderPipelineId,
-    pub draw_function: DrawFunctionId,
-    pub batch_range: Range<u32>,
-    pub extra_index: PhaseItemExtraIndex,
-    /// Whether the mesh in question is indexed
-    /// (uses an index buffer in addition to
-    /// its vertex buffer).
-    pub indexed: bool,
-}
-
-// For more information about writing a phase
-// item, please look at the custom_phase_item
-// example
-impl PhaseItem for SectionTexturePhase {
-    #[inline]
-    fn entity(&self) -> Entity {
-        self.entity.0
-    }
-
-    #[inline]
-    fn main_entity(&self) -> MainEntity {
-        self.entity.1
-    }
-
-    #[inline]
-    fn draw_function(&self) -> DrawFunctionId {
-        self.draw_function
-    }
-
-    #[inline]
-    fn batch_range(&self) -> &Range<u32> {
-        &self.batch_range
-    }
-
-    #[inline]
-    fn batch_range_mut(&mut self) -> &mut Range<u32> {
-        &mut self.batch_range
-    }
-
-    #[inline]
-    fn extra_index(&self) -> PhaseItemExtraIndex {
-        self.extra_index.clone()
-    }
-
-    #[inline]
-    fn batch_range_and_extra_index_mut(&mut self) -> (&mut Range<u32>, &mut PhaseItemExtraIndex) {
-        (&mut self.batch_range, &mut self.extra_index)
-    }
-}
-
-impl SortedPhaseItem for SectionTexturePhase {
-    type SortKey = FloatOrd;
-
-    #[inline]
-    fn sort_key(&self) -> Self::SortKey {
-        self.sort_key
-    }
-
-    #[inline]
-    fn sort(items: &mut [Self]) {
-        // bevy normally uses radsort instead of the std
-        // slice::sort_by_key radsort is a stable
-        // radix sort that performed better than
-        // `slice::sort_by_key` or
-        // `slice::sort_unstable_by_key`. Since it
-        // is not re-exported by bevy, we just use the std
-        // sort for the purpose of the example
-        items.sort_by_key(SortedPhaseItem::sort_key);
-    }
-
-    #[inline]
-    fn indexed(&self) -> bool {
-        self.indexed
-    }
-}
-
-impl CachedRenderPipelinePhaseItem for SectionTexturePhase {
-    #[inline]
-    fn cached_pipeline(&self) -> CachedRenderPipelineId {
-        self.pipeline
-    }
-}
+/// The sorted phase of the 3d section pass.
+type SectionTexturePhase = SectionPhaseItem<Core3d>;
 
 impl GetBatchData for SectionTexturePipeline {
     type Param = (
@@ -532,27 +548,13 @@ impl GetFullBatchData for SectionTexturePipeline {
         indirect_parameters_buffers: &mut UntypedPhaseIndirectParametersBuffers,
         indirect_parameters_offset: u32,
     ) {
-        // Note that `IndirectParameters` covers both of
-        // these structures, even though they
-        // actually have distinct layouts. See the comment
-        // above that type for more information.
-        let indirect_parameters = IndirectParametersCpuMetadata {
+        write_batch_indirect_parameters_metadata(
+            indexed,
             base_output_index,
-            batch_set_index: match batch_set_index {
-                None => !0,
-                Some(batch_set_index) => u32::from(batch_set_index),
-            },
-        };
-
-        if indexed {
-            indirect_parameters_buffers
-                .indexed
-                .set(indirect_parameters_offset, indirect_parameters);
-        } else {
-            indirect_parameters_buffers
-                .non_indexed
-                .set(indirect_parameters_offset, indirect_parameters);
-        }
+            batch_set_index,
+            indirect_parameters_buffers,
+            indirect_parameters_offset,
+        );
     }
 
     fn get_binned_index(
@@ -563,40 +565,58 @@ impl GetFullBatchData for SectionTexturePipeline {
     }
 }
 
-// When defining a custom phase, we need to
-// extract it from the main world and add it to a
-// resource that will be used by the render world.
-// We need to give that resource all views that
-// will use that phase
-fn extract_camera_phases(
+/// Gives every [`DrawSection`] mesh its section
+/// uniform, and collects the distinct
+/// [`OutlineStyle`]s of the frame.
+fn extract_sections(
     mut commands: Commands,
-    mut sections_phases: ResMut<ViewSortedRenderPhases<SectionTexturePhase>>,
-    cameras: Extract<Query<(RenderEntity, Entity, &Camera, Has<SectionsPrepass>), With<Camera3d>>>,
-    mut live_entities: Local<HashSet<RetainedViewEntity>>,
+    mut outline_styles: ResMut<OutlineStyles>,
+    sections: Extract<
+        Query<(RenderEntity, &SectionGroupId, Option<&OutlineStyle>), With<DrawSection>>,
+    >,
 ) {
-    live_entities.clear();
-    for (render_entity, main_entity, camera, has_sections_prepass) in &cameras {
-        if !camera.is_active {
-            continue;
-        }
-        // This is the main camera, so we use the first
-        // subview index (0)
-        let retained_view_entity = RetainedViewEntity::new(main_entity.into(), None, 0);
-
-        if has_sections_prepass {
-            sections_phases.insert_or_clear(retained_view_entity);
+    outline_styles.styles.clear();
+    for (render_entity, group, style) in &sections {
+        let mut entity = commands.entity(render_entity);
+        if style.is_some_and(|style| style.draw_occluded) {
+            entity.insert(DrawOccluded);
         } else {
-            sections_phases.remove(&retained_view_entity);
+            entity.remove::<DrawOccluded>();
         }
-        live_entities.insert(retained_view_entity);
 
-        commands
-            .get_entity(render_entity)
-            .expect("Camera entity wasn't synced.")
-            .insert_if(SectionsPrepass, || has_sections_prepass);
+        let style = style.map_or(StyleIndex::Camera, |style| {
+            outline_styles.index(style).unwrap_or_else(|| {
+                warn_once!(
+                    "More than {MAX_STYLES} distinct OutlineStyles, the rest use the camera's OutlineSettings"
+                );
+                StyleIndex::Camera
+            })
+        });
+        entity.insert(SectionUniform {
+            group_id: group.id,
+            style: style.texture_value(),
+        });
     }
-    // Clear out all dead views.
-    sections_phases.retain(|camera_entity, _| live_entities.contains(camera_entity));
+}
+
+fn prepare_outline_styles(
+    mut outline_styles: ResMut<OutlineStyles>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    // WebGL2 outlines everything with the camera's
+    // OutlineSettings
+    if render_device.limits().max_storage_buffers_per_shader_stage == 0 {
+        return;
+    }
+    let OutlineStyles { styles, buffer } = &mut *outline_styles;
+    let mut data = styles.clone();
+    // storage bindings can't be empty
+    if data.is_empty() {
+        data.push(GpuOutlineStyle::default());
+    }
+    buffer.set(data);
+    buffer.write_buffer(&render_device, &render_queue);
 }
 
 // This is a very important step when writing a
@@ -614,7 +634,7 @@ fn queue_custom_meshes(
     render_mesh_instances: Res<RenderMeshInstances>,
     mut custom_render_phases: ResMut<ViewSortedRenderPhases<SectionTexturePhase>>,
     mut views: Query<(&ExtractedView, &RenderVisibleEntities, &Msaa)>,
-    has_marker: Query<(), With<DrawSection>>,
+    has_marker: Query<Has<DrawOccluded>, With<DrawSection>>,
 ) {
     for (view, visible_entities, msaa) in &mut views {
         let Some(custom_phase) = custom_render_phases.get_mut(&view.retained_view_entity) else {
@@ -636,9 +656,9 @@ fn queue_custom_meshes(
         for (render_entity, visible_entity) in visible_entities.iter::<Mesh3d>() {
             // We only want meshes with the marker component
             // to be queued to our phase.
-            if has_marker.get(*render_entity).is_err() {
+            let Ok(draw_occluded) = has_marker.get(*render_entity) else {
                 continue;
-            }
+            };
             let Some(mesh_instance) = render_mesh_instances.render_mesh_queue_data(*visible_entity)
             else {
                 continue;
@@ -655,35 +675,45 @@ fn queue_custom_meshes(
             let mut mesh_key = view_key;
             mesh_key |= MeshPipelineKey::from_primitive_topology(mesh.primitive_topology());
 
-            let pipeline_id = pipelines.specialize(
-                &pipeline_cache,
-                &custom_draw_pipeline,
-                mesh_key,
-                &mesh.layout,
-            );
-            let pipeline_id = match pipeline_id {
-                Ok(id) => id,
-                Err(err) => {
-                    error!("{}", err);
-                    continue;
-                }
-            };
             let distance = rangefinder.distance(&mesh_instance.center);
-            // At this point we have all the data we need to
-            // create a phase item and add it to our
-            // phase
-            custom_phase.add(SectionTexturePhase {
-                // Sort the data based on the distance to
-                // the view
-                sort_key: FloatOrd(distance),
-                entity: (*render_entity, *visible_entity),
-                pipeline: pipeline_id,
-                draw_function: draw_custom,
-                // Sorted phase items aren't batched
-                batch_range: 0..1,
-                extra_index: PhaseItemExtraIndex::None,
-                indexed: mesh.indexed(),
-            });
+            // meshes drawn through occluders get a
+            // second draw for their hidden parts
+            let passes: &[bool] = if draw_occluded {
+                &[false, true]
+            } else {
+                &[false]
+            };
+            for &hidden in passes {
+                let pipeline_id = pipelines.specialize(
+                    &pipeline_cache,
+                    &custom_draw_pipeline,
+                    SectionPipelineKey {
+                        mesh_key,
+                        draw_occluded,
+                        hidden,
+                    },
+                    &mesh.layout,
+                );
+                let pipeline_id = match pipeline_id {
+                    Ok(id) => id,
+                    Err(err) => {
+                        error!("{}", err);
+                        continue;
+                    }
+                };
+                // At this point we have all the data we need to
+                // create a phase item and add it to our
+                // phase
+                custom_phase.add(SectionTexturePhase::new(
+                    // Sort the data based on the distance to
+                    // the view
+                    distance,
+                    (*render_entity, *visible_entity),
+                    pipeline_id,
+                    draw_custom,
+                    mesh.indexed(),
+                ));
+            }
         }
     }
 }
@@ -721,10 +751,6 @@ impl ViewNode for CustomDrawNode {
         // not required but makes profiling easier
         let diagnostics = render_context.diagnostic_recorder();
 
-        // this target writes directly to output. keeping
-        // as maybe potentially useful for debug?
-        // let color_attachments =
-        //     [Some(target.get_color_attachment())];
         // write to the section texture
         let color_attachments = [section_texture
             .sections
@@ -734,14 +760,6 @@ impl ViewNode for CustomDrawNode {
         // Get the view entity from the graph
         let view_entity = graph.view_entity();
 
-        // let group_id_uniforms = world
-        //     .resource::<ComponentUniforms<SectionGroupId>>(
-        //     );
-        // let Some(group_id_binding) =
-        //     group_id_uniforms.uniforms().binding()
-        // else {
-        //     return Ok(());
-        // };
         // Get the phase for the current view running our
         // node
         let Some(section_phase) = section_phases.get(&view.retained_view_entity) else {
@@ -773,17 +791,7 @@ impl ViewNode for CustomDrawNode {
             let mut render_pass = TrackedRenderPass::new(&render_device, render_pass);
             let pass_span = diagnostics.pass_span(&mut render_pass, "custom_pass");
 
-            if let Some(viewport) = camera.viewport.as_ref() {
-                render_pass.set_camera_viewport(viewport);
-            }
-
-            // Render the phase
-            if !section_phase.items.is_empty() {
-                if let Err(err) = section_phase.render(&mut render_pass, world, view_entity) {
-                    error!("Error encountered while rendering the custom phase {err:?}");
-                }
-            }
-            // render_pass.set_bind_group(2, group_id_binding, &[]);
+            draw_section_phase(&mut render_pass, camera, section_phase, world, view_entity);
 
             pass_span.end(&mut render_pass);
             drop(render_pass);
@@ -794,6 +802,7 @@ impl ViewNode for CustomDrawNode {
     }
 }
 
+/// The section texture of a view, in the render world.
 #[derive(Component)]
 pub struct SectionTexture {
     /// The section texture generated by the
@@ -803,84 +812,23 @@ pub struct SectionTexture {
     pub size: Extent3d,
 }
 
-#[derive(Component)]
+/// Renders a [`SectionTexture`] for this camera.
+///
+/// Required by [`OutlineSettings`](crate::OutlineSettings).
+#[derive(Component, Default)]
 pub struct SectionsPrepass;
 
-/// Prepares the textures used by the section pass
-fn prepare_section_textures(
-    mut commands: Commands,
-    mut texture_cache: ResMut<TextureCache>,
-    render_device: Res<RenderDevice>,
-    sections_phases: Res<ViewSortedRenderPhases<SectionTexturePhase>>,
-    views_3d: Query<(
-        Entity,
-        &ExtractedCamera,
-        &ExtractedView,
-        &Msaa,
-        Has<SectionsPrepass>,
-    )>,
-) {
-    let mut sections_textures = <HashMap<_, _>>::default();
-
-    for (entity, camera, view, msaa, sections_prepass) in &views_3d {
-        if !sections_phases.contains_key(&view.retained_view_entity) {
-            continue;
-        };
-
-        let Some(physical_target_size) = camera.physical_target_size else {
-            continue;
-        };
-
-        let size = Extent3d {
-            depth_or_array_layers: 1,
-            width: physical_target_size.x,
-            height: physical_target_size.y,
-        };
-
-        let cached_sections_texture = sections_prepass.then(|| {
-            sections_textures
-                .entry(camera.target.clone())
-                .or_insert_with(|| {
-                    let descriptor = TextureDescriptor {
-                        label: Some("prepass_sections_texture"),
-                        size,
-                        mip_level_count: 1,
-                        sample_count: msaa.samples(),
-                        dimension: TextureDimension::D2,
-                        format: if view.hdr {
-                            ViewTarget::TEXTURE_FORMAT_HDR
-                        } else {
-                            TextureFormat::bevy_default()
-                        },
-                        usage: TextureUsages::COPY_DST
-                            | TextureUsages::RENDER_ATTACHMENT
-                            | TextureUsages::TEXTURE_BINDING,
-                        view_formats: &[],
-                    };
-                    texture_cache.get(&render_device, descriptor)
-                })
-                .clone()
-        });
-
-        commands.entity(entity).insert(SectionTexture {
-            sections: cached_sections_texture
-                .map(|t| ColorAttachment::new(t, None, None, Some(LinearRgba::BLACK))),
-            size,
-        });
-    }
-}
-
-pub struct SetSectionDataBindGroup<const I: usize>;
+pub(crate) struct SetSectionDataBindGroup<const I: usize>;
 impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetSectionDataBindGroup<I> {
     type Param = (SRes<SectionDataBindGroups>,);
     type ViewQuery = ();
-    type ItemQuery = &'static DynamicUniformIndex<SectionGroupId>;
+    type ItemQuery = &'static DynamicUniformIndex<SectionUniform>;
 
     #[inline]
     fn render<'w>(
         _item: &P,
         _view: (),
-        item_query: Option<&DynamicUniformIndex<SectionGroupId>>,
+        item_query: Option<&DynamicUniformIndex<SectionUniform>>,
         (section_groups,): SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
@@ -888,7 +836,7 @@ impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetSectionDataBindGroup<
 
         pass.set_bind_group(
             I,
-            &section_groups.0.as_ref().unwrap(),
+            section_groups.0.as_ref().unwrap(),
             &[item_query.unwrap().index()],
         );
 
@@ -900,7 +848,7 @@ fn prepare_section_data_bind_group(
     mut commands: Commands,
     section_texture_pipeline: Res<SectionTexturePipeline>,
     render_device: Res<RenderDevice>,
-    uniforms: Res<ComponentUniforms<SectionGroupId>>,
+    uniforms: Res<ComponentUniforms<SectionUniform>>,
     pipeline_cache: Res<PipelineCache>,
 ) {
     // todo: maybe reset the resource instead?
@@ -915,3 +863,28 @@ fn prepare_section_data_bind_group(
     );
     commands.insert_resource(SectionDataBindGroups(Some(bind_group)));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(width: u32) -> OutlineStyle {
+        OutlineStyle { width, ..default() }
+    }
+
+    #[test]
+    fn styles_past_max_styles_fall_back_to_the_camera() {
+        let mut styles = OutlineStyles::default();
+        for width in 0..MAX_STYLES as u32 {
+            assert_eq!(styles.index(&style(width)), Some(StyleIndex::Style(width)));
+        }
+        // identical styles keep sharing their entry
+        assert_eq!(styles.index(&style(7)), Some(StyleIndex::Style(7)));
+        assert_eq!(styles.index(&style(MAX_STYLES as u32)), None);
+
+        // the last style is still exact in the f16 texture
+        let last = StyleIndex::Style(MAX_STYLES as u32 - 1).texture_value();
+        assert_eq!(last, 2047);
+        assert_eq!(StyleIndex::Camera.texture_value(), 1);
+    }
+}
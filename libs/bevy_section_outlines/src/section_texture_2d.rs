@@ -0,0 +1,623 @@
+use bevy::{
+    core_pipeline::core_2d::graph::{Core2d, Node2d},
+    ecs::{
+        query::QueryItem,
+        system::{
+            SystemParamItem,
+            lifetimeless::{Read, SRes},
+        },
+    },
+    image::TextureAtlasLayout,
+    math::Affine3A,
+    mesh::MeshVertexBufferLayoutRef,
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        Extract, Render, RenderApp, RenderDebugFlags, RenderStartup, RenderSystems,
+        batching::{
+            GetBatchData, GetFullBatchData,
+            gpu_preprocessing::UntypedPhaseIndirectParametersBuffers,
+        },
+        camera::ExtractedCamera,
+        diagnostic::RecordDiagnostics,
+        extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
+        mesh::RenderMesh,
+        render_asset::RenderAssets,
+        render_graph::{
+            NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, RenderCommand, RenderCommandResult,
+            SetItemPipeline, SortedRenderPhasePlugin, TrackedRenderPass, ViewSortedRenderPhases,
+        },
+        render_resource::{
+            binding_types::{sampler, texture_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        sync_world::{MainEntity, RenderEntity},
+        texture::GpuImage,
+        view::{ExtractedView, RenderVisibleEntities},
+    },
+    sprite::Anchor,
+    sprite_render::{
+        DrawMesh2d, Mesh2dPipeline, Mesh2dPipelineKey, Mesh2dUniform, RenderMesh2dInstances,
+        SetMesh2dBindGroup, SetMesh2dViewBindGroup, init_mesh_2d_pipeline,
+    },
+};
+use nonmax::NonMaxU32;
+
+use crate::{
+    DrawSection,
+    section_phase::{
+        SectionPhaseItem, add_section_phase, draw_section_phase, section_data_layout,
+        section_shader_stages, section_vertex_layout, write_batch_indirect_parameters_metadata,
+    },
+    section_texture::{SectionTexture, SetSectionDataBindGroup},
+};
+
+const MESH_SHADER_ASSET_PATH: &str =
+    "embedded://bevy_section_outlines/shaders/section_texture_2d.wgsl";
+const SPRITE_SHADER_ASSET_PATH: &str =
+    "embedded://bevy_section_outlines/shaders/section_sprite.wgsl";
+
+/// Renders every [`DrawSection`] `Mesh2d` and `Sprite`
+/// into the [`SectionTexture`] of 2d cameras with
+/// [`SectionsPrepass`](crate::SectionsPrepass).
+///
+/// There is no depth in 2d: sections are drawn back to
+/// front, so the topmost mesh or sprite owns a pixel.
+pub(crate) struct SectionTexture2dPlugin;
+
+impl Plugin for SectionTexture2dPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            UniformComponentPlugin::<SectionSpriteUniform>::default(),
+            SortedRenderPhasePlugin::<SectionTexture2dPhase, SectionTexture2dPipeline>::new(
+                RenderDebugFlags::default(),
+            ),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        add_section_phase::<SectionTexture2dPhase, Camera2d>(render_app);
+        render_app
+            .init_resource::<SectionSpriteBindGroups>()
+            .init_resource::<SpecializedMeshPipelines<SectionTexture2dPipeline>>()
+            .add_render_command::<SectionTexture2dPhase, DrawMesh2dSectionTexture>()
+            .add_render_command::<SectionTexture2dPhase, DrawSpriteSectionTexture>()
+            .add_systems(
+                RenderStartup,
+                // the mesh pipeline's layouts are shared
+                init_section_texture_2d_pipeline.after(init_mesh_2d_pipeline),
+            )
+            .add_systems(ExtractSchedule, extract_section_sprites)
+            .add_systems(
+                Render,
+                (
+                    queue_sections_2d.in_set(RenderSystems::QueueMeshes),
+                    prepare_section_sprite_bind_groups.in_set(RenderSystems::PrepareBindGroups),
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<SectionTexture2dNode>>(
+                Core2d,
+                SectionTexture2dPassLabel,
+            )
+            // after everything is drawn, before the
+            // outline post-process reads the texture
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::MainTransparentPass,
+                    SectionTexture2dPassLabel,
+                    Node2d::EndMainPass,
+                ),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct SectionTexture2dPipeline {
+    /// Bevy's 2d mesh pipeline, for its view and mesh
+    /// bind group layouts
+    mesh2d_pipeline: Mesh2dPipeline,
+    shader_handle: Handle<Shader>,
+    section_data_layout: BindGroupLayoutDescriptor,
+    /// The quad and image of a sprite
+    sprite_layout: BindGroupLayoutDescriptor,
+    /// Sprites are all drawn the same way, so they
+    /// only need the one pipeline
+    sprite_pipeline_id: CachedRenderPipelineId,
+}
+
+fn init_section_texture_2d_pipeline(
+    mut commands: Commands,
+    mesh2d_pipeline: Res<Mesh2dPipeline>,
+    asset_server: Res<AssetServer>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let sprite_layout = BindGroupLayoutDescriptor::new(
+        "section_sprite_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::VERTEX_FRAGMENT,
+            (
+                uniform_buffer::<SectionSpriteUniform>(true),
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                sampler(SamplerBindingType::Filtering),
+            ),
+        ),
+    );
+
+    let (vertex, fragment) = section_shader_stages(
+        &asset_server.load(SPRITE_SHADER_ASSET_PATH),
+        vec![],
+        vec![],
+        ColorWrites::RED | ColorWrites::GREEN,
+    );
+    let sprite_pipeline_id = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+        label: Some("section_sprite_pipeline".into()),
+        layout: vec![
+            mesh2d_pipeline.view_layout.clone(),
+            sprite_layout.clone(),
+            section_data_layout(),
+        ],
+        push_constant_ranges: vec![],
+        vertex,
+        fragment,
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        zero_initialize_workgroup_memory: false,
+    });
+
+    commands.insert_resource(SectionTexture2dPipeline {
+        mesh2d_pipeline: mesh2d_pipeline.clone(),
+        shader_handle: asset_server.load(MESH_SHADER_ASSET_PATH),
+        section_data_layout: section_data_layout(),
+        sprite_layout,
+        sprite_pipeline_id,
+    });
+}
+
+impl SpecializedMeshPipeline for SectionTexture2dPipeline {
+    type Key = Mesh2dPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut shader_defs = Vec::new();
+        let vertex_buffer_layout = section_vertex_layout(layout, &mut shader_defs)?;
+        let (vertex, fragment) = section_shader_stages(
+            &self.shader_handle,
+            shader_defs,
+            vec![vertex_buffer_layout],
+            // r: section id, g: style. Nothing is
+            // occluded in 2d.
+            ColorWrites::RED | ColorWrites::GREEN,
+        );
+
+        Ok(RenderPipelineDescriptor {
+            label: Some("section_texture_2d_pipeline".into()),
+            layout: vec![
+                self.mesh2d_pipeline.view_layout.clone(),
+                self.mesh2d_pipeline.mesh_layout.clone(),
+                self.section_data_layout.clone(),
+            ],
+            push_constant_ranges: vec![],
+            vertex,
+            fragment,
+            primitive: PrimitiveState {
+                topology: key.primitive_topology(),
+                front_face: FrontFace::Ccw,
+                // 2d meshes are often mirrored with a
+                // negative scale
+                cull_mode: None,
+                polygon_mode: PolygonMode::Fill,
+                ..default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            zero_initialize_workgroup_memory: false,
+        })
+    }
+}
+
+type DrawMesh2dSectionTexture = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    SetMesh2dBindGroup<1>,
+    SetSectionDataBindGroup<2>,
+    DrawMesh2d,
+);
+
+type DrawSpriteSectionTexture = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    SetSectionSpriteBindGroup<1>,
+    SetSectionDataBindGroup<2>,
+    DrawSectionSprite,
+);
+
+/// The sorted phase of the 2d section pass. Meshes and
+/// sprites share it, so they're drawn in z order.
+type SectionTexture2dPhase = SectionPhaseItem<Core2d>;
+
+/// The same per-mesh data as bevy's `Mesh2dPipeline`,
+/// without batching: every mesh binds its own
+/// [`SectionUniform`](crate::section_texture::SectionUniform).
+impl GetBatchData for SectionTexture2dPipeline {
+    type Param = SRes<RenderMesh2dInstances>;
+    type CompareData = ();
+    type BufferData = Mesh2dUniform;
+
+    fn get_batch_data(
+        mesh_instances: &SystemParamItem<Self::Param>,
+        (_entity, main_entity): (Entity, MainEntity),
+    ) -> Option<(Self::BufferData, Option<Self::CompareData>)> {
+        // sprites have no mesh instance, and are left
+        // as they were queued
+        let mesh_instance = mesh_instances.get(&main_entity)?;
+        Some((mesh2d_uniform(&mesh_instance.transforms), None))
+    }
+}
+
+impl GetFullBatchData for SectionTexture2dPipeline {
+    type BufferInputData = ();
+
+    fn get_binned_batch_data(
+        mesh_instances: &SystemParamItem<Self::Param>,
+        main_entity: MainEntity,
+    ) -> Option<Self::BufferData> {
+        let mesh_instance = mesh_instances.get(&main_entity)?;
+        Some(mesh2d_uniform(&mesh_instance.transforms))
+    }
+
+    fn get_index_and_compare_data(
+        _: &SystemParamItem<Self::Param>,
+        _main_entity: MainEntity,
+    ) -> Option<(NonMaxU32, Option<Self::CompareData>)> {
+        error!("2d meshes don't support GPU mesh uniform building");
+        None
+    }
+
+    fn get_binned_index(
+        _: &SystemParamItem<Self::Param>,
+        _main_entity: MainEntity,
+    ) -> Option<NonMaxU32> {
+        error!("2d meshes don't support GPU mesh uniform building");
+        None
+    }
+
+    fn write_batch_indirect_parameters_metadata(
+        indexed: bool,
+        base_output_index: u32,
+        batch_set_index: Option<NonMaxU32>,
+        indirect_parameters_buffers: &mut UntypedPhaseIndirectParametersBuffers,
+        indirect_parameters_offset: u32,
+    ) {
+        write_batch_indirect_parameters_metadata(
+            indexed,
+            base_output_index,
+            batch_set_index,
+            indirect_parameters_buffers,
+            indirect_parameters_offset,
+        );
+    }
+}
+
+fn mesh2d_uniform(transforms: &bevy::sprite_render::Mesh2dTransforms) -> Mesh2dUniform {
+    let (local_from_world_transpose_a, local_from_world_transpose_b) =
+        transforms.world_from_local.inverse_transpose_3x3();
+    Mesh2dUniform {
+        world_from_local: transforms.world_from_local.to_transpose(),
+        local_from_world_transpose_a,
+        local_from_world_transpose_b,
+        flags: transforms.flags,
+        tag: 0,
+    }
+}
+
+/// The quad a [`DrawSection`] sprite covers, and the
+/// part of its image shown on it.
+#[derive(Component, Clone, Copy, ShaderType)]
+pub(crate) struct SectionSpriteUniform {
+    world_from_local: Mat4,
+    /// xy: the uv of the quad's origin, zw: the uv size
+    /// of the quad, negative when flipped
+    uv_offset_scale: Vec4,
+}
+
+/// Render world data of a [`DrawSection`] sprite.
+#[derive(Component)]
+struct SectionSprite {
+    image: AssetId<Image>,
+    z: f32,
+}
+
+/// Computes the quad of every [`DrawSection`] sprite,
+/// the same way bevy's sprite pass does. Sliced and
+/// tiled sprites are outlined as one quad.
+fn extract_section_sprites(
+    mut commands: Commands,
+    images: Extract<Res<Assets<Image>>>,
+    texture_atlases: Extract<Res<Assets<TextureAtlasLayout>>>,
+    sprites: Extract<Query<(RenderEntity, &Sprite, &GlobalTransform, &Anchor), With<DrawSection>>>,
+) {
+    for (render_entity, sprite, transform, anchor) in &sprites {
+        // images that haven't loaded have no size yet
+        let Some(image_size) = images.get(&sprite.image).map(Image::size_f32) else {
+            commands
+                .entity(render_entity)
+                .remove::<(SectionSprite, SectionSpriteUniform)>();
+            continue;
+        };
+
+        let atlas_rect = sprite
+            .texture_atlas
+            .as_ref()
+            .and_then(|atlas| atlas.texture_rect(&texture_atlases))
+            .map(|rect| rect.as_rect());
+        let rect = match (atlas_rect, sprite.rect) {
+            (None, rect) | (rect, None) => rect,
+            (Some(atlas_rect), Some(rect)) => Some(Rect {
+                min: rect.min + atlas_rect.min,
+                max: rect.max + atlas_rect.min,
+            }),
+        };
+
+        let (mut uv_offset_scale, quad_size) = match rect {
+            Some(rect) => (
+                Vec4::new(
+                    rect.min.x / image_size.x,
+                    rect.max.y / image_size.y,
+                    rect.width() / image_size.x,
+                    -rect.height() / image_size.y,
+                ),
+                rect.size(),
+            ),
+            None => (Vec4::new(0., 1., 1., -1.), image_size),
+        };
+        if sprite.flip_x {
+            uv_offset_scale.x += uv_offset_scale.z;
+            uv_offset_scale.z *= -1.;
+        }
+        if sprite.flip_y {
+            uv_offset_scale.y += uv_offset_scale.w;
+            uv_offset_scale.w *= -1.;
+        }
+
+        let quad_size = sprite.custom_size.unwrap_or(quad_size);
+        let world_from_local = transform.affine()
+            * Affine3A::from_scale_rotation_translation(
+                quad_size.extend(1.),
+                Quat::IDENTITY,
+                (quad_size * (-anchor.as_vec() - Vec2::splat(0.5))).extend(0.),
+            );
+
+        commands.entity(render_entity).insert((
+            SectionSprite {
+                image: sprite.image.id(),
+                z: transform.translation().z,
+            },
+            SectionSpriteUniform {
+                world_from_local: world_from_local.into(),
+                uv_offset_scale,
+            },
+        ));
+    }
+}
+
+/// One bind group per sprite image, with the quads of
+/// every sprite at a dynamic offset.
+#[derive(Resource, Default)]
+struct SectionSpriteBindGroups(HashMap<AssetId<Image>, BindGroup>);
+
+fn prepare_section_sprite_bind_groups(
+    mut bind_groups: ResMut<SectionSpriteBindGroups>,
+    pipeline: Res<SectionTexture2dPipeline>,
+    render_device: Res<RenderDevice>,
+    pipeline_cache: Res<PipelineCache>,
+    images: Res<RenderAssets<GpuImage>>,
+    uniforms: Res<ComponentUniforms<SectionSpriteUniform>>,
+    sprites: Query<&SectionSprite>,
+) {
+    bind_groups.0.clear();
+    let Some(uniforms) = uniforms.binding() else {
+        return;
+    };
+
+    for sprite in &sprites {
+        let Some(gpu_image) = images.get(sprite.image) else {
+            continue;
+        };
+        bind_groups.0.entry(sprite.image).or_insert_with(|| {
+            render_device.create_bind_group(
+                "section_sprite_bind_group",
+                &pipeline_cache.get_bind_group_layout(&pipeline.sprite_layout),
+                &BindGroupEntries::sequential((
+                    uniforms.clone(),
+                    &gpu_image.texture_view,
+                    &gpu_image.sampler,
+                )),
+            )
+        });
+    }
+}
+
+struct SetSectionSpriteBindGroup<const I: usize>;
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetSectionSpriteBindGroup<I> {
+    type Param = SRes<SectionSpriteBindGroups>;
+    type ViewQuery = ();
+    type ItemQuery = (
+        Read<SectionSprite>,
+        Read<DynamicUniformIndex<SectionSpriteUniform>>,
+    );
+
+    #[inline]
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        item_query: Option<(&SectionSprite, &DynamicUniformIndex<SectionSpriteUniform>)>,
+        bind_groups: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some((sprite, uniform_index)) = item_query else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(bind_group) = bind_groups.into_inner().0.get(&sprite.image) else {
+            return RenderCommandResult::Skip;
+        };
+        pass.set_bind_group(I, bind_group, &[uniform_index.index()]);
+
+        RenderCommandResult::Success
+    }
+}
+
+struct DrawSectionSprite;
+impl<P: PhaseItem> RenderCommand<P> for DrawSectionSprite {
+    type Param = ();
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    #[inline]
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        _item_query: Option<()>,
+        _param: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        // the quad's corners come from the vertex index
+        pass.draw(0..6, 0..1);
+        RenderCommandResult::Success
+    }
+}
+
+/// Queues every visible [`DrawSection`] `Mesh2d` and
+/// `Sprite`, sorted by z like bevy's 2d passes.
+#[allow(clippy::too_many_arguments)]
+fn queue_sections_2d(
+    draw_functions: Res<DrawFunctions<SectionTexture2dPhase>>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<SectionTexture2dPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    section_pipeline: Res<SectionTexture2dPipeline>,
+    render_meshes: Res<RenderAssets<RenderMesh>>,
+    render_mesh_instances: Res<RenderMesh2dInstances>,
+    mut section_phases: ResMut<ViewSortedRenderPhases<SectionTexture2dPhase>>,
+    views: Query<(&ExtractedView, &RenderVisibleEntities)>,
+    sections: Query<(), With<DrawSection>>,
+    sprites: Query<&SectionSprite, With<DrawSection>>,
+) {
+    let draw_mesh = draw_functions.read().id::<DrawMesh2dSectionTexture>();
+    let draw_sprite = draw_functions.read().id::<DrawSpriteSectionTexture>();
+
+    for (view, visible_entities) in &views {
+        let Some(section_phase) = section_phases.get_mut(&view.retained_view_entity) else {
+            continue;
+        };
+
+        for (render_entity, visible_entity) in visible_entities.iter::<Mesh2d>() {
+            if !sections.contains(*render_entity) {
+                continue;
+            }
+            let Some(mesh_instance) = render_mesh_instances.get(visible_entity) else {
+                continue;
+            };
+            let Some(mesh) = render_meshes.get(mesh_instance.mesh_asset_id) else {
+                continue;
+            };
+
+            let pipeline_id = match pipelines.specialize(
+                &pipeline_cache,
+                &section_pipeline,
+                Mesh2dPipelineKey::from_primitive_topology(mesh.primitive_topology()),
+                &mesh.layout,
+            ) {
+                Ok(id) => id,
+                Err(err) => {
+                    error!("{}", err);
+                    continue;
+                }
+            };
+            section_phase.add(SectionTexture2dPhase::new(
+                mesh_instance.transforms.world_from_local.translation.z,
+                (*render_entity, *visible_entity),
+                pipeline_id,
+                draw_mesh,
+                mesh.indexed(),
+            ));
+        }
+
+        for (render_entity, visible_entity) in visible_entities.iter::<Sprite>() {
+            let Ok(sprite) = sprites.get(*render_entity) else {
+                continue;
+            };
+            section_phase.add(SectionTexture2dPhase::new(
+                sprite.z,
+                (*render_entity, *visible_entity),
+                section_pipeline.sprite_pipeline_id,
+                draw_sprite,
+                false,
+            ));
+        }
+    }
+}
+
+#[derive(RenderLabel, Debug, Clone, Hash, PartialEq, Eq)]
+struct SectionTexture2dPassLabel;
+
+#[derive(Default)]
+struct SectionTexture2dNode;
+impl ViewNode for SectionTexture2dNode {
+    type ViewQuery = (
+        &'static ExtractedCamera,
+        &'static ExtractedView,
+        &'static SectionTexture,
+    );
+
+    fn run<'w>(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext<'w>,
+        (camera, view, section_texture): QueryItem<'w, '_, Self::ViewQuery>,
+        world: &'w World,
+    ) -> Result<(), NodeRunError> {
+        let Some(section_phase) = world
+            .resource::<ViewSortedRenderPhases<SectionTexture2dPhase>>()
+            .get(&view.retained_view_entity)
+        else {
+            return Ok(());
+        };
+        let Some(sections) = &section_texture.sections else {
+            return Ok(());
+        };
+
+        #[cfg(feature = "trace")]
+        let _ = info_span!("section_2d_pass").entered();
+
+        let diagnostics = render_context.diagnostic_recorder();
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("section_2d_pass"),
+            color_attachments: &[Some(sections.get_attachment())],
+            // sections are drawn back to front instead
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        let pass_span = diagnostics.pass_span(&mut render_pass, "section_2d_pass");
+
+        draw_section_phase(
+            &mut render_pass,
+            camera,
+            section_phase,
+            world,
+            graph.view_entity(),
+        );
+
+        pass_span.end(&mut render_pass);
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+// Jump flooding over the section texture.
+//
+// The seed pass marks every pixel on the inside of a
+// section edge with its own coordinates. Each flood pass
+// then looks at 9 pixels `step` apart and keeps the
+// nearest seed any of them has seen, halving `step`
+// every pass. After log2(size) passes every pixel knows
+// its nearest edge pixel, so the outline can be any
+// width for the same cost.
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+// pixels that haven't seen a seed yet
+const NO_SEED: vec2u = vec2u(0xffffu);
+
+#ifdef SEED
+@group(0) @binding(0) var section_texture: texture_2d<f32>;
+
+@fragment
+fn seed(in: FullscreenVertexOutput) -> @location(0) vec2<u32> {
+    let xy = vec2i(in.position.xy);
+    let dimensions = vec2i(textureDimensions(section_texture));
+    let center = textureLoad(section_texture, xy, 0);
+
+    // 0. is a "no stroke" value, and only meshes that
+    // were drawn have a style
+    if center.r == 0. || center.g == 0. {
+        return NO_SEED;
+    }
+
+    let neighbors = array(vec2i(-1, 0), vec2i(1, 0), vec2i(0, -1), vec2i(0, 1));
+    for (var i = 0; i < 4; i++) {
+        let neighbor = clamp(xy + neighbors[i], vec2i(0), dimensions - 1);
+        if textureLoad(section_texture, neighbor, 0).r != center.r {
+            return vec2u(xy);
+        }
+    }
+    return NO_SEED;
+}
+#else
+struct JumpFloodStep {
+    step: u32,
+    // WebGL2 structs must be 16 byte aligned.
+    _webgl2_padding: vec3<u32>,
+}
+@group(0) @binding(0) var seeds: texture_2d<u32>;
+@group(0) @binding(1) var<uniform> flood_step: JumpFloodStep;
+
+@fragment
+fn flood(in: FullscreenVertexOutput) -> @location(0) vec2<u32> {
+    let xy = vec2i(in.position.xy);
+    let dimensions = vec2i(textureDimensions(seeds));
+    let step = i32(flood_step.step);
+
+    var nearest = NO_SEED;
+    var nearest_distance = 0xffffffffu;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let sample_xy = xy + vec2i(x, y) * step;
+            if any(sample_xy < vec2i(0)) || any(sample_xy >= dimensions) {
+                continue;
+            }
+            let seed = textureLoad(seeds, sample_xy, 0).xy;
+            if all(seed == NO_SEED) {
+                continue;
+            }
+            let offset = vec2i(seed) - xy;
+            let distance = u32(dot(offset, offset));
+            if distance < nearest_distance {
+                nearest = seed;
+                nearest_distance = distance;
+            }
+        }
+    }
+    return nearest;
+}
+#endif
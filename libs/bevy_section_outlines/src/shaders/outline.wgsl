@@ -0,0 +1,326 @@
+// Since post processing is a fullscreen effect, we use the fullscreen vertex shader provided by bevy.
+// This will import a vertex shader that renders a single fullscreen triangle.
+//
+// A fullscreen triangle is a single triangle that covers the entire screen.
+// The box in the top left in that diagram is the screen. The 4 x are the corner of the screen
+//
+// Y axis
+//  1 |  x-----x......
+//  0 |  |  s  |  . ´
+// -1 |  x_____x´
+// -2 |  :  .´
+// -3 |  :´
+//    +---------------  X axis
+//      -1  0  1  2  3
+//
+// As you can see, the triangle ends up bigger than the screen.
+//
+// You don't need to worry about this too much since bevy will compute the correct UVs for you.
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+struct OutlineSettings {
+    stroke_color: vec4f,
+    // x: section ids, y: depth, z: normals
+    thresholds: vec4f,
+    weights: vec4f,
+    width: u32,
+    // lines get thinner past this distance, 0. disables thinning
+    thinning_distance: f32,
+    // the camera's near plane, to linearize depth
+    near: f32,
+    // how far past jump flood lines a glow fades out
+    glow: f32,
+}
+@group(0) @binding(2) var<uniform> settings: OutlineSettings;
+@group(0) @binding(3) var vertex_id_texture: texture_2d<f32>;
+@group(0) @binding(4) var vertex_id_sampler: sampler;
+
+struct OutlineStyle {
+    color: vec4f,
+    fill: vec4f,
+    width: u32,
+    flags: u32,
+}
+#if AVAILABLE_STORAGE_BUFFER_BINDINGS >= 1
+@group(0) @binding(5) var<storage, read> styles: array<OutlineStyle>;
+#endif
+
+// style indices in the section texture, see `StyleIndex::texture_value`.
+// 0 is the background
+const CAMERA_STYLE: u32 = 1u;
+const FIRST_STYLE: u32 = 2u;
+const STYLE_FLAG_FILL: u32 = 1u;
+
+// section texture channels
+const SECTION: u32 = 0u;
+const OCCLUDED_STYLE: u32 = 2u;
+
+#ifdef DEPTH_TEXTURE
+@group(0) @binding(6) var depth_texture: texture_depth_2d;
+#endif
+#ifdef NORMAL_TEXTURE
+@group(0) @binding(7) var normal_texture: texture_2d<f32>;
+#endif
+#ifdef JUMP_FLOOD
+// the coordinates of every pixel's nearest section edge
+@group(0) @binding(8) var jump_flood_texture: texture_2d<u32>;
+
+const NO_SEED: vec2u = vec2u(0xffffu);
+#endif
+
+fn outline_style(index: u32) -> OutlineStyle {
+#if AVAILABLE_STORAGE_BUFFER_BINDINGS >= 1
+    if index >= FIRST_STYLE {
+        return styles[index - FIRST_STYLE];
+    }
+#endif
+    return OutlineStyle(settings.stroke_color, vec4(0.), settings.width, 0u);
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let dimensions = textureDimensions(vertex_id_texture);
+    let xy = vec2i(in.uv * vec2f(dimensions));
+
+    let center = textureLoad(vertex_id_texture, xy, 0);
+    var color = textureSample(screen_texture, texture_sampler, in.uv);
+
+    // outlines are drawn on the inside of each edge,
+    // in the style of the mesh they're inside of
+    let style_index = u32(round(center.g));
+    let style = outline_style(style_index);
+    var edges = 0.;
+    // 0. is a "no stroke" value
+    if style_index != 0u && center.r != 0. {
+        let width = thinned_width(xy, style.width);
+        let offset = vec2u(width, width);
+#ifndef JUMP_FLOOD
+        let diff = sobel(
+            vertex_id_texture,
+            SECTION,
+            dimensions,
+            in.uv,
+            offset
+        );
+        // step() is used to determine a cutoff for showing/not showing 
+        // the outline.
+        // without step() the diff would cause a intermediate mixing
+        // resulting in subdued outlines.
+        edges += settings.weights.x * step(settings.thresholds.x, diff);
+#endif
+#ifdef DEPTH_EDGES
+        // creases and overlaps inside a single section
+        edges += settings.weights.y * step(settings.thresholds.y, depth_sobel(in.uv, offset));
+#endif
+#ifdef NORMAL_EDGES
+        edges += settings.weights.z * step(settings.thresholds.z, normal_edges(in.uv, offset));
+#endif
+        edges = saturate(edges);
+    }
+
+    // the silhouettes of meshes drawn through occluders
+    let occluded_index = u32(round(center.b));
+    let occluded_style = outline_style(occluded_index);
+    var occluded_edges = 0.;
+    if occluded_index != 0u {
+        let width = thinned_width(xy, occluded_style.width);
+        let diff = sobel(
+            vertex_id_texture,
+            OCCLUDED_STYLE,
+            dimensions,
+            in.uv,
+            vec2u(width, width)
+        );
+        occluded_edges = step(0.001, diff);
+    }
+
+#ifdef JUMP_FLOOD
+    // section edges come from the distance field instead,
+    // which reaches past the mesh they're inside of
+    let flood = jump_flood_outline(xy);
+    let flood_edges = settings.weights.x * flood.a;
+#else
+    let flood = vec4(0.);
+    let flood_edges = 0.;
+#endif
+
+#ifdef DISPLAY_SOBEL_ONLY
+    // render just the sobel result, section edges in
+    // white and occluded silhouettes in blue
+    let all_edges = max(edges, flood_edges);
+    return vec4(all_edges, all_edges, max(all_edges, occluded_edges), 1.);
+#else ifdef DISPLAY_SECTION_TEXTURE_ONLY
+    // render just the section texture. The style
+    // indices aren't colors, so the green and blue
+    // channels only show where a style was written.
+    return vec4(center.r, 0.5 * f32(style_index != 0u), 0.5 * f32(occluded_index != 0u), 1.);
+#else ifdef DISPLAY_DEPTH_EDGES_ONLY
+    let width = thinned_width(xy, settings.width);
+    let depth_edges = step(settings.thresholds.y, depth_sobel(in.uv, vec2u(width, width)));
+    return vec4(settings.stroke_color.rgb * depth_edges, 1.);
+#else
+    if style_index != 0u && (style.flags & STYLE_FLAG_FILL) != 0u {
+        color = vec4(mix(color.rgb, style.fill.rgb, style.fill.a), color.a);
+    }
+    color = mix(color, style.color, edges);
+    color = vec4(mix(color.rgb, flood.rgb, flood_edges), color.a);
+    color = mix(color, occluded_style.color, occluded_edges);
+    return color;
+#endif
+}
+
+#ifdef JUMP_FLOOD
+// the line around the nearest section edge, in the style
+// of the mesh that edge is inside of. The alpha is the
+// anti-aliased coverage of the line and its glow.
+fn jump_flood_outline(xy: vec2i) -> vec4f {
+    let seed = textureLoad(jump_flood_texture, xy, 0).xy;
+    if all(seed == NO_SEED) {
+        return vec4(0.);
+    }
+    let seed_xy = vec2i(seed);
+    let style = outline_style(u32(round(textureLoad(vertex_id_texture, seed_xy, 0).g)));
+
+    // the line is centered on the edge, `width` wide
+    let half_width = 0.5 * f32(thinned_width(seed_xy, style.width));
+    let distance = distance(vec2f(xy), vec2f(seed_xy));
+    let line = saturate(half_width + 0.5 - distance);
+    var glow = 0.;
+    if settings.glow > 0. {
+        let falloff = 1. - saturate((distance - half_width) / settings.glow);
+        glow = falloff * falloff;
+    }
+    return vec4(style.color.rgb, max(line, glow) * style.color.a);
+}
+#endif
+
+// lines further away than `thinning_distance` shrink
+// with distance, down to a single pixel
+fn thinned_width(xy: vec2i, width: u32) -> u32 {
+#ifdef DEPTH_TEXTURE
+    if settings.thinning_distance > 0. {
+        // reverse-z perspective depth is near / distance
+        let depth = textureLoad(depth_texture, xy, 0);
+        let distance = settings.near / max(depth, 1e-6);
+        let scale = min(settings.thinning_distance / distance, 1.);
+        return max(1u, u32(round(f32(width) * scale)));
+    }
+#endif
+    return width;
+}
+
+// "sample" (a textureLoad) north/south/east/west pixels
+// and compare to current pixel
+// then sum the differences
+fn sobel(
+    vertex_id_texture: texture_2d<f32>,
+    channel: u32,
+    dimensions: vec2u,
+    uv: vec2f,
+    offset: vec2u
+) -> f32 {
+    let offseti: vec2i = vec2i(offset);
+    let xy = vec2i(uv * vec2f(dimensions));
+
+    let px_center: f32 = textureLoad(vertex_id_texture, xy, 0)[channel];
+
+    let px_left: f32 = textureLoad(vertex_id_texture, xy + vec2i(-offseti.x, 0), 0)[channel];
+    let px_left_up: f32 = textureLoad(vertex_id_texture, xy + vec2i(-offseti.x, 1), 0)[channel];
+    let px_left_down: f32 = textureLoad(vertex_id_texture, xy + vec2i(-offseti.x, -offseti.y), 0)[channel];
+
+    let px_up: f32 = textureLoad(vertex_id_texture, xy + vec2i(0, offseti.y), 0)[channel];
+
+    let px_right: f32 = textureLoad(vertex_id_texture, xy + vec2i(offseti.x, 0), 0)[channel];
+    let px_right_up: f32 = textureLoad(vertex_id_texture, xy + vec2i(offseti.x, offseti.y), 0)[channel];
+    let px_right_down: f32 = textureLoad(vertex_id_texture, xy + vec2i(offseti.x, -offseti.y), 0)[channel];
+
+    let px_down: f32 = textureLoad(vertex_id_texture, xy + vec2i(0, -offseti.y), 0)[channel];
+
+    return max(
+        abs(
+            1 * px_left_down + 2 * px_left + 1 * px_left_up - 1 * px_right_down - 2 * px_right - 1 * px_right_up
+        ),
+        abs(
+            1 * px_left_up + 2 * px_up + 1 * px_right_up - 1 * px_left_down - 2 * px_down - 1 * px_right_down
+        )
+    );
+}
+
+// sample using roberts cross
+fn roberts(
+    vertex_id_texture: texture_2d<f32>,
+    dimensions: vec2u,
+    uv: vec2f,
+    offset: vec3u
+) -> f32 {
+    let xy = vec2u(uv * vec2f(dimensions));
+    let px_center: f32 = textureLoad(vertex_id_texture, xy, 0).r;
+    let px_left: f32 = textureLoad(vertex_id_texture, xy - offset.xz, 0).r;
+    let px_right: f32 = textureLoad(vertex_id_texture, xy + offset.xz, 0).r;
+    let px_up: f32 = textureLoad(vertex_id_texture, xy + offset.zy, 0).r;
+    let px_down: f32 = textureLoad(vertex_id_texture, xy - offset.zy, 0).r;
+
+    return abs(px_left - px_center) + abs(px_right - px_center) + abs(px_up - px_center) + abs(px_down - px_center);
+}
+#ifdef DEPTH_TEXTURE
+// sobel over the depth prepass, relative to the depth
+// at the current pixel. Reverse-z perspective depth is
+// proportional to 1/z, so the relative change is the
+// same at any distance from the camera.
+fn depth_sobel(uv: vec2f, offset: vec2u) -> f32 {
+    let dimensions = textureDimensions(depth_texture);
+    let offseti: vec2i = vec2i(offset);
+    let xy = vec2i(uv * vec2f(dimensions));
+
+    let px_center = textureLoad(depth_texture, xy, 0);
+
+    let px_left = textureLoad(depth_texture, xy + vec2i(-offseti.x, 0), 0);
+    let px_left_up = textureLoad(depth_texture, xy + vec2i(-offseti.x, offseti.y), 0);
+    let px_left_down = textureLoad(depth_texture, xy + vec2i(-offseti.x, -offseti.y), 0);
+
+    let px_up = textureLoad(depth_texture, xy + vec2i(0, offseti.y), 0);
+
+    let px_right = textureLoad(depth_texture, xy + vec2i(offseti.x, 0), 0);
+    let px_right_up = textureLoad(depth_texture, xy + vec2i(offseti.x, offseti.y), 0);
+    let px_right_down = textureLoad(depth_texture, xy + vec2i(offseti.x, -offseti.y), 0);
+
+    let px_down = textureLoad(depth_texture, xy + vec2i(0, -offseti.y), 0);
+
+    let gradient = max(
+        abs(
+            1 * px_left_down + 2 * px_left + 1 * px_left_up - 1 * px_right_down - 2 * px_right - 1 * px_right_up
+        ),
+        abs(
+            1 * px_left_up + 2 * px_up + 1 * px_right_up - 1 * px_left_down - 2 * px_down - 1 * px_right_down
+        )
+    );
+    // the far plane is 0., where any depth is an edge
+    return gradient / max(px_center, 1e-6);
+}
+#endif
+
+#ifdef NORMAL_TEXTURE
+// the largest change in normal direction between the
+// current pixel and its neighbors, 0. for parallel
+// normals and 2. for opposite ones
+fn normal_edges(uv: vec2f, offset: vec2u) -> f32 {
+    let dimensions = textureDimensions(normal_texture);
+    let offseti: vec2i = vec2i(offset);
+    let xy = vec2i(uv * vec2f(dimensions));
+
+    let normal = prepass_normal(xy);
+    let dot_left = dot(normal, prepass_normal(xy + vec2i(-offseti.x, 0)));
+    let dot_right = dot(normal, prepass_normal(xy + vec2i(offseti.x, 0)));
+    let dot_up = dot(normal, prepass_normal(xy + vec2i(0, offseti.y)));
+    let dot_down = dot(normal, prepass_normal(xy + vec2i(0, -offseti.y)));
+
+    return 1. - min(min(dot_left, dot_right), min(dot_up, dot_down));
+}
+
+// the normal prepass stores world space normals in 0..1
+fn prepass_normal(xy: vec2i) -> vec3f {
+    return normalize(textureLoad(normal_texture, xy, 0).xyz * 2. - 1.);
+}
+#endif
@@ -0,0 +1,58 @@
+// Draws a sprite's silhouette into the section texture,
+// as one section cut out by the image's alpha.
+#import bevy_sprite::mesh2d_view_bindings::view
+
+struct SectionSprite {
+    world_from_local: mat4x4<f32>,
+    // xy: the uv of the quad's origin, zw: the uv
+    // size of the quad, negative when flipped
+    uv_offset_scale: vec4<f32>,
+}
+@group(1) @binding(0) var<uniform> sprite: SectionSprite;
+@group(1) @binding(1) var sprite_texture: texture_2d<f32>;
+@group(1) @binding(2) var sprite_sampler: sampler;
+
+struct SectionData {
+    group_id: u32,
+    // index of the sprite's outline style, 0 is the background
+    style: u32,
+}
+@group(2) @binding(0) var<uniform> section_data: SectionData;
+
+// texels more transparent than this aren't part of
+// the silhouette
+const ALPHA_CUTOFF: f32 = 0.5;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vertex(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // two triangles covering the unit quad
+    var corners = array(
+        vec2(0., 0.),
+        vec2(1., 0.),
+        vec2(1., 1.),
+        vec2(0., 0.),
+        vec2(1., 1.),
+        vec2(0., 1.),
+    );
+    let corner = corners[vertex_index];
+
+    var out: VertexOutput;
+    out.clip_position = view.clip_from_world * sprite.world_from_local * vec4(corner, 0., 1.);
+    out.uv = sprite.uv_offset_scale.xy + corner * sprite.uv_offset_scale.zw;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    if textureSample(sprite_texture, sprite_sampler, in.uv).a < ALPHA_CUTOFF {
+        discard;
+    }
+    let section = 1. + f32(section_data.group_id) / 13.;
+    let style = f32(section_data.style);
+    return vec4(section, style, 0., 0.);
+}
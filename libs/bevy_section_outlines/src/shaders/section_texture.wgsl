@@ -4,7 +4,12 @@
 }
 #import bevy_pbr::mesh_bindings::mesh;
 
-@group(3) @binding(0) var<uniform> section_group: u32;
+struct SectionData {
+    group_id: u32,
+    // index of the mesh's outline style, 0 is the background
+    style: u32,
+}
+@group(3) @binding(0) var<uniform> section_data: SectionData;
 
 struct Vertex {
     // This is needed if you are using batching and/or gpu preprocessing
@@ -37,11 +42,12 @@ fn vertex(vertex: Vertex) -> VertexOutput {
     out.clip_position = position_world_to_clip(out.world_position.xyz);
 
     #ifdef SECTION_COLORS
-    if vertex.color.r == 1. || vertex.color.r == 0. {
+    // 0. is a "no stroke" value
+    if vertex.color.r == 0. {
         out.color = vertex.color;
     } else {
         out.color = (vertex.color + vec4(
-            f32(section_group),
+            f32(section_data.group_id),
             0.,
             0.,
             0.
@@ -52,14 +58,21 @@ fn vertex(vertex: Vertex) -> VertexOutput {
     return out;
 }
 
+// r: the section id, g: the outline style,
+// b: the outline style of meshes drawn through occluders.
+// The pipeline's write mask picks which channels are written.
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let style = f32(section_data.style);
+#ifdef HIDDEN
+    return vec4(0., 0., style, 0.);
+#else
     #ifdef SECTION_COLORS
         // return the typically hand authored section id
-    return in.color;
+    let section = in.color.r;
     #else
-    return vec4(0., 0., 0., 0.);
-        // TODO: maybe discard one day?
-        // discard;
+    let section = 0.;
     #endif
+    return vec4(section, style, style, 0.);
+#endif
 }
\ No newline at end of file
@@ -0,0 +1,58 @@
+#import bevy_sprite::mesh2d_functions
+
+struct SectionData {
+    group_id: u32,
+    // index of the mesh's outline style, 0 is the background
+    style: u32,
+}
+@group(2) @binding(0) var<uniform> section_data: SectionData;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    #ifdef SECTION_COLORS
+    @location(1) color: vec4<f32>
+    #endif
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) section: f32,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let world_from_local = mesh2d_functions::get_world_from_local(vertex.instance_index);
+    out.clip_position = mesh2d_functions::mesh2d_position_local_to_clip(
+        world_from_local,
+        vec4(vertex.position, 1.0)
+    );
+
+    #ifdef SECTION_COLORS
+    let color = vertex.color.r;
+    #else
+    // 2d meshes have no depth to outline, so meshes
+    // without section colors are one section and only
+    // outline their silhouette
+    let color = 1.;
+    #endif
+
+    // 0. is a "no stroke" value
+    if color == 0. {
+        out.section = 0.;
+    } else {
+        out.section = color + f32(section_data.group_id) / 13.;
+    }
+
+    return out;
+}
+
+// r: the section id, g: the outline style. Nothing is
+// hidden in 2d, so the occluded silhouette channel is
+// never written.
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let style = f32(section_data.style);
+    return vec4(in.section, style, 0., 0.);
+}
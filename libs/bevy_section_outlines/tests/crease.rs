@@ -0,0 +1,114 @@
+//! Splits bevy's primitive meshes into sections by their
+//! crease angles.
+
+use bevy::{
+    mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+    platform::collections::HashSet,
+    prelude::*,
+};
+use bevy_section_outlines::{
+    ATTRIBUTE_SECTION_COLOR, CreaseSections, SectionColorError, generate_section_colors,
+};
+
+fn distinct_colors(mesh: &Mesh) -> usize {
+    let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(ATTRIBUTE_SECTION_COLOR)
+    else {
+        panic!("the mesh has no section colors");
+    };
+    colors
+        .iter()
+        .map(|color| color.map(f32::to_bits))
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Every face of a cube meets its neighbours at 90°.
+#[test]
+fn cuboid_has_a_section_per_face() {
+    let mut mesh = Cuboid::default().mesh().build();
+    let sections = generate_section_colors(&mut mesh, CreaseSections::default()).unwrap();
+
+    assert_eq!(sections, 6);
+    assert_eq!(distinct_colors(&mesh), 6);
+    // the faces already have their own vertices
+    assert_eq!(mesh.count_vertices(), 24);
+}
+
+/// A sphere is smooth everywhere, its uv seam doesn't
+/// split it.
+#[test]
+fn sphere_is_one_section() {
+    let mut mesh = Sphere::default().mesh().build();
+    let sections = generate_section_colors(&mut mesh, CreaseSections::default()).unwrap();
+
+    assert_eq!(sections, 1);
+    assert_eq!(distinct_colors(&mesh), 1);
+}
+
+/// Vertices shared across a crease are duplicated.
+#[test]
+fn shared_vertices_are_split_along_creases() {
+    let cuboid = Cuboid::default().mesh().build();
+    let positions = cuboid.attribute(Mesh::ATTRIBUTE_POSITION).unwrap();
+    let positions = positions.as_float3().unwrap();
+    // one vertex per corner
+    let mut corners: Vec<[f32; 3]> = vec![];
+    let indices = cuboid
+        .indices()
+        .unwrap()
+        .iter()
+        .map(|index| {
+            let position = positions[index];
+            let corner = corners.iter().position(|corner| *corner == position);
+            corner.unwrap_or_else(|| {
+                corners.push(position);
+                corners.len() - 1
+            }) as u32
+        })
+        .collect();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, corners)
+        .with_inserted_indices(Indices::U32(indices));
+    assert_eq!(mesh.count_vertices(), 8);
+
+    let sections = generate_section_colors(&mut mesh, CreaseSections::default()).unwrap();
+
+    assert_eq!(sections, 6);
+    assert_eq!(distinct_colors(&mesh), 6);
+    // three faces meet at each corner
+    assert_eq!(mesh.count_vertices(), 24);
+}
+
+/// Colors continue from `first_section`, so consecutive
+/// meshes don't repeat each other's colors.
+#[test]
+fn first_section_offsets_colors() {
+    let mut first = Cuboid::default().mesh().build();
+    let mut second = Cuboid::default().mesh().build();
+    let sections = generate_section_colors(&mut first, CreaseSections::default()).unwrap();
+    generate_section_colors(
+        &mut second,
+        CreaseSections {
+            first_section: sections,
+            ..default()
+        },
+    )
+    .unwrap();
+
+    let mut combined = first;
+    combined.merge(&second).unwrap();
+    assert_eq!(distinct_colors(&combined), 12);
+}
+
+#[test]
+fn line_lists_are_rejected() {
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList, default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0., 0., 0.], [1., 0., 0.]]);
+
+    assert!(matches!(
+        generate_section_colors(&mut mesh, CreaseSections::default()),
+        Err(SectionColorError::UnsupportedTopology(
+            PrimitiveTopology::LineList
+        ))
+    ));
+}
@@ -0,0 +1,172 @@
+//! Sharing and recycling `SectionGroupId`s across
+//! `OutlineGroup` hierarchies.
+
+use bevy::prelude::*;
+use bevy_section_outlines::{OutlineGroup, SectionGroupId, SectionOutlinePlugin};
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default(), SectionOutlinePlugin));
+    app
+}
+
+fn mesh() -> Mesh3d {
+    Mesh3d(Handle::default())
+}
+
+fn id(app: &App, entity: Entity) -> Option<u32> {
+    app.world()
+        .get::<SectionGroupId>(entity)
+        .map(|group| group.id)
+}
+
+/// A group root without a mesh of its own, with two
+/// meshes under it.
+fn group(app: &mut App) -> (Entity, [Entity; 2]) {
+    let root = app.world_mut().spawn(OutlineGroup).id();
+    let children = [(); 2].map(|_| app.world_mut().spawn((mesh(), ChildOf(root))).id());
+    app.update();
+    (root, children)
+}
+
+#[test]
+fn meshes_join_their_group() {
+    let mut app = app();
+    let (root, [first, second]) = group(&mut app);
+
+    let root_id = id(&app, root).expect("the root owns the group's id");
+    assert_eq!(id(&app, first), Some(root_id));
+    assert_eq!(id(&app, second), Some(root_id));
+
+    // meshes added later, however deep, join too
+    let nested = app.world_mut().spawn(ChildOf(first)).id();
+    let late = app.world_mut().spawn((mesh(), ChildOf(nested))).id();
+    app.update();
+    assert_eq!(id(&app, late), Some(root_id));
+}
+
+#[test]
+fn meshes_leave_their_group() {
+    let mut app = app();
+    let (root, [first, second]) = group(&mut app);
+    let root_id = id(&app, root).unwrap();
+
+    app.world_mut().entity_mut(first).remove::<ChildOf>();
+    app.update();
+    let own_id = id(&app, first).unwrap();
+    assert_ne!(own_id, root_id);
+    assert_eq!(id(&app, second), Some(root_id));
+
+    // and join another one when moved under it
+    let (other_root, _) = group(&mut app);
+    app.world_mut()
+        .entity_mut(second)
+        .insert(ChildOf(other_root));
+    app.update();
+    assert_eq!(id(&app, second), id(&app, other_root));
+}
+
+#[test]
+fn nested_groups_have_their_own_id() {
+    let mut app = app();
+    let (root, [first, _]) = group(&mut app);
+    let inner = app.world_mut().spawn((OutlineGroup, ChildOf(root))).id();
+    let inner_mesh = app.world_mut().spawn((mesh(), ChildOf(inner))).id();
+    app.update();
+
+    assert_eq!(id(&app, first), id(&app, root));
+    assert_eq!(id(&app, inner_mesh), id(&app, inner));
+    assert_ne!(id(&app, inner_mesh), id(&app, root));
+}
+
+#[test]
+fn removing_a_group_recycles_its_id() {
+    let mut app = app();
+    let (root, [first, second]) = group(&mut app);
+    let root_id = id(&app, root).unwrap();
+
+    app.world_mut().entity_mut(root).remove::<OutlineGroup>();
+    app.update();
+    assert_eq!(id(&app, root), None);
+    let first_id = id(&app, first).unwrap();
+    let second_id = id(&app, second).unwrap();
+    assert_ne!(first_id, second_id);
+    assert!(![first_id, second_id].contains(&root_id));
+
+    let next = app.world_mut().spawn(mesh()).id();
+    app.update();
+    assert_eq!(id(&app, next), Some(root_id));
+}
+
+#[test]
+fn despawning_recycles_ids() {
+    let mut app = app();
+    let single = app.world_mut().spawn(mesh()).id();
+    app.update();
+    let single_id = id(&app, single).unwrap();
+
+    app.world_mut().despawn(single);
+    let next = app.world_mut().spawn(mesh()).id();
+    app.update();
+    assert_eq!(id(&app, next), Some(single_id));
+
+    // a despawned group's meshes go with it, but only
+    // the root owned an id
+    let (root, _) = group(&mut app);
+    let root_id = id(&app, root).unwrap();
+    app.world_mut().entity_mut(root).despawn();
+    let [first, second] = [(); 2].map(|_| app.world_mut().spawn(mesh()).id());
+    app.update();
+    let ids = [id(&app, first).unwrap(), id(&app, second).unwrap()];
+    assert!(ids.contains(&root_id));
+    assert_ne!(ids[0], ids[1]);
+}
+
+#[test]
+fn ids_from_world_are_recycled() {
+    let mut app = app();
+    let id_from_world = SectionGroupId::from_world(app.world_mut());
+    let owned = app.world_mut().spawn((mesh(), id_from_world)).id();
+    app.update();
+    assert_eq!(id(&app, owned), Some(id_from_world.id));
+
+    app.world_mut().despawn(owned);
+    let next = app.world_mut().spawn(mesh()).id();
+    app.update();
+    assert_eq!(id(&app, next), Some(id_from_world.id));
+
+    // ids set by hand aren't recycled
+    let by_hand = app
+        .world_mut()
+        .spawn((
+            mesh(),
+            SectionGroupId {
+                id: id_from_world.id + 100,
+            },
+        ))
+        .id();
+    app.update();
+    app.world_mut().despawn(by_hand);
+    let next = app.world_mut().spawn(mesh()).id();
+    app.update();
+    assert_ne!(id(&app, next), Some(id_from_world.id + 100));
+}
+
+/// Meshes many levels under a group still join it.
+#[test]
+fn deep_groups_share_one_id() {
+    let mut app = app();
+    let root = app.world_mut().spawn((mesh(), OutlineGroup)).id();
+    let mut parent = root;
+    let mut chain = vec![];
+    for _ in 0..200 {
+        parent = app.world_mut().spawn((mesh(), ChildOf(parent))).id();
+        chain.push(parent);
+    }
+    app.update();
+
+    let root_id = id(&app, root).unwrap();
+    for entity in chain {
+        assert_eq!(id(&app, entity), Some(root_id));
+    }
+}
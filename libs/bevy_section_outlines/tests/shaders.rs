@@ -0,0 +1,123 @@
+//! Composes the section texture and outline shaders through naga_oil, the
+//! same way the `PipelineCache` does at runtime. Runs on the CPU only.
+
+use bevy_shader_validation::{
+    BEVY_SHADER_CRATES, Platform, ShaderLibrary, assert_no_failures, compose_all, defs,
+};
+
+fn library() -> ShaderLibrary {
+    ShaderLibrary::new(env!("CARGO_MANIFEST_DIR"))
+        .with_crates(&BEVY_SHADER_CRATES)
+        .with_crates(&["bevy_sprite_render"])
+        .with_dir("src/shaders")
+}
+
+/// Meshes without a section color attribute are drawn
+/// too, they only contribute depth. Meshes drawing their
+/// occluded outline are drawn twice, once `HIDDEN`.
+#[test]
+fn section_texture_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    for platform in Platform::ALL {
+        failures.extend(compose_all(
+            &mut library,
+            "src/shaders/section_texture.wgsl",
+            defs::permutations(
+                &platform.global_shader_defs(),
+                &["SECTION_COLORS", "HIDDEN"],
+            ),
+        ));
+    }
+
+    assert_no_failures(&failures);
+}
+
+/// 2d meshes, with and without section colors, and
+/// sprites, under the 2d mesh pipeline's bindings.
+#[test]
+fn section_texture_2d_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    for platform in Platform::ALL {
+        let base = defs::mesh2d_pipeline(platform);
+        failures.extend(compose_all(
+            &mut library,
+            "src/shaders/section_texture_2d.wgsl",
+            defs::permutations(&base, &["SECTION_COLORS"]),
+        ));
+        failures.extend(compose_all(
+            &mut library,
+            "src/shaders/section_sprite.wgsl",
+            [base],
+        ));
+    }
+
+    assert_no_failures(&failures);
+}
+
+/// Every `PostProcessDisplay`, `EdgeDetection` source
+/// and `OutlineMethod`, with the defs the pipeline is
+/// specialized with.
+#[test]
+fn outline_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    let displays: [&[&str]; 4] = [
+        &[],
+        &["DISPLAY_SOBEL_ONLY"],
+        &["DISPLAY_SECTION_TEXTURE_ONLY"],
+        &["DISPLAY_DEPTH_EDGES_ONLY", "DEPTH_TEXTURE"],
+    ];
+    // thinning only binds the depth texture
+    let edges: [&[&str]; 5] = [
+        &[],
+        &["DEPTH_TEXTURE"],
+        &["DEPTH_TEXTURE", "DEPTH_EDGES"],
+        &["NORMAL_TEXTURE", "NORMAL_EDGES"],
+        &[
+            "DEPTH_TEXTURE",
+            "DEPTH_EDGES",
+            "NORMAL_TEXTURE",
+            "NORMAL_EDGES",
+        ],
+    ];
+    for platform in Platform::ALL {
+        for display in displays {
+            for edges in edges {
+                let base: Vec<_> = platform
+                    .global_shader_defs()
+                    .into_iter()
+                    .chain(display.iter().chain(edges).map(|&def| def.into()))
+                    .collect();
+                failures.extend(compose_all(
+                    &mut library,
+                    "src/shaders/outline.wgsl",
+                    defs::permutations(&base, &["JUMP_FLOOD"]),
+                ));
+            }
+        }
+    }
+
+    assert_no_failures(&failures);
+}
+
+/// The seed pass, and the flood passes.
+#[test]
+fn jump_flood_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    for platform in Platform::ALL {
+        failures.extend(compose_all(
+            &mut library,
+            "src/shaders/jump_flood.wgsl",
+            defs::permutations(&platform.global_shader_defs(), &["SEED"]),
+        ));
+    }
+
+    assert_no_failures(&failures);
+}
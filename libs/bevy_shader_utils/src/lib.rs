@@ -19,11 +19,12 @@ pub const SIMPLEX_NOISE_2D: Handle<Shader> =
     uuid_handle!("f6a37262-741d-442e-b990-fb2851253284");
 pub const SIMPLEX_NOISE_3D: Handle<Shader> =
     uuid_handle!("6239ad8c-41e1-4302-8f03-b2e41c154764");
-// pub const FBM: Handle<Shader> =
-// uuid_handle!("
-// b84472cd-83dc-4cc2-b18a-021b42d11cb8");
+pub const FBM: Handle<Shader> =
+    uuid_handle!("b84472cd-83dc-4cc2-b18a-021b42d11cb8");
 pub const VORONOISE: Handle<Shader> =
     uuid_handle!("06dc4bde-2702-4fe2-aff5-df69078c4b59");
+pub const STOCHASTIC: Handle<Shader> =
+    uuid_handle!("5b0f8a2e-4c7d-4e59-9a16-3f2d8c7e1b40");
 // other utility functions
 pub const MOCK_FRESNEL: Handle<Shader> =
     uuid_handle!("294d2ab5-554b-41f2-a1ca-95fb3689c1c3");
@@ -106,10 +107,11 @@ impl Plugin for ShaderUtilsPlugin {
         );
         load_internal_asset!(
             app,
-            VORONOISE,
-            "shaders/voronoise.wgsl",
+            FBM,
+            "shaders/fbm.wgsl",
             Shader::from_wgsl
         );
+        load_stochastic_shaders(app);
         load_internal_asset!(
             app,
             MOCK_FRESNEL,
@@ -129,6 +131,29 @@ impl Plugin for ShaderUtilsPlugin {
     }
 }
 
+/// Loads `bevy_shader_utils::stochastic`, and the
+/// `voronoise` hash it builds on, without the rest of
+/// [`ShaderUtilsPlugin`].
+///
+/// For other crates' plugins that sample stochastically.
+/// Loading the shaders again, or along with
+/// [`ShaderUtilsPlugin`], replaces them with the same
+/// source.
+pub fn load_stochastic_shaders(app: &mut App) {
+    load_internal_asset!(
+        app,
+        VORONOISE,
+        "shaders/voronoise.wgsl",
+        Shader::from_wgsl
+    );
+    load_internal_asset!(
+        app,
+        STOCHASTIC,
+        "shaders/stochastic.wgsl",
+        Shader::from_wgsl
+    );
+}
+
 impl Material for PristineGridMaterial {
     fn fragment_shader() -> ShaderRef {
         "embedded://bevy_shader_utils/materials/pristine_grid.wgsl".into()
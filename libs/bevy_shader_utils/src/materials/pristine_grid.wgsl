@@ -1,5 +1,4 @@
 #import bevy_pbr::forward_io::VertexOutput
-#import bevy_sprite::mesh2d_view_bindings::globals
 
 #import bevy_shader_utils::pristine_grid::pristine_grid
 
@@ -1,12 +1,40 @@
+#define_import_path bevy_shader_utils::fbm
+
+#import bevy_shader_utils::simplex_noise_2d::{simplex_noise_2d, simplex_noise_2d_gradient}
+
 //  MIT License. © Inigo Quilez, Munrocket
-//  noise_2d() is any noise here: Value, Perlin, Simplex, Worley
+//  four octaves of simplex noise, each rotated and scaled from the last
 //
-var m_two: mat2x2<f32> = mat2x2<f32>(vec2<f32>(0.8, 0.6), vec2<f32>(-0.6, 0.8));
-fn fbm(p: vec2<f32>, noise_2d: ) -> f32 {
+const m_two: mat2x2<f32> = mat2x2<f32>(vec2<f32>(0.8, 0.6), vec2<f32>(-0.6, 0.8));
+fn fbm(p: vec2<f32>) -> f32 {
+  var q: vec2<f32> = p;
   var f: f32 = 0.;
-  f = f + 0.5000 * noise_2d(p); p = m_two * p * 2.02;
-  f = f + 0.2500 * noise_2d(p); p = m_two * p * 2.03;
-  f = f + 0.1250 * noise_2d(p); p = m_two * p * 2.01;
-  f = f + 0.0625 * noise_2d(p);
+  f = f + 0.5000 * simplex_noise_2d(q); q = m_two * q * 2.02;
+  f = f + 0.2500 * simplex_noise_2d(q); q = m_two * q * 2.03;
+  f = f + 0.1250 * simplex_noise_2d(q); q = m_two * q * 2.01;
+  f = f + 0.0625 * simplex_noise_2d(q);
   return f / 0.9375;
-}
\ No newline at end of file
+}
+
+// fbm, along with its gradient in .yz
+fn fbm_gradient(p: vec2<f32>) -> vec3<f32> {
+    var q = p;
+    // how q changes with p, to bring each octave's gradient back
+    var jacobian = mat2x2<f32>(vec2(1., 0.), vec2(0., 1.));
+    var f = vec3(0.);
+    var n = simplex_noise_2d_gradient(q);
+    f += 0.5000 * vec3(n.x, n.yz * jacobian);
+    q = m_two * q * 2.02;
+    jacobian = m_two * jacobian * 2.02;
+    n = simplex_noise_2d_gradient(q);
+    f += 0.2500 * vec3(n.x, n.yz * jacobian);
+    q = m_two * q * 2.03;
+    jacobian = m_two * jacobian * 2.03;
+    n = simplex_noise_2d_gradient(q);
+    f += 0.1250 * vec3(n.x, n.yz * jacobian);
+    q = m_two * q * 2.01;
+    jacobian = m_two * jacobian * 2.01;
+    n = simplex_noise_2d_gradient(q);
+    f += 0.0625 * vec3(n.x, n.yz * jacobian);
+    return f / 0.9375;
+}
@@ -57,4 +57,53 @@ fn simplex_noise_2d(v: vec2<f32>) -> f32 {
     // Compute final noise value at P
     let g = vec3(a0.x * x0.x + h.x * x0.y, a0.yz * x12.xz + h.yz * x12.yw);
     return 130. * dot(m, g);
-}
\ No newline at end of file
+}
+
+// simplex_noise_2d, along with its gradient in .yz
+//  MIT License. © Ian McEwan, Stefan Gustavson, Munrocket
+fn simplex_noise_2d_gradient(v: vec2<f32>) -> vec3<f32> {
+    let C = vec4(
+        0.211324865405187, // (3.0-sqrt(3.0))/6.0
+        0.366025403784439, // 0.5*(sqrt(3.0)-1.0)
+        -0.577350269189626, // -1.0 + 2.0 * C.x
+        0.024390243902439 // 1.0 / 41.0
+    );
+
+    // First corner
+    var i = floor(v + dot(v, C.yy));
+    let x0 = v - i + dot(i, C.xx);
+
+    // Other corners
+    var i1: vec2<f32> = select(vec2<f32>(0., 1.), vec2<f32>(1., 0.), (x0.x > x0.y));
+    var x12 = x0.xyxy + C.xxzz;
+    x12.x = x12.x - i1.x;
+    x12.y = x12.y - i1.y;
+
+    // Permutations
+    i = mod289_(i); // Avoid truncation effects in permutation
+
+    var p = permute_three(permute_three(i.y + vec3(0., i1.y, 1.)) + i.x + vec3(0., i1.x, 1.));
+    let t = max(0.5 - vec3(dot(x0, x0), dot(x12.xy, x12.xy), dot(x12.zw, x12.zw)), vec3(0.));
+    let t2 = t * t;
+
+    // Gradients: 41 points uniformly over a line, mapped onto a diamond.
+    let x = 2. * fract(p * C.www) - 1.;
+    let h = abs(x) - 0.5;
+    let ox = floor(x + 0.5);
+    let a0 = x - ox;
+
+    // Normalize gradients implicitly by scaling m
+    let norm = 1.79284291400159 - 0.85373472095314 * (a0 * a0 + h * h);
+    let m = t2 * t2 * norm;
+
+    // Compute final noise value at P
+    let g = vec3(a0.x * x0.x + h.x * x0.y, a0.yz * x12.xz + h.yz * x12.yw);
+
+    // each corner adds t^4 * dot(gradient, x), where t = 0.5 - dot(x, x),
+    // which changes by t^4 * gradient - 8 * t^3 * dot(gradient, x) * x
+    let falloff = -8. * t2 * t * norm * g;
+    let d = m.x * vec2(a0.x, h.x) + falloff.x * x0
+        + m.y * vec2(a0.y, h.y) + falloff.y * x12.xy
+        + m.z * vec2(a0.z, h.z) + falloff.z * x12.zw;
+    return 130. * vec3(dot(m, g), d);
+}
@@ -0,0 +1,112 @@
+// Hex-tiling, or stochastic, texture sampling. Hides the repetition of a
+// tiling texture by blending three randomly offset copies of it over a
+// triangle grid.
+//
+// Heitz & Neyret 2018, "High-Performance By-Example Noise using a
+// Histogram-Preserving Blending Operator"
+// https://eheitzresearch.wordpress.com/722-2/
+//
+// Mikkelsen 2022, "Practical Real-Time Hex-Tiling"
+// https://jcgt.org/published/0011/03/05/
+#define_import_path bevy_shader_utils::stochastic
+
+#import bevy_shader_utils::voronoise::hash_three
+
+// The three uvs to sample a texture at, and how much each sample
+// contributes.
+struct StochasticTiles {
+    uv_a: vec2<f32>,
+    uv_b: vec2<f32>,
+    uv_c: vec2<f32>,
+    // always sums to 1
+    weights: vec3<f32>,
+}
+
+// `contrast` sharpens the transition between tiles. 1.0 is a linear blend,
+// which washes out the texture where three tiles meet. Somewhere around
+// 4.0 to 8.0 keeps most of the texture's contrast.
+fn stochastic_tiles(uv: vec2<f32>, contrast: f32) -> StochasticTiles {
+    // 2 * sqrt(3), so each triangle covers a bit less than a third of the
+    // texture
+    let scaled = uv * 3.4641016;
+    // skew the square grid into a grid of equilateral triangles, with
+    // edges along (1, 0) and (0.5, sqrt(3) / 2) in uv space
+    let skewed = vec2(scaled.x - 0.57735027 * scaled.y, 1.15470054 * scaled.y);
+    let base = floor(skewed);
+    let f = fract(skewed);
+    let z = 1.0 - f.x - f.y;
+
+    var weights: vec3<f32>;
+    var vertex_a: vec2<f32>;
+    var vertex_b: vec2<f32>;
+    var vertex_c: vec2<f32>;
+    if z > 0.0 {
+        weights = vec3(z, f.y, f.x);
+        vertex_a = base;
+        vertex_b = base + vec2(0.0, 1.0);
+        vertex_c = base + vec2(1.0, 0.0);
+    } else {
+        weights = vec3(-z, 1.0 - f.y, 1.0 - f.x);
+        vertex_a = base + vec2(1.0, 1.0);
+        vertex_b = base + vec2(1.0, 0.0);
+        vertex_c = base + vec2(0.0, 1.0);
+    }
+
+    weights = pow(weights, vec3(contrast));
+    weights /= weights.x + weights.y + weights.z;
+
+    // every vertex of the grid gets its own random offset into the texture
+    return StochasticTiles(
+        uv + hash_three(vertex_a).xy,
+        uv + hash_three(vertex_b).xy,
+        uv + hash_three(vertex_c).xy,
+        weights,
+    );
+}
+
+fn stochastic_blend(
+    a: vec4<f32>,
+    b: vec4<f32>,
+    c: vec4<f32>,
+    tiles: StochasticTiles,
+) -> vec4<f32> {
+    return a * tiles.weights.x + b * tiles.weights.y + c * tiles.weights.z;
+}
+
+// Drop-in replacement for `textureSample`. Samples with the gradients of
+// the unoffset uv, so the mip level doesn't jump at the tile borders.
+fn stochastic_sample(
+    t: texture_2d<f32>,
+    s: sampler,
+    uv: vec2<f32>,
+    contrast: f32,
+) -> vec4<f32> {
+    let tiles = stochastic_tiles(uv, contrast);
+    let ddx = dpdx(uv);
+    let ddy = dpdy(uv);
+    return stochastic_blend(
+        textureSampleGrad(t, s, tiles.uv_a, ddx, ddy),
+        textureSampleGrad(t, s, tiles.uv_b, ddx, ddy),
+        textureSampleGrad(t, s, tiles.uv_c, ddx, ddy),
+        tiles,
+    );
+}
+
+// `stochastic_sample` for one layer of an array texture.
+fn stochastic_sample_array(
+    t: texture_2d_array<f32>,
+    s: sampler,
+    uv: vec2<f32>,
+    layer: u32,
+    contrast: f32,
+) -> vec4<f32> {
+    let tiles = stochastic_tiles(uv, contrast);
+    let ddx = dpdx(uv);
+    let ddy = dpdy(uv);
+    return stochastic_blend(
+        textureSampleGrad(t, s, tiles.uv_a, layer, ddx, ddy),
+        textureSampleGrad(t, s, tiles.uv_b, layer, ddx, ddy),
+        textureSampleGrad(t, s, tiles.uv_c, layer, ddx, ddy),
+        tiles,
+    );
+}
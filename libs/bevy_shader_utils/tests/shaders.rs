@@ -0,0 +1,63 @@
+//! Composes every shader in the crate through naga_oil, the same way the
+//! `PipelineCache` does at runtime. Runs on the CPU only.
+
+use std::fs;
+
+use bevy_shader_validation::{
+    BEVY_SHADER_CRATES, Platform, ShaderLibrary, assert_no_failures, compose_all, defs,
+};
+
+fn library() -> ShaderLibrary {
+    ShaderLibrary::new(env!("CARGO_MANIFEST_DIR"))
+        .with_crates(&BEVY_SHADER_CRATES)
+        .with_crates(&["bevy_sprite_render"])
+        .with_dir("src")
+        .with_dir("assets")
+}
+
+/// Every function the plugin loads, validated as a module of its own.
+#[test]
+fn every_module_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    for path in library.files_in("src/shaders") {
+        for platform in Platform::ALL {
+            failures.extend(compose_all(
+                &mut library,
+                &path,
+                [platform.global_shader_defs()],
+            ));
+        }
+    }
+
+    assert_no_failures(&failures);
+}
+
+/// The pristine grid material and the materials the examples render,
+/// under the pipeline they're drawn with.
+#[test]
+fn every_material_composes() {
+    let mut library = library();
+    let mut failures = vec![];
+
+    let mut materials = library.files_in("src/materials");
+    materials.extend(library.files_in("assets/shaders"));
+    for path in materials {
+        let is_2d = fs::read_to_string(&path).unwrap().contains("bevy_sprite::");
+        for platform in Platform::ALL {
+            let base = if is_2d {
+                defs::mesh2d_pipeline(platform)
+            } else {
+                defs::mesh_pipeline(defs::Pass::Forward, platform)
+            };
+            failures.extend(compose_all(
+                &mut library,
+                &path,
+                defs::permutations(&base, &["MAY_DISCARD"]),
+            ));
+        }
+    }
+
+    assert_no_failures(&failures);
+}
@@ -0,0 +1,192 @@
+//! The shader defs bevy's pipelines push, so shaders can be composed under
+//! the same permutations they'll see at runtime.
+
+use bevy_shader::ShaderDefVal;
+
+/// Which of the mesh pipelines a material shader is specialized for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pass {
+    Forward,
+    /// The depth, normal and motion vector prepass
+    Prepass,
+    Deferred,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 3] = [Pass::Forward, Pass::Prepass, Pass::Deferred];
+
+    pub fn shader_defs(self) -> Vec<ShaderDefVal> {
+        let shader_defs: &[&str] = match self {
+            Pass::Forward => &["MESH_PIPELINE"],
+            Pass::Prepass => &[
+                "PREPASS_PIPELINE",
+                "PREPASS_FRAGMENT",
+                "DEPTH_PREPASS",
+                "NORMAL_PREPASS",
+                "MOTION_VECTOR_PREPASS",
+                "NORMAL_PREPASS_OR_DEFERRED_PREPASS",
+                "MOTION_VECTOR_PREPASS_OR_DEFERRED_PREPASS",
+            ],
+            Pass::Deferred => &[
+                "PREPASS_PIPELINE",
+                "PREPASS_FRAGMENT",
+                "DEFERRED_PREPASS",
+                "NORMAL_PREPASS_OR_DEFERRED_PREPASS",
+                "MOTION_VECTOR_PREPASS_OR_DEFERRED_PREPASS",
+            ],
+        };
+        shader_defs
+            .iter()
+            .map(|&shader_def| shader_def.into())
+            .collect()
+    }
+}
+
+/// The device limits that change which defs the pipelines push.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    Native,
+    /// No storage buffers, no array textures, and uniforms padded to 16
+    /// bytes
+    WebGl2,
+}
+
+impl Platform {
+    pub const ALL: [Platform; 2] = [Platform::Native, Platform::WebGl2];
+
+    /// The defs `PipelineCache` adds to every shader.
+    pub fn global_shader_defs(self) -> Vec<ShaderDefVal> {
+        match self {
+            Platform::Native => vec![ShaderDefVal::UInt(
+                "AVAILABLE_STORAGE_BUFFER_BINDINGS".into(),
+                16,
+            )],
+            Platform::WebGl2 => vec![
+                "NO_ARRAY_TEXTURES_SUPPORT".into(),
+                "NO_CUBE_ARRAY_TEXTURES_SUPPORT".into(),
+                "SIXTEEN_BYTE_ALIGNMENT".into(),
+                ShaderDefVal::UInt("AVAILABLE_STORAGE_BUFFER_BINDINGS".into(), 0),
+            ],
+        }
+    }
+}
+
+/// The defs a shader library is loaded with, the way
+/// `load_shader_library!` attaches them to the module itself rather than to
+/// the pipelines importing it.
+pub fn library_shader_defs(source: &str) -> Vec<ShaderDefVal> {
+    if source.contains("#define_import_path bevy_pbr::mesh_view_types") {
+        vec![
+            ShaderDefVal::UInt("MAX_DIRECTIONAL_LIGHTS".into(), 10),
+            ShaderDefVal::UInt("MAX_CASCADES_PER_LIGHT".into(), 4),
+        ]
+    } else {
+        vec![]
+    }
+}
+
+/// The defs the mesh pipeline and `MaterialPlugin` push for a mesh with
+/// every vertex attribute a material can use.
+pub fn mesh_pipeline(pass: Pass, platform: Platform) -> Vec<ShaderDefVal> {
+    let mut shader_defs = platform.global_shader_defs();
+    shader_defs.extend(pass.shader_defs());
+    shader_defs.extend([
+        ShaderDefVal::UInt("MATERIAL_BIND_GROUP".into(), 3),
+        ShaderDefVal::Int("SCREEN_SPACE_SPECULAR_TRANSMISSION_BLUR_TAPS".into(), 8),
+        "VIEW_PROJECTION_PERSPECTIVE".into(),
+        "VERTEX_OUTPUT_INSTANCE_INDEX".into(),
+        "VERTEX_POSITIONS".into(),
+        "VERTEX_NORMALS".into(),
+        "VERTEX_UVS".into(),
+        "VERTEX_UVS_A".into(),
+        "VERTEX_UVS_B".into(),
+        "VERTEX_TANGENTS".into(),
+    ]);
+    match platform {
+        Platform::Native => shader_defs.extend([
+            "MULTIPLE_LIGHT_PROBES_IN_ARRAY".into(),
+            "MULTIPLE_LIGHTMAPS_IN_ARRAY".into(),
+            "IRRADIANCE_VOLUMES_ARE_USABLE".into(),
+            "CLUSTERED_DECALS_ARE_USABLE".into(),
+        ]),
+        Platform::WebGl2 => shader_defs.extend([
+            "WEBGL2".into(),
+            // what a 16 KiB uniform buffer holds of `MeshUniform`s
+            ShaderDefVal::UInt("PER_OBJECT_BUFFER_BATCH_SIZE".into(), 102),
+        ]),
+    }
+    shader_defs
+}
+
+/// The defs the 2d mesh pipeline and `Material2dPlugin` push for a mesh
+/// with every vertex attribute.
+pub fn mesh2d_pipeline(platform: Platform) -> Vec<ShaderDefVal> {
+    let mut shader_defs = platform.global_shader_defs();
+    shader_defs.extend([
+        ShaderDefVal::UInt("MATERIAL_BIND_GROUP".into(), 2),
+        "VERTEX_POSITIONS".into(),
+        "VERTEX_NORMALS".into(),
+        "VERTEX_UVS".into(),
+        "VERTEX_TANGENTS".into(),
+    ]);
+    if platform == Platform::WebGl2 {
+        shader_defs.extend([
+            "WEBGL2".into(),
+            ShaderDefVal::UInt("PER_OBJECT_BUFFER_BATCH_SIZE".into(), 102),
+        ]);
+    }
+    shader_defs
+}
+
+/// The defs `StandardMaterial`'s key can switch on, for extended materials.
+/// The uv-b defs are left out, they only change which uv a texture is
+/// sampled with.
+pub const STANDARD_MATERIAL_KEYS: [&str; 7] = [
+    "STANDARD_MATERIAL_NORMAL_MAP",
+    "STANDARD_MATERIAL_DIFFUSE_TRANSMISSION",
+    "STANDARD_MATERIAL_SPECULAR_TRANSMISSION",
+    "STANDARD_MATERIAL_DIFFUSE_OR_SPECULAR_TRANSMISSION",
+    "STANDARD_MATERIAL_CLEARCOAT",
+    "STANDARD_MATERIAL_CLEARCOAT_NORMAL_MAP",
+    "STANDARD_MATERIAL_ANISOTROPY",
+];
+
+/// The defs bevy_pbr's texture cargo features push. Some
+/// [`STANDARD_MATERIAL_KEYS`] can only be set with one of them enabled.
+pub const PBR_TEXTURE_FEATURES: [&str; 4] = [
+    "PBR_TRANSMISSION_TEXTURES_SUPPORTED",
+    "PBR_MULTI_LAYER_MATERIAL_TEXTURES_SUPPORTED",
+    "PBR_ANISOTROPY_TEXTURE_SUPPORTED",
+    "PBR_SPECULAR_TEXTURES_SUPPORTED",
+];
+
+/// `base` extended with every subset of `optional`.
+pub fn permutations(base: &[ShaderDefVal], optional: &[&str]) -> Vec<Vec<ShaderDefVal>> {
+    (0..1u32 << optional.len())
+        .map(|combination| {
+            let mut shader_defs = base.to_vec();
+            shader_defs.extend(
+                optional
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| combination & (1 << bit) != 0)
+                    .map(|(_, &shader_def)| ShaderDefVal::from(shader_def)),
+            );
+            shader_defs
+        })
+        .collect()
+}
+
+/// The defs as they'd be written in a shader, for failure messages.
+pub fn describe(shader_defs: &[ShaderDefVal]) -> String {
+    shader_defs
+        .iter()
+        .map(|shader_def| match shader_def {
+            ShaderDefVal::Bool(name, true) => name.clone(),
+            ShaderDefVal::Bool(name, false) => format!("!{name}"),
+            ShaderDefVal::Int(name, value) => format!("{name}={value}"),
+            ShaderDefVal::UInt(name, value) => format!("{name}={value}u"),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
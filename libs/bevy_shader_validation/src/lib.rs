@@ -0,0 +1,280 @@
+//! Composes WGSL the way bevy's `PipelineCache` does, resolving `#import`s
+//! through naga_oil, and validates the result with naga. Runs on the CPU
+//! only, no adapter is needed, so it works in CI.
+//!
+//! ```ignore
+//! let mut library = ShaderLibrary::new(env!("CARGO_MANIFEST_DIR"))
+//!     .with_crates(&BEVY_SHADER_CRATES)
+//!     .with_dir("src");
+//!
+//! let base = defs::mesh_pipeline(Pass::Forward, Platform::Native);
+//! let failures = compose_all(
+//!     &mut library,
+//!     "src/materials/my_material.wgsl",
+//!     defs::permutations(&base, &["MAY_DISCARD"]),
+//! );
+//! assert_no_failures(&failures);
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use bevy_asset::{AssetId, uuid::Uuid};
+use bevy_render::render_resource::{DownlevelFlags, WgpuFeatures};
+use bevy_shader::{PipelineCacheError, Shader, ShaderCache, ShaderCacheSource, ValidateShader};
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+pub mod defs;
+
+pub use bevy_shader::ShaderDefVal;
+pub use defs::{Pass, Platform};
+
+/// The render crates most materials import from.
+pub const BEVY_SHADER_CRATES: [&str; 3] = ["bevy_render", "bevy_core_pipeline", "bevy_pbr"];
+
+/// Every WGSL file that can be imported or composed, keyed by path.
+pub struct ShaderLibrary {
+    manifest_dir: PathBuf,
+    cache: ShaderCache<(), ()>,
+    shaders: HashMap<PathBuf, AssetId<Shader>>,
+    next_pipeline: usize,
+}
+
+impl ShaderLibrary {
+    /// An empty library for the crate at `manifest_dir`, which is where
+    /// crate names and relative paths are resolved from.
+    pub fn new(manifest_dir: impl Into<PathBuf>) -> Self {
+        ShaderLibrary {
+            manifest_dir: manifest_dir.into(),
+            cache: ShaderCache::new(WgpuFeatures::all(), DownlevelFlags::all(), validate),
+            shaders: HashMap::new(),
+            next_pipeline: 0,
+        }
+    }
+
+    /// Adds every WGSL file in the `src` directory of each named crate, as
+    /// resolved by cargo. The crates must be in the dependency graph of the
+    /// crate at `manifest_dir`.
+    pub fn with_crates(mut self, crates: &[&str]) -> Self {
+        for root in crate_roots(&self.manifest_dir, crates) {
+            // entry points of other pipelines can't be imported, and some
+            // only compose under defs their own pipeline pushes
+            self.add_dir(&root, true);
+        }
+        self
+    }
+
+    /// Adds every WGSL file under `dir`, relative to `manifest_dir`.
+    pub fn with_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        let dir = self.manifest_dir.join(dir);
+        self.add_dir(&dir, false);
+        self
+    }
+
+    fn add_dir(&mut self, dir: &Path, imports_only: bool) {
+        let mut files = vec![];
+        collect_wgsl(dir, &mut files);
+        for path in files {
+            let source = fs::read_to_string(&path)
+                .unwrap_or_else(|error| panic!("couldn't read {}: {error}", path.display()));
+            if imports_only && !source.contains("#define_import_path") {
+                continue;
+            }
+            let id = AssetId::Uuid {
+                uuid: Uuid::from_u128(self.shaders.len() as u128 + 1),
+            };
+            let shader_defs = defs::library_shader_defs(&source);
+            self.cache.set_shader(
+                id,
+                Shader::from_wgsl_with_defs(source, path.display().to_string(), shader_defs),
+            );
+            self.shaders.insert(path, id);
+        }
+    }
+
+    /// The files added to the library under `dir`, relative to
+    /// `manifest_dir`, sorted.
+    pub fn files_in(&self, dir: impl AsRef<Path>) -> Vec<PathBuf> {
+        let dir = self.manifest_dir.join(dir);
+        let mut files: Vec<PathBuf> = self
+            .shaders
+            .keys()
+            .filter(|path| path.starts_with(&dir))
+            .cloned()
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Composes the shader at `path` (relative to `manifest_dir`, or
+    /// absolute) with `shader_defs` and validates it. Library modules
+    /// without entry points are validated too.
+    ///
+    /// Errors are rendered with the source location they point at.
+    pub fn compose(
+        &mut self,
+        path: impl AsRef<Path>,
+        shader_defs: &[ShaderDefVal],
+    ) -> Result<(), String> {
+        let path = self.manifest_dir.join(path);
+        let Some(&id) = self.shaders.get(&path) else {
+            panic!("{} isn't in the library", path.display());
+        };
+
+        // every call is its own pipeline, so nothing is served from the
+        // cache of a previous permutation
+        self.next_pipeline += 1;
+        self.cache
+            .get(&(), self.next_pipeline, id, shader_defs)
+            .map(|_| ())
+            .map_err(|error| match error {
+                PipelineCacheError::ProcessShaderError(error) => {
+                    error.emit_to_string(&self.cache.composer)
+                }
+                other => other.to_string(),
+            })
+    }
+}
+
+/// Composes `path` under every set of defs and collects the failures, each
+/// labelled with the defs it failed under.
+pub fn compose_all(
+    library: &mut ShaderLibrary,
+    path: impl AsRef<Path>,
+    permutations: impl IntoIterator<Item = Vec<ShaderDefVal>>,
+) -> Vec<String> {
+    let path = path.as_ref();
+    permutations
+        .into_iter()
+        .filter_map(|shader_defs| {
+            library.compose(path, &shader_defs).err().map(|error| {
+                format!(
+                    "{} with {}\n{error}",
+                    path.display(),
+                    defs::describe(&shader_defs)
+                )
+            })
+        })
+        .collect()
+}
+
+/// Panics listing every failure, if there are any.
+pub fn assert_no_failures(failures: &[String]) {
+    assert!(
+        failures.is_empty(),
+        "{} permutations failed to compose:\n\n{}",
+        failures.len(),
+        failures.join("\n\n")
+    );
+}
+
+/// Validates the composed module, even in release builds where naga_oil
+/// skips validation.
+#[allow(clippy::result_large_err)]
+fn validate(
+    _render_device: &(),
+    source: ShaderCacheSource,
+    _validate_shader: &ValidateShader,
+) -> Result<(), PipelineCacheError> {
+    let module = match source {
+        ShaderCacheSource::Naga(module) => module,
+        ShaderCacheSource::Wgsl(source) => naga::front::wgsl::parse_str(&source)
+            .map_err(|error| PipelineCacheError::CreateShaderModule(error.to_string()))?,
+        ShaderCacheSource::SpirV(_) => unreachable!("only wgsl is composed"),
+    };
+    Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|error| PipelineCacheError::CreateShaderModule(format!("{error:?}")))?;
+    Ok(())
+}
+
+/// The `src` directories of `crates`, as resolved by cargo from
+/// `manifest_dir`.
+///
+/// The graph can hold several versions of a crate, for example while
+/// dependencies move between bevy releases, so each name is looked up
+/// breadth-first from the package at `manifest_dir`. That finds the
+/// version it depends on most directly rather than whichever cargo
+/// lists first.
+fn crate_roots(manifest_dir: &Path, crates: &[&str]) -> Vec<PathBuf> {
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .args(["metadata", "--format-version", "1"])
+        .current_dir(manifest_dir)
+        .output()
+        .expect("cargo metadata");
+    assert!(
+        output.status.success(),
+        "cargo metadata failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let packages: HashMap<&str, &serde_json::Value> = metadata["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|package| (package["id"].as_str().unwrap(), package))
+        .collect();
+    let nodes: HashMap<&str, &serde_json::Value> = metadata["resolve"]["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| (node["id"].as_str().unwrap(), node))
+        .collect();
+
+    let manifest_path = manifest_dir.join("Cargo.toml");
+    let root = packages
+        .iter()
+        .find(|(_, package)| Path::new(package["manifest_path"].as_str().unwrap()) == manifest_path)
+        .map(|(id, _)| *id)
+        .unwrap_or_else(|| panic!("no package at {}", manifest_dir.display()));
+
+    // breadth-first, so nearer dependencies win
+    let mut order = vec![root];
+    let mut next = 0;
+    while let Some(id) = order.get(next).copied() {
+        next += 1;
+        for dep in nodes[id]["deps"].as_array().unwrap() {
+            let dep = dep["pkg"].as_str().unwrap();
+            if !order.contains(&dep) {
+                order.push(dep);
+            }
+        }
+    }
+
+    crates
+        .iter()
+        .map(|name| {
+            let package = order
+                .iter()
+                .map(|id| packages[id])
+                .find(|package| package["name"] == *name)
+                .unwrap_or_else(|| panic!("{name} is not in the dependency graph"));
+            Path::new(package["manifest_path"].as_str().unwrap())
+                .parent()
+                .unwrap()
+                .join("src")
+        })
+        .collect()
+}
+
+fn collect_wgsl(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|error| panic!("couldn't read {}: {error}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_wgsl(&path, files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "wgsl")
+        {
+            files.push(path);
+        }
+    }
+}
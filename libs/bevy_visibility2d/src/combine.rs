@@ -0,0 +1,103 @@
+use bevy_math::{Rect, Vec2};
+
+use crate::Polygon;
+
+/// How the views of several viewers are combined into
+/// one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Combine {
+    /// Visible to any of them.
+    #[default]
+    Union,
+    /// Visible to all of them.
+    Intersection,
+}
+
+/// The area `combine` covers, as triangles.
+///
+/// The triangles of a union overlap where the views
+/// do, which only matters when they're drawn
+/// translucent.
+pub fn combine_polygons(polygons: &[Polygon], combine: Combine) -> Vec<[Vec2; 3]> {
+    let triangles =
+        |polygon: &Polygon| -> Vec<[Vec2; 3]> { polygon.triangles().filter(has_area).collect() };
+
+    let Some((first, rest)) = polygons.split_first() else {
+        return vec![];
+    };
+    match combine {
+        Combine::Union => polygons.iter().flat_map(triangles).collect(),
+        Combine::Intersection => {
+            // the fan triangles of one polygon don't
+            // overlap, so neither do the convex pieces
+            // cut from them
+            let mut pieces: Vec<Vec<Vec2>> = triangles(first).into_iter().map(Vec::from).collect();
+            for polygon in rest {
+                let clips = triangles(polygon);
+                pieces = pieces
+                    .iter()
+                    .flat_map(|piece| {
+                        let bounds = Rect::from_corners(
+                            piece.iter().copied().reduce(Vec2::min).unwrap_or_default(),
+                            piece.iter().copied().reduce(Vec2::max).unwrap_or_default(),
+                        );
+                        clips
+                            .iter()
+                            .filter(move |clip| {
+                                let clip_bounds = Rect::from_corners(
+                                    clip[0].min(clip[1]).min(clip[2]),
+                                    clip[0].max(clip[1]).max(clip[2]),
+                                );
+                                !bounds.intersect(clip_bounds).is_empty()
+                            })
+                            .map(|clip| clip_convex(piece, clip))
+                            .filter(|piece| piece.len() >= 3)
+                    })
+                    .collect();
+            }
+            pieces
+                .iter()
+                .flat_map(|piece| (1..piece.len() - 1).map(|i| [piece[0], piece[i], piece[i + 1]]))
+                // pieces cut down to where two outlines
+                // touch
+                .filter(has_area)
+                .collect()
+        }
+    }
+}
+
+/// Whether the triangle is counter-clockwise and not a
+/// sliver. Rays grazing a corner leave slivers with
+/// both far corners on one ray, and rounding would let
+/// anything through them when clipping.
+fn has_area(&[a, b, c]: &[Vec2; 3]) -> bool {
+    let (ab, ac) = (b - a, c - a);
+    ab.perp_dot(ac) > 1e-4 * ab.length() * ac.length()
+}
+
+/// The part of the convex `piece` inside the
+/// counter-clockwise triangle `clip`.
+fn clip_convex(piece: &[Vec2], clip: &[Vec2; 3]) -> Vec<Vec2> {
+    let mut piece = piece.to_vec();
+    for i in 0..3 {
+        let (a, b) = (clip[i], clip[(i + 1) % 3]);
+        let side = |point: Vec2| (b - a).perp_dot(point - a);
+        let mut kept = Vec::with_capacity(piece.len() + 1);
+        for (j, &current) in piece.iter().enumerate() {
+            let next = piece[(j + 1) % piece.len()];
+            let (current_side, next_side) = (side(current), side(next));
+            if current_side >= 0. {
+                kept.push(current);
+            }
+            if (current_side >= 0.) != (next_side >= 0.) {
+                let t = current_side / (current_side - next_side);
+                kept.push(current.lerp(next, t));
+            }
+        }
+        piece = kept;
+        if piece.len() < 3 {
+            break;
+        }
+    }
+    piece
+}
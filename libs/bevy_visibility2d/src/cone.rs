@@ -0,0 +1,234 @@
+use std::f32::consts::TAU;
+
+use bevy_math::{Dir2, Vec2};
+
+use crate::{Polygon, Segment, compute_visibility_polygon};
+
+/// How far, and in which directions, a viewer sees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewCone {
+    /// The middle of the cone.
+    pub facing: Dir2,
+    /// The angle between the edges of the cone, in
+    /// radians. `TAU` or more sees all around.
+    pub fov: f32,
+    /// How far the viewer sees, `f32::INFINITY` for as
+    /// far as the view is open.
+    pub range: f32,
+}
+
+impl Default for ViewCone {
+    fn default() -> Self {
+        Self {
+            facing: Dir2::X,
+            fov: TAU,
+            range: f32::INFINITY,
+        }
+    }
+}
+
+/// Computes what's visible from `origin` within
+/// `cone` when `segments` block the view.
+///
+/// Unlike clipping the result of
+/// [`compute_visibility_polygon`], the view reaches
+/// the full range where nothing blocks it, and segments
+/// out of range are skipped.
+pub fn compute_view_polygon(origin: Vec2, segments: &[Segment], cone: &ViewCone) -> Polygon {
+    if cone.range == f32::INFINITY {
+        return compute_visibility_polygon(origin, segments).clip_to_cone(cone);
+    }
+
+    let in_range = segments.iter().copied().filter(|segment| {
+        let along = segment.end - segment.start;
+        let t = (origin - segment.start).dot(along) / along.length_squared();
+        let nearest = segment.start + along * t.clamp(0., 1.);
+        !t.is_finite() || nearest.distance(origin) <= cone.range
+    });
+    // a box just outside the range stands in for the
+    // bounds, so the view is cut off by the range
+    let corner = Vec2::splat(cone.range * 1.1);
+    let corners = [
+        origin - corner,
+        origin + corner.with_y(-corner.y),
+        origin + corner,
+        origin + corner.with_x(-corner.x),
+    ];
+    let walls: Vec<Segment> = in_range
+        .chain([0, 1, 2, 3].map(|i| Segment::new(corners[i], corners[(i + 1) % 4])))
+        .collect();
+    compute_visibility_polygon(origin, &walls).clip_to_cone(cone)
+}
+
+/// How many segments a full circle of range is drawn
+/// with. Arcs get their share of them.
+const CIRCLE_SEGMENTS: f32 = 64.;
+
+impl Polygon {
+    /// The part of the polygon inside `cone`, with
+    /// arcs where the range cuts it off.
+    ///
+    /// Unless the cone sees all around, the origin is a
+    /// vertex of the result.
+    pub fn clip_to_cone(&self, cone: &ViewCone) -> Polygon {
+        let sees_around = cone.fov >= TAU;
+        if self.vertices.is_empty() || (sees_around && cone.range == f32::INFINITY) {
+            return self.clone();
+        }
+
+        let relative: Vec<Vec2> = self
+            .vertices
+            .iter()
+            .map(|&vertex| vertex - self.origin)
+            .collect();
+        let (path, closed) = if sees_around {
+            (relative, true)
+        } else {
+            (cone_path(&relative, cone), false)
+        };
+
+        let mut vertices = if cone.range == f32::INFINITY {
+            path
+        } else {
+            clip_range(&path, closed, cone.range)
+        };
+        if !closed {
+            vertices.push(Vec2::ZERO);
+        }
+
+        Polygon {
+            origin: self.origin,
+            vertices: vertices
+                .into_iter()
+                .map(|vertex| vertex + self.origin)
+                .collect(),
+        }
+    }
+}
+
+/// The outline between the edges of the cone, from
+/// the clockwise edge to the counter-clockwise one.
+fn cone_path(vertices: &[Vec2], cone: &ViewCone) -> Vec<Vec2> {
+    let start = Vec2::from_angle(-cone.fov / 2.).rotate(*cone.facing);
+    let end = Vec2::from_angle(cone.fov / 2.).rotate(*cone.facing);
+    let count = vertices.len();
+
+    // the edge the clockwise side of the cone hits,
+    // nearest first when it grazes a corner
+    let Some((first, hit)) = (0..count)
+        .filter(|&i| {
+            let (a, b) = (vertices[i], vertices[(i + 1) % count]);
+            a.perp_dot(start) >= 0. && start.perp_dot(b) > 0.
+        })
+        .map(|i| (i, ray_hit(start, vertices[i], vertices[(i + 1) % count])))
+        .min_by(|(_, a), (_, b)| a.length_squared().total_cmp(&b.length_squared()))
+    else {
+        return vec![];
+    };
+
+    let mut path = vec![hit];
+    for step in 1..=count {
+        let (previous, vertex) = (
+            vertices[(first + step - 1) % count],
+            vertices[(first + step) % count],
+        );
+        // `angle_to` loses small angles to `acos`, which
+        // would let corners just outside the cone in
+        let angle = start
+            .perp_dot(vertex)
+            .atan2(start.dot(vertex))
+            .rem_euclid(TAU);
+        if angle >= cone.fov {
+            path.push(ray_hit(end, previous, vertex));
+            return path;
+        }
+        path.push(vertex);
+    }
+    // all the way around: the cone leaves out less
+    // than the edge the clockwise side hit, so the
+    // other side hits it too
+    path.push(ray_hit(end, vertices[first], vertices[(first + 1) % count]));
+    path
+}
+
+/// Where the ray from the origin along `direction`
+/// meets the line through `a` and `b`, or `b` if it
+/// doesn't.
+fn ray_hit(direction: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let along = b - a;
+    let distance = a.perp_dot(along) / direction.perp_dot(along);
+    if distance.is_finite() && distance >= 0. {
+        direction * distance
+    } else {
+        b
+    }
+}
+
+/// Cuts `path` off at `range` from the origin, with
+/// arcs in place of the parts beyond it.
+fn clip_range(path: &[Vec2], closed: bool, range: f32) -> Vec<Vec2> {
+    let clamp = |point: Vec2| point.clamp_length_max(range);
+    let mut clipped = Vec::with_capacity(path.len() * 2);
+    let mut push = |point: Vec2| {
+        if clipped
+            .last()
+            .is_none_or(|last: &Vec2| last.distance(point) > range * 1e-6)
+        {
+            clipped.push(point);
+        }
+    };
+
+    let pairs = if closed {
+        path.len()
+    } else {
+        push(clamp(path[0]));
+        path.len().saturating_sub(1)
+    };
+    for i in 0..pairs {
+        let (start, end) = (path[i], path[(i + 1) % path.len()]);
+        let along = end - start;
+        let at = |t: f32| start + along * t;
+
+        // where the edge crosses the circle, if it does
+        let a = along.length_squared();
+        let b = 2. * start.dot(along);
+        let c = start.length_squared() - range * range;
+        let discriminant = b * b - 4. * a * c;
+        let mut knots = vec![0.];
+        if a > 0. && discriminant > 0. {
+            let root = discriminant.sqrt();
+            for t in [(-b - root) / (2. * a), (-b + root) / (2. * a)] {
+                if t > 0. && t < 1. {
+                    knots.push(t);
+                }
+            }
+        }
+        knots.push(1.);
+
+        for pair in knots.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if at((from + to) / 2.).length() <= range {
+                push(clamp(at(to)));
+                continue;
+            }
+            let (from, to) = (clamp(at(from)), clamp(at(to)));
+            let sweep = from.angle_to(to);
+            let steps = (sweep / TAU * CIRCLE_SEGMENTS).ceil().max(1.) as u32;
+            if sweep > 0. {
+                for step in 1..steps {
+                    let angle = sweep * step as f32 / steps as f32;
+                    push(Vec2::from_angle(angle).rotate(from));
+                }
+            }
+            push(to);
+        }
+    }
+
+    if closed
+        && clipped.len() > 1
+        && clipped[0].distance(clipped[clipped.len() - 1]) <= range * 1e-6
+    {
+        clipped.pop();
+    }
+    clipped
+}
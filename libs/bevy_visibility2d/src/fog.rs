@@ -0,0 +1,159 @@
+use bevy_math::{Rect, UVec2, Vec2};
+use thiserror::Error;
+
+use crate::Polygon;
+
+/// What's known about a spot of the map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FogState {
+    /// Never seen.
+    Unexplored,
+    /// Seen before, but not right now.
+    Explored,
+    /// Seen right now.
+    Visible,
+}
+
+/// Why an explored mask couldn't be loaded.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FogError {
+    #[error("the explored mask has {found} texels, but the fog has {expected}")]
+    SizeMismatch { expected: usize, found: usize },
+}
+
+/// A grid over a part of the world, remembering where
+/// viewers see now and what they've explored.
+///
+/// Texels are one byte each, 0 or 255, row by row from
+/// the top of `bounds`, like an image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FogMask {
+    bounds: Rect,
+    resolution: UVec2,
+    visible: Vec<u8>,
+    explored: Vec<u8>,
+}
+
+impl FogMask {
+    /// An unexplored mask of `resolution` texels
+    /// covering `bounds`.
+    pub fn new(bounds: Rect, resolution: UVec2) -> Self {
+        let texels = resolution.element_product() as usize;
+        Self {
+            bounds,
+            resolution,
+            visible: vec![0; texels],
+            explored: vec![0; texels],
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    pub fn resolution(&self) -> UVec2 {
+        self.resolution
+    }
+
+    /// The world size of a texel.
+    pub fn texel_size(&self) -> Vec2 {
+        self.bounds.size() / self.resolution.as_vec2()
+    }
+
+    /// Hides everything, keeping what was explored.
+    pub fn clear_visible(&mut self) {
+        self.visible.fill(0);
+    }
+
+    /// Makes the texels whose centers are inside
+    /// `polygon` visible, and explored.
+    pub fn reveal(&mut self, polygon: &Polygon) {
+        let max = self.resolution.as_vec2() - 1.;
+
+        for triangle in polygon.triangles() {
+            let [a, b, c] = triangle.map(|point| self.to_texels(point));
+            let area = (b - a).perp_dot(c - a);
+            if area == 0. || !area.is_finite() {
+                continue;
+            }
+            // texel centers are at half texels
+            let min = (a.min(b).min(c) - 0.5).ceil().max(Vec2::ZERO);
+            let end = (a.max(b).max(c) - 0.5).floor().min(max);
+            if min.x > end.x || min.y > end.y {
+                continue;
+            }
+            for y in min.y as u32..=end.y as u32 {
+                for x in min.x as u32..=end.x as u32 {
+                    let center = Vec2::new(x as f32, y as f32) + 0.5;
+                    // inside when on the same side of every
+                    // edge as the triangle's area
+                    let inside = [(a, b), (b, c), (c, a)]
+                        .iter()
+                        .all(|&(from, to)| (to - from).perp_dot(center - from) * area >= 0.);
+                    if inside {
+                        let index = (y * self.resolution.x + x) as usize;
+                        self.visible[index] = u8::MAX;
+                        self.explored[index] = u8::MAX;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The state of the texel at `point`. Outside the
+    /// bounds everything is unexplored.
+    pub fn state(&self, point: Vec2) -> FogState {
+        let Some(index) = self.index(point) else {
+            return FogState::Unexplored;
+        };
+        if self.visible[index] > 0 {
+            FogState::Visible
+        } else if self.explored[index] > 0 {
+            FogState::Explored
+        } else {
+            FogState::Unexplored
+        }
+    }
+
+    pub fn visible(&self) -> &[u8] {
+        &self.visible
+    }
+
+    /// The explored texels, to save with a game.
+    pub fn explored(&self) -> &[u8] {
+        &self.explored
+    }
+
+    /// Restores texels saved from [`FogMask::explored`]
+    /// of a mask with the same resolution.
+    pub fn load_explored(&mut self, explored: &[u8]) -> Result<(), FogError> {
+        if explored.len() != self.explored.len() {
+            return Err(FogError::SizeMismatch {
+                expected: self.explored.len(),
+                found: explored.len(),
+            });
+        }
+        self.explored.copy_from_slice(explored);
+        Ok(())
+    }
+
+    /// `point` in texels from the top left corner, which
+    /// [`FogMask::reveal`] and [`FogMask::state`] have to
+    /// agree on.
+    fn to_texels(&self, point: Vec2) -> Vec2 {
+        let texel = self.texel_size();
+        // rows go down from the top
+        Vec2::new(
+            (point.x - self.bounds.min.x) / texel.x,
+            (self.bounds.max.y - point.y) / texel.y,
+        )
+    }
+
+    fn index(&self, point: Vec2) -> Option<usize> {
+        if !self.bounds.contains(point) || self.resolution.min_element() == 0 {
+            return None;
+        }
+        let texel = self.to_texels(point).as_uvec2().min(self.resolution - 1);
+        Some((texel.y * self.resolution.x + texel.x) as usize)
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::{asset::RenderAssetUsages, image::ImageSampler, prelude::*};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::{FogMask, ViewGroupMembers, ViewPolygon};
+
+/// A persistent fog of war over `bounds`, revealed by
+/// the viewers [`InViewGroup`](crate::InViewGroup) of
+/// this entity.
+///
+/// The fog is written to `image` whenever it changes,
+/// in world space: red is visible now, green explored.
+/// Sample it with linear filtering at
+/// `(world - min) / size`, flipping y, for soft edges.
+#[derive(Component, Clone, Debug)]
+pub struct FogOfWar {
+    pub mask: FogMask,
+    /// How many texels the edges of the image fade over.
+    /// The mask itself stays sharp.
+    pub softness: u32,
+    /// Created when the fog is spawned, unless it's
+    /// already set.
+    pub image: Handle<Image>,
+}
+
+impl FogOfWar {
+    pub fn new(bounds: Rect, resolution: UVec2) -> Self {
+        Self {
+            mask: FogMask::new(bounds, resolution),
+            softness: 0,
+            image: Handle::default(),
+        }
+    }
+
+    pub fn with_softness(mut self, softness: u32) -> Self {
+        self.softness = softness;
+        self
+    }
+}
+
+pub(crate) fn add_fog_image(
+    add: On<Add, FogOfWar>,
+    mut fogs: Query<&mut FogOfWar>,
+    images: Option<ResMut<Assets<Image>>>,
+) {
+    // without images, only the mask is kept
+    let (Ok(mut fog), Some(mut images)) = (fogs.get_mut(add.entity), images) else {
+        return;
+    };
+    if fog.image != Handle::default() {
+        return;
+    }
+    let resolution = fog.mask.resolution();
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: resolution.x,
+            height: resolution.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0],
+        TextureFormat::Rg8Unorm,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::linear();
+    fog.image = images.add(image);
+}
+
+pub(crate) fn update_fog(
+    mut fogs: Query<(&mut FogOfWar, Option<&ViewGroupMembers>)>,
+    viewers: Query<&ViewPolygon>,
+    mut images: Option<ResMut<Assets<Image>>>,
+    mut previous: Local<Vec<u8>>,
+) {
+    for (mut fog, members) in &mut fogs {
+        // new fogs, and changes from outside like another
+        // softness or loaded texels
+        let edited = fog.is_changed();
+        let fog = fog.bypass_change_detection();
+        previous.clear();
+        previous.extend_from_slice(fog.mask.visible());
+        fog.mask.clear_visible();
+        for polygon in viewers.iter_many(members.into_iter().flat_map(|members| members.iter())) {
+            fog.mask.reveal(polygon);
+        }
+        // the mask is rebuilt every frame, but the image
+        // only changes with it. Texels are only explored
+        // by becoming visible.
+        if !edited && fog.mask.visible() == previous.as_slice() {
+            continue;
+        }
+
+        let Some(image) = images
+            .as_mut()
+            .and_then(|images| images.get_mut(&fog.image))
+        else {
+            continue;
+        };
+        let resolution = fog.mask.resolution();
+        let visible = blur(fog.mask.visible(), resolution, fog.softness);
+        let explored = blur(fog.mask.explored(), resolution, fog.softness);
+        image.data = Some(
+            visible
+                .into_iter()
+                .zip(explored)
+                .flat_map(|(visible, explored)| [visible, explored])
+                .collect(),
+        );
+    }
+}
+
+/// A box blur of `radius` texels, once along each axis.
+fn blur(texels: &[u8], resolution: UVec2, radius: u32) -> Vec<u8> {
+    if radius == 0 {
+        return texels.to_vec();
+    }
+    let (width, height) = (resolution.x as usize, resolution.y as usize);
+    let radius = radius as usize;
+    let pass = |texels: &[u8], step: usize, length: usize, lines: usize, line_step: usize| {
+        let mut blurred = vec![0; texels.len()];
+        for line in 0..lines {
+            let at = |i: usize| texels[line * line_step + i * step] as u32;
+            for i in 0..length {
+                let (start, end) = (i.saturating_sub(radius), (i + radius).min(length - 1));
+                let sum: u32 = (start..=end).map(at).sum();
+                blurred[line * line_step + i * step] = (sum / (end - start + 1) as u32) as u8;
+            }
+        }
+        blurred
+    };
+    let rows = pass(texels, 1, width, height, width);
+    pass(&rows, width, height, width, 1)
+}
@@ -0,0 +1,71 @@
+//! 2D line of sight.
+//!
+//! [`compute_visibility_polygon`] is the core: given a
+//! point and the segments blocking the view, it returns
+//! the [`Polygon`] visible from the point. It doesn't
+//! depend on the ECS, so it can run anywhere.
+//!
+//! ```
+//! use bevy_math::Vec2;
+//! use bevy_visibility2d::{Segment, compute_visibility_polygon};
+//!
+//! let wall = Segment::new(Vec2::new(-1., 2.), Vec2::new(1., 2.));
+//! let polygon = compute_visibility_polygon(Vec2::ZERO, &[wall]);
+//!
+//! assert!(polygon.contains(Vec2::new(0., 1.)));
+//! assert!(!polygon.contains(Vec2::new(0., 3.)));
+//! ```
+//!
+//! [`compute_view_polygon`] limits the view to a
+//! [`ViewCone`] with a range, and [`combine_polygons`]
+//! merges the views of several viewers.
+//! [`triangle_outline`] and [`grid_outline`] turn
+//! meshes and tiles into segments.
+//!
+//! With the default `bevy` feature,
+//! [`Visibility2dPlugin`] computes the polygon of every
+//! [`Viewer`] every frame, among [`Obstacle`] meshes
+//! and [`ObstacleOutline`] components like
+//! [`ObstaclePolygon`] and [`ObstacleGrid`]. It writes
+//! the polygon to the viewer's [`ViewMesh`], and the
+//! combined views of each [`ViewGroup`] to its mesh.
+//! [`VisibilityQuery`] answers what each viewer sees
+//! from those polygons, and [`EnteredView`] and
+//! [`ExitedView`] fire as [`Detectable`] entities come
+//! and go. A [`FogOfWar`] remembers what its viewers
+//! explored in a [`FogMask`], and draws it into an
+//! image.
+mod combine;
+mod cone;
+mod fog;
+#[cfg(feature = "bevy")]
+mod fog_of_war;
+#[cfg(feature = "bevy")]
+mod obstacle;
+mod outline;
+#[cfg(feature = "bevy")]
+mod plugin;
+mod polygon;
+#[cfg(feature = "bevy")]
+mod query;
+
+pub use combine::{Combine, combine_polygons};
+pub use cone::{ViewCone, compute_view_polygon};
+pub use fog::{FogError, FogMask, FogState};
+#[cfg(feature = "bevy")]
+pub use fog_of_war::FogOfWar;
+#[cfg(feature = "tilemap_chunk")]
+pub use obstacle::TilemapObstacle;
+#[cfg(feature = "bevy")]
+pub use obstacle::{
+    Obstacle, ObstacleGrid, ObstacleOutline, ObstacleOutlinePlugin, ObstaclePolygon,
+    ObstacleSegments,
+};
+pub use outline::{grid_outline, triangle_outline};
+#[cfg(feature = "bevy")]
+pub use plugin::{
+    InViewGroup, ViewGroup, ViewGroupMembers, ViewMesh, ViewPolygon, Viewer, Visibility2dPlugin,
+};
+pub use polygon::{Polygon, Segment, compute_visibility_polygon};
+#[cfg(feature = "bevy")]
+pub use query::{Detectable, EnteredView, ExitedView, SeenEntities, VisibilityQuery};
@@ -0,0 +1,381 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::AssetEventSystems,
+    mesh::{PrimitiveTopology, VertexAttributeValues},
+    platform::collections::HashSet,
+    prelude::*,
+};
+
+use crate::{Segment, grid_outline, triangle_outline};
+
+#[cfg(feature = "tilemap_chunk")]
+pub use tilemap::TilemapObstacle;
+
+/// A `Mesh2d` blocking the view. Triangle meshes block
+/// along their silhouette, line meshes along their
+/// lines.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[type_path = "api"]
+#[require(ObstacleSegments)]
+pub struct Obstacle;
+
+/// A component with an outline blocking the view, like
+/// a physics collider. Each one needs an
+/// [`ObstacleOutlinePlugin`].
+pub trait ObstacleOutline: Component {
+    /// The walls, in the entity's own space.
+    fn outline(&self) -> Vec<Segment>;
+}
+
+/// Makes every entity with a `T` an obstacle, rebuilding
+/// its walls when `T` changes and clearing them when
+/// it's removed.
+pub struct ObstacleOutlinePlugin<T>(PhantomData<T>);
+
+impl<T> Default for ObstacleOutlinePlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: ObstacleOutline> Plugin for ObstacleOutlinePlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.register_required_components::<T, ObstacleSegments>()
+            .add_observer(clear_outline::<T>)
+            .add_systems(
+                PostUpdate,
+                extract_outlines::<T>.in_set(ObstacleSystems::Extract),
+            );
+    }
+}
+
+/// A polygon, or a polyline when it isn't `closed`,
+/// blocking the view.
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct ObstaclePolygon {
+    pub vertices: Vec<Vec2>,
+    pub closed: bool,
+}
+
+impl ObstaclePolygon {
+    pub fn closed(vertices: impl IntoIterator<Item = Vec2>) -> Self {
+        Self {
+            vertices: vertices.into_iter().collect(),
+            closed: true,
+        }
+    }
+
+    pub fn open(vertices: impl IntoIterator<Item = Vec2>) -> Self {
+        Self {
+            vertices: vertices.into_iter().collect(),
+            closed: false,
+        }
+    }
+}
+
+impl ObstacleOutline for ObstaclePolygon {
+    fn outline(&self) -> Vec<Segment> {
+        let count = self.vertices.len();
+        let pairs = if self.closed && count > 2 {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        (0..pairs)
+            .map(|i| Segment::new(self.vertices[i], self.vertices[(i + 1) % count]))
+            .collect()
+    }
+}
+
+/// A grid of solid and open cells blocking the view,
+/// centered on the entity. Row 0 is the top row, like
+/// in bevy's `TilemapChunk`.
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct ObstacleGrid {
+    pub size: UVec2,
+    pub tile_size: Vec2,
+    /// Whether each cell is solid, row by row.
+    pub solid: Vec<bool>,
+}
+
+impl ObstacleGrid {
+    /// A grid with every cell open.
+    pub fn new(size: UVec2, tile_size: Vec2) -> Self {
+        Self {
+            size,
+            tile_size,
+            solid: vec![false; size.element_product() as usize],
+        }
+    }
+
+    pub fn is_solid(&self, cell: UVec2) -> bool {
+        self.solid
+            .get((cell.y * self.size.x + cell.x) as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_solid(&mut self, cell: UVec2, solid: bool) {
+        if let Some(tile) = self.solid.get_mut((cell.y * self.size.x + cell.x) as usize) {
+            *tile = solid;
+        }
+    }
+}
+
+impl ObstacleOutline for ObstacleGrid {
+    fn outline(&self) -> Vec<Segment> {
+        grid_outline(self.size, self.tile_size, |cell| self.is_solid(cell))
+    }
+}
+
+/// The walls of an obstacle, kept until its mesh,
+/// outline or transform changes.
+#[derive(Component, Clone, Debug, Default)]
+pub struct ObstacleSegments {
+    local: Vec<Segment>,
+    world: Vec<Segment>,
+    /// `local` changed since `world` was built
+    dirty: bool,
+}
+
+impl ObstacleSegments {
+    /// The walls, in world space.
+    pub fn segments(&self) -> &[Segment] {
+        &self.world
+    }
+
+    fn set_local(&mut self, local: Vec<Segment>) {
+        self.local = local;
+        self.dirty = true;
+    }
+
+    /// Right away, since entities without a transform
+    /// are never moved into world space again.
+    fn clear(&mut self) {
+        self.local.clear();
+        self.world.clear();
+        self.dirty = false;
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum ObstacleSystems {
+    /// Rebuilding walls in the obstacles' own space
+    Extract,
+    /// Moving them into world space
+    Transform,
+}
+
+/// Adds the systems keeping [`ObstacleSegments`] up to
+/// date. Part of the
+/// [`Visibility2dPlugin`](crate::Visibility2dPlugin).
+pub(crate) struct ObstaclePlugin;
+
+impl Plugin for ObstaclePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Obstacle>()
+            .register_type::<ObstaclePolygon>()
+            .register_type::<ObstacleGrid>()
+            .add_plugins((
+                ObstacleOutlinePlugin::<ObstaclePolygon>::default(),
+                ObstacleOutlinePlugin::<ObstacleGrid>::default(),
+            ))
+            .add_observer(clear_mesh_outline)
+            .configure_sets(
+                PostUpdate,
+                (ObstacleSystems::Extract, ObstacleSystems::Transform)
+                    .chain()
+                    .after(TransformSystems::Propagate)
+                    // mesh changes are picked up the same frame
+                    .after(AssetEventSystems),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    extract_mesh_outlines.in_set(ObstacleSystems::Extract),
+                    transform_outlines.in_set(ObstacleSystems::Transform),
+                ),
+            );
+
+        #[cfg(feature = "tilemap_chunk")]
+        app.register_type::<TilemapObstacle>().add_systems(
+            PostUpdate,
+            tilemap::sync_tilemap_grids.before(ObstacleSystems::Extract),
+        );
+    }
+}
+
+fn extract_outlines<T: ObstacleOutline>(
+    mut outlines: Query<(&T, &mut ObstacleSegments), Changed<T>>,
+) {
+    for (outline, mut segments) in &mut outlines {
+        segments.set_local(outline.outline());
+    }
+}
+
+/// The segments stay behind, required by the obstacle,
+/// and would keep blocking the view.
+fn clear_outline<T: ObstacleOutline>(
+    remove: On<Remove, T>,
+    mut obstacles: Query<&mut ObstacleSegments>,
+) {
+    if let Ok(mut segments) = obstacles.get_mut(remove.entity) {
+        segments.clear();
+    }
+}
+
+/// Like [`clear_outline`], for meshes that lose either
+/// their [`Obstacle`] or their mesh.
+fn clear_mesh_outline(
+    remove: On<Remove, (Obstacle, Mesh2d)>,
+    mut obstacles: Query<&mut ObstacleSegments, With<Obstacle>>,
+) {
+    if let Ok(mut segments) = obstacles.get_mut(remove.entity) {
+        segments.clear();
+    }
+}
+
+fn extract_mesh_outlines(
+    mut mesh_events: MessageReader<AssetEvent<Mesh>>,
+    mut obstacles: Query<(Ref<Mesh2d>, Ref<Obstacle>, &mut ObstacleSegments)>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    let changed: HashSet<AssetId<Mesh>> = mesh_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (mesh2d, obstacle, mut segments) in &mut obstacles {
+        // meshes already loaded when they become
+        // obstacles send no asset event
+        let rebuild = mesh2d.is_changed()
+            || obstacle.is_added()
+            || segments.is_added()
+            || changed.contains(&mesh2d.id());
+        if !rebuild {
+            continue;
+        }
+        let local = meshes.get(&mesh2d.0).map(mesh_outline).unwrap_or_default();
+        segments.set_local(local);
+    }
+}
+
+fn transform_outlines(mut obstacles: Query<(Ref<GlobalTransform>, &mut ObstacleSegments)>) {
+    for (transform, mut segments) in &mut obstacles {
+        if !transform.is_changed() && !segments.dirty {
+            continue;
+        }
+        let affine = transform.affine();
+        let to_world = |point: Vec2| affine.transform_point3(point.extend(0.)).xy();
+        let segments = &mut *segments;
+        segments.world = segments
+            .local
+            .iter()
+            .map(|segment| Segment::new(to_world(segment.start), to_world(segment.end)))
+            .collect();
+        segments.dirty = false;
+    }
+}
+
+/// The walls of an obstacle mesh, in its own space.
+fn mesh_outline(mesh: &Mesh) -> Vec<Segment> {
+    let positions: Vec<Vec2> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => {
+            positions.iter().map(|&[x, y, _]| Vec2::new(x, y)).collect()
+        }
+        Some(VertexAttributeValues::Float32x2(positions)) => {
+            positions.iter().copied().map(Vec2::from).collect()
+        }
+        _ => {
+            warn_once!("Obstacle meshes need Float32x3 or Float32x2 positions");
+            return vec![];
+        }
+    };
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+    let line =
+        |start: usize, end: usize| Some(Segment::new(*positions.get(start)?, *positions.get(end)?));
+
+    match mesh.primitive_topology() {
+        PrimitiveTopology::TriangleList => {
+            let triangles: Vec<[usize; 3]> = indices
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                .collect();
+            triangle_outline(&positions, &triangles)
+        }
+        PrimitiveTopology::TriangleStrip => {
+            // the winding flips every other triangle,
+            // which the outline doesn't care about
+            let triangles: Vec<[usize; 3]> = indices
+                .windows(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                .collect();
+            triangle_outline(&positions, &triangles)
+        }
+        PrimitiveTopology::LineList => indices
+            .chunks_exact(2)
+            .filter_map(|pair| line(pair[0], pair[1]))
+            .collect(),
+        PrimitiveTopology::LineStrip => indices
+            .windows(2)
+            .filter_map(|pair| line(pair[0], pair[1]))
+            .collect(),
+        topology => {
+            warn_once!("Obstacle meshes with {topology:?} aren't supported");
+            vec![]
+        }
+    }
+}
+
+#[cfg(feature = "tilemap_chunk")]
+mod tilemap {
+    use bevy::{
+        prelude::*,
+        sprite_render::{TilemapChunk, TilemapChunkTileData},
+    };
+
+    use super::ObstacleGrid;
+
+    /// Makes the visible tiles of a `TilemapChunk` block
+    /// the view, through an [`ObstacleGrid`] kept in
+    /// sync with the tiles.
+    #[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+    #[reflect(Component)]
+    #[require(ObstacleGrid)]
+    pub struct TilemapObstacle;
+
+    pub(super) fn sync_tilemap_grids(
+        mut chunks: Query<
+            (&TilemapChunk, &TilemapChunkTileData, &mut ObstacleGrid),
+            (
+                With<TilemapObstacle>,
+                Or<(
+                    Changed<TilemapChunk>,
+                    Changed<TilemapChunkTileData>,
+                    Added<TilemapObstacle>,
+                )>,
+            ),
+        >,
+    ) {
+        for (chunk, tiles, mut grid) in &mut chunks {
+            *grid = ObstacleGrid {
+                size: chunk.chunk_size,
+                tile_size: chunk.tile_display_size.as_vec2(),
+                solid: tiles
+                    .iter()
+                    .map(|tile| tile.is_some_and(|tile| tile.visible))
+                    .collect(),
+            };
+        }
+    }
+}
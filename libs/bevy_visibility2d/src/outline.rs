@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use bevy_math::{UVec2, Vec2};
+
+use crate::Segment;
+
+/// The silhouette of a triangle mesh: the edges only
+/// one triangle uses.
+///
+/// Vertices at the same position count as one, so seams
+/// where a mesh duplicates vertices, for uvs or
+/// normals, don't show up as edges. Edges inside the
+/// shape are shared by two triangles and left out, so
+/// the vertex order doesn't matter.
+pub fn triangle_outline(positions: &[Vec2], triangles: &[[usize; 3]]) -> Vec<Segment> {
+    let mut welded = HashMap::new();
+    let ids: Vec<usize> = positions
+        .iter()
+        .map(|position| {
+            let next = welded.len();
+            *welded
+                .entry(position.to_array().map(f32::to_bits))
+                .or_insert(next)
+        })
+        .collect();
+
+    // how often each edge is used, and by which
+    // vertices the first time
+    let mut edges: HashMap<(usize, usize), (u32, [usize; 2])> = HashMap::new();
+    let mut order = vec![];
+    for triangle in triangles {
+        if triangle.iter().any(|&index| index >= positions.len()) {
+            continue;
+        }
+        let [a, b, c] = triangle.map(|index| ids[index]);
+        if a == b || b == c || c == a {
+            continue;
+        }
+        for [start, end] in [[0, 1], [1, 2], [2, 0]] {
+            let (from, to) = (ids[triangle[start]], ids[triangle[end]]);
+            let key = (from.min(to), from.max(to));
+            let (count, _) = edges.entry(key).or_insert_with(|| {
+                order.push(key);
+                (0, [triangle[start], triangle[end]])
+            });
+            *count += 1;
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|key| {
+            let (count, [start, end]) = edges[&key];
+            (count == 1).then(|| Segment::new(positions[start], positions[end]))
+        })
+        .collect()
+}
+
+/// The walls between solid and open cells of a grid of
+/// `size` cells of `tile_size`, centered on the origin.
+///
+/// Row 0 is the top row, like in images and bevy's
+/// `TilemapChunk`. Walls running along several cells
+/// come out as one segment.
+pub fn grid_outline(
+    size: UVec2,
+    tile_size: Vec2,
+    is_solid: impl Fn(UVec2) -> bool,
+) -> Vec<Segment> {
+    let solid = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && x < size.x as i64
+            && y < size.y as i64
+            && is_solid(UVec2::new(x as u32, y as u32))
+    };
+    let half = size.as_vec2() * tile_size / 2.;
+    // the corner between cells, y going up
+    let corner = |x: i64, y: i64| {
+        Vec2::new(
+            x as f32 * tile_size.x - half.x,
+            half.y - y as f32 * tile_size.y,
+        )
+    };
+
+    let mut segments = vec![];
+    // a run of walls along one line, and which side of
+    // it is solid
+    let mut flush = |run: &mut Option<(i64, bool)>, end: i64, line: i64, horizontal: bool| {
+        if let Some((start, _)) = run.take() {
+            segments.push(if horizontal {
+                Segment::new(corner(start, line), corner(end, line))
+            } else {
+                Segment::new(corner(line, start), corner(line, end))
+            });
+        }
+    };
+
+    // horizontal walls, above row `y`
+    for y in 0..=size.y as i64 {
+        let mut run = None;
+        for x in 0..=size.x as i64 {
+            let wall = (x < size.x as i64 && solid(x, y - 1) != solid(x, y)).then(|| solid(x, y));
+            if run.map(|(_, side)| side) != wall {
+                flush(&mut run, x, y, true);
+                run = wall.map(|side| (x, side));
+            }
+        }
+    }
+    // vertical walls, left of column `x`
+    for x in 0..=size.x as i64 {
+        let mut run = None;
+        for y in 0..=size.y as i64 {
+            let wall = (y < size.y as i64 && solid(x - 1, y) != solid(x, y)).then(|| solid(x, y));
+            if run.map(|(_, side)| side) != wall {
+                flush(&mut run, y, x, false);
+                run = wall.map(|side| (y, side));
+            }
+        }
+    }
+    segments
+}
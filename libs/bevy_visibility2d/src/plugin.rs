@@ -0,0 +1,181 @@
+use std::f32::consts::TAU;
+
+use bevy::{asset::RenderAssetUsages, mesh::PrimitiveTopology, prelude::*};
+
+use crate::{
+    Combine, Detectable, ObstacleSegments, Polygon, SeenEntities, Segment, ViewCone,
+    combine_polygons, compute_view_polygon,
+    fog_of_war::{add_fog_image, update_fog},
+    obstacle::{ObstaclePlugin, ObstacleSystems},
+    query::update_seen_entities,
+};
+
+/// Keeps the [`ViewMesh`] of every [`Viewer`], and the
+/// mesh of every [`ViewGroup`], up to date with what
+/// they see past the obstacles.
+#[derive(Default)]
+pub struct Visibility2dPlugin;
+
+impl Plugin for Visibility2dPlugin {
+    fn build(&self, app: &mut App) {
+        // Running directly after propagation ensures that
+        // the mesh is up to date at the beginning of each
+        // frame which in turn allows placing the
+        // visibility mesh as a child of an entity
+        app.add_plugins(ObstaclePlugin)
+            .register_type::<Viewer>()
+            .register_type::<Detectable>()
+            .add_observer(add_view_mesh)
+            .add_observer(add_fog_image)
+            .add_systems(
+                PostUpdate,
+                (
+                    calculate_visibility,
+                    (combine_views, update_seen_entities, update_fog),
+                )
+                    .chain()
+                    .after(ObstacleSystems::Transform),
+            );
+    }
+}
+
+/// Something that sees, like a guard, a camera or the
+/// player.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+#[require(ViewPolygon, SeenEntities)]
+pub struct Viewer {
+    /// The angle of the view cone, in radians. `TAU`
+    /// sees all around.
+    pub fov: f32,
+    /// How far the viewer sees.
+    pub range: f32,
+    /// The middle of the view cone, turned with the
+    /// viewer's rotation.
+    pub facing: Dir2,
+}
+
+impl Default for Viewer {
+    fn default() -> Self {
+        Self {
+            fov: TAU,
+            range: f32::INFINITY,
+            facing: Dir2::X,
+        }
+    }
+}
+
+/// What a [`Viewer`] saw this frame, in world space.
+#[derive(Component, Clone, Debug, Default, Deref)]
+pub struct ViewPolygon(pub Polygon);
+
+/// The triangle list mesh a [`Viewer`]'s view is
+/// written to, in the viewer's space so it can be
+/// drawn by a child. Viewers spawned without one get
+/// an empty mesh.
+#[derive(Component, Clone, Debug)]
+pub struct ViewMesh(pub Handle<Mesh>);
+
+/// One mesh showing the combined view of its
+/// [`ViewGroupMembers`], in world space. For example
+/// the union of a team's views for fog of war.
+#[derive(Component, Clone, Debug)]
+pub struct ViewGroup {
+    pub combine: Combine,
+    pub mesh: Handle<Mesh>,
+}
+
+/// The [`ViewGroup`] a [`Viewer`] is part of.
+#[derive(Component, Clone, Copy, Debug)]
+#[relationship(relationship_target = ViewGroupMembers)]
+pub struct InViewGroup(pub Entity);
+
+/// The [`Viewer`]s of a [`ViewGroup`].
+#[derive(Component, Debug, Default)]
+#[relationship_target(relationship = InViewGroup)]
+pub struct ViewGroupMembers(Vec<Entity>);
+
+fn add_view_mesh(
+    add: On<Add, Viewer>,
+    view_meshes: Query<(), With<ViewMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut commands: Commands,
+) {
+    if view_meshes.contains(add.entity) {
+        return;
+    }
+    let mesh = meshes.add(Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    ));
+    commands.entity(add.entity).insert(ViewMesh(mesh));
+}
+
+fn calculate_visibility(
+    obstacles: Query<&ObstacleSegments>,
+    mut viewers: Query<(
+        &Viewer,
+        &GlobalTransform,
+        &mut ViewPolygon,
+        Option<&ViewMesh>,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if viewers.is_empty() {
+        return;
+    }
+    let segments: Vec<Segment> = obstacles
+        .iter()
+        .flat_map(|obstacle| obstacle.segments())
+        .copied()
+        .collect();
+
+    for (viewer, transform, mut view_polygon, view_mesh) in &mut viewers {
+        let facing = (transform.rotation() * viewer.facing.extend(0.)).xy();
+        let cone = ViewCone {
+            facing: Dir2::new(facing).unwrap_or(viewer.facing),
+            fov: viewer.fov,
+            range: viewer.range,
+        };
+        let polygon = compute_view_polygon(transform.translation().xy(), &segments, &cone);
+
+        if let Some(view_mesh) = view_mesh {
+            let to_local = transform.affine().inverse();
+            let positions = polygon
+                .triangles()
+                .flatten()
+                .map(|vertex| to_local.transform_point3(vertex.extend(0.)).with_z(0.));
+            write_positions(&mut meshes, &view_mesh.0, positions);
+        }
+        view_polygon.0 = polygon;
+    }
+}
+
+fn combine_views(
+    groups: Query<(&ViewGroup, Option<&ViewGroupMembers>)>,
+    viewers: Query<&ViewPolygon>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (group, members) in &groups {
+        let polygons: Vec<Polygon> = viewers
+            .iter_many(members.into_iter().flat_map(|members| members.iter()))
+            .map(|polygon| polygon.0.clone())
+            .collect();
+        let positions = combine_polygons(&polygons, group.combine)
+            .into_iter()
+            .flatten()
+            .map(|vertex| vertex.extend(0.));
+        write_positions(&mut meshes, &group.mesh, positions);
+    }
+}
+
+fn write_positions(
+    meshes: &mut Assets<Mesh>,
+    handle: &Handle<Mesh>,
+    positions: impl Iterator<Item = Vec3>,
+) {
+    if let Some(mesh) = meshes.get_mut(handle) {
+        let positions: Vec<[f32; 3]> = positions.map(|position| position.to_array()).collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    }
+}
@@ -0,0 +1,580 @@
+use std::{
+    cell::Cell,
+    cmp::{Ordering, Reverse},
+    collections::{BTreeSet, BinaryHeap},
+    f64::consts::TAU,
+    ops::Bound::{Excluded, Unbounded},
+};
+
+use bevy_math::{DVec2, Vec2};
+
+/// A wall blocking the view, with no thickness.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Segment {
+    pub const fn new(start: Vec2, end: Vec2) -> Self {
+        Self { start, end }
+    }
+}
+
+/// Everything visible from `origin`.
+///
+/// The vertices are counter-clockwise, and every ray
+/// from the origin crosses the outline exactly once,
+/// unless the polygon was clipped to a cone.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polygon {
+    pub origin: Vec2,
+    pub vertices: Vec<Vec2>,
+}
+
+impl Polygon {
+    /// The outline, as pairs of consecutive vertices.
+    pub fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        let next = self.vertices.iter().cycle().skip(1);
+        self.vertices.iter().copied().zip(next.copied())
+    }
+
+    /// Whether `point` is inside the outline.
+    pub fn contains(&self, point: Vec2) -> bool {
+        // even-odd rule, counting the edges a ray to +x
+        // crosses
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if (a.y > point.y) != (b.y > point.y) {
+                let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+                if point.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    /// Whether any part of the circle around `center`
+    /// is inside the outline.
+    pub fn intersects_circle(&self, center: Vec2, radius: f32) -> bool {
+        self.contains(center)
+            || self.edges().any(|(a, b)| {
+                let along = b - a;
+                let t = (center - a).dot(along) / along.length_squared();
+                let nearest = if t.is_finite() {
+                    a + along * t.clamp(0., 1.)
+                } else {
+                    a
+                };
+                nearest.distance(center) <= radius
+            })
+    }
+
+    /// The polygon as a fan of triangles around the
+    /// origin, each counter-clockwise.
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec2; 3]> + '_ {
+        self.edges().map(|(a, b)| [self.origin, a, b])
+    }
+}
+
+/// Computes what's visible from `origin` when
+/// `segments` block the view.
+///
+/// The segments don't have to form closed shapes, and
+/// may touch or cross each other. Segments pointing
+/// straight at the origin, or running through it,
+/// only graze the view and are ignored. The view is
+/// bounded by a box around all the segments, so the
+/// polygon is closed even where nothing blocks it.
+///
+/// Sweeps a ray around the origin, keeping the walls it
+/// crosses ordered by how far along it they are, so the
+/// nearest one is always first. Walls that become
+/// neighbours in that order are checked for a crossing
+/// ahead of the ray and split there, so no wall ever
+/// passes another: the order only changes at an
+/// endpoint, and the outline only bends at one. With
+/// `n` segments crossing `k` times, that's
+/// `O((n + k) log n)`. Vertices on the same ray, such
+/// as a corner the ray grazes, split the outline into
+/// the part before and after the ray.
+pub fn compute_visibility_polygon(origin: Vec2, segments: &[Segment]) -> Polygon {
+    let center = origin.as_dvec2();
+
+    // everything is relative to the origin from here on
+    let mut lines: Vec<[DVec2; 2]> = segments
+        .iter()
+        .map(|segment| {
+            [
+                segment.start.as_dvec2() - center,
+                segment.end.as_dvec2() - center,
+            ]
+        })
+        .collect();
+    lines.extend(bounds(&lines));
+
+    let walls: Vec<[DVec2; 2]> = lines
+        .into_iter()
+        .filter_map(|[a, b]| {
+            // counter-clockwise around the origin, and
+            // not pointing at it
+            let sin = a.perp_dot(b) / (a.length() * b.length());
+            if !sin.is_finite() || sin.abs() < EDGE_ON {
+                None
+            } else if sin > 0. {
+                Some([a, b])
+            } else {
+                Some([b, a])
+            }
+        })
+        .collect();
+
+    let ray = Cell::new(Ray {
+        direction: DVec2::X,
+        side: Side::After,
+    });
+    let mut sweep = Sweep::new(walls, &ray);
+    let (angles, nearest) = sweep.run();
+
+    let hit = |wall: usize, angle: f64| {
+        let [start, end] = sweep.spans[wall];
+        if start == angle {
+            sweep.walls[wall][0]
+        } else if end == angle {
+            sweep.walls[wall][1]
+        } else {
+            let direction = DVec2::from_angle(angle);
+            direction * ray_distance(direction, sweep.lines[wall])
+        }
+    };
+
+    let count = angles.len();
+    let mut vertices: Vec<DVec2> = Vec::with_capacity(count * 2);
+    for (i, &angle) in angles.iter().enumerate() {
+        let before = nearest[(i + count - 1) % count];
+        let after = nearest[i];
+        // the same wall carries on past this angle
+        if before == after {
+            continue;
+        }
+        vertices.extend([hit(before, angle), hit(after, angle)]);
+    }
+    let vertices = simplify(vertices);
+
+    Polygon {
+        origin,
+        vertices: vertices
+            .into_iter()
+            .map(|vertex| (vertex + center).as_vec2())
+            .collect(),
+    }
+}
+
+/// Walls closer to pointing at the origin than this,
+/// as the sine of their angle, are ignored.
+const EDGE_ON: f64 = 1e-12;
+
+/// The counter-clockwise angle of `point` from +x, in
+/// `[0, TAU)`.
+fn angle(point: DVec2) -> f64 {
+    let angle = point.y.atan2(point.x);
+    if angle < 0. { angle + TAU } else { angle }
+}
+
+/// How far along `direction` the ray from the origin
+/// meets the line through `wall`.
+fn ray_distance(direction: DVec2, [a, b]: [DVec2; 2]) -> f64 {
+    let along = b - a;
+    a.perp_dot(along) / direction.perp_dot(along)
+}
+
+/// A box around the origin and all `lines`, so every
+/// ray hits something.
+fn bounds(lines: &[[DVec2; 2]]) -> [[DVec2; 2]; 4] {
+    let (min, max) = lines
+        .iter()
+        .flatten()
+        .fold((DVec2::ZERO, DVec2::ZERO), |(min, max), &point| {
+            (min.min(point), max.max(point))
+        });
+    let margin = ((max - min).max_element() * 0.1).max(1.);
+    let (min, max) = (min - margin, max + margin);
+    let corners = [min, DVec2::new(max.x, min.y), max, DVec2::new(min.x, max.y)];
+    [0, 1, 2, 3].map(|i| [corners[i], corners[(i + 1) % 4]])
+}
+
+/// Which side of the ray walls that meet on it are
+/// ordered by.
+#[derive(Clone, Copy)]
+enum Side {
+    /// Just before it, for walls ending there.
+    Before,
+    /// Just after it, for walls starting there.
+    After,
+}
+
+/// Where the sweep is.
+#[derive(Clone, Copy)]
+struct Ray {
+    direction: DVec2,
+    side: Side,
+}
+
+/// A wall the ray crosses, ordered by how far along the
+/// ray it is. Walls never pass each other while both
+/// are active, so the order holds for as long as they
+/// are.
+struct Active<'a> {
+    wall: usize,
+    line: [DVec2; 2],
+    ray: &'a Cell<Ray>,
+}
+
+impl PartialEq for Active<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Active<'_> {}
+
+impl PartialOrd for Active<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Active<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.wall == other.wall {
+            return Ordering::Equal;
+        }
+        let Ray { direction, side } = self.ray.get();
+        let (near, far) = (
+            ray_distance(direction, self.line),
+            ray_distance(direction, other.line),
+        );
+        if (near - far).abs() > 1e-9 * near.abs().max(far.abs()) {
+            return near.total_cmp(&far);
+        }
+
+        // they meet on the ray: the one turning towards
+        // the origin is the nearer one after it, and the
+        // further one before it
+        let (along, other_along) = (self.line[1] - self.line[0], other.line[1] - other.line[0]);
+        let turn = along.perp_dot(other_along) / (along.length() * other_along.length());
+        let after = if turn > EDGE_ON {
+            Ordering::Greater
+        } else if turn < -EDGE_ON {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        };
+        let order = match side {
+            Side::Before => after.reverse(),
+            Side::After => after,
+        };
+        // overlapping walls on one line
+        order.then(self.wall.cmp(&other.wall))
+    }
+}
+
+/// Ends are handled before starts at the same angle, so
+/// walls meeting at a corner never overlap in the
+/// active set.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    End,
+    Start,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Event {
+    angle: f64,
+    kind: Kind,
+    wall: usize,
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.angle
+            .total_cmp(&other.angle)
+            .then(self.kind.cmp(&other.kind))
+            .then(self.wall.cmp(&other.wall))
+    }
+}
+
+/// The ray's sweep from angle 0 around to `TAU`.
+struct Sweep<'a> {
+    /// Counter-clockwise around the origin. Walls split
+    /// at a crossing end there, and carry on as a new
+    /// wall.
+    walls: Vec<[DVec2; 2]>,
+    /// The line each wall is on, the same for every
+    /// piece of one, so they're ordered alike.
+    lines: Vec<[DVec2; 2]>,
+    /// The angles each wall starts and ends at.
+    spans: Vec<[f64; 2]>,
+    events: BinaryHeap<Reverse<Event>>,
+    active: BTreeSet<Active<'a>>,
+    ray: &'a Cell<Ray>,
+}
+
+impl<'a> Sweep<'a> {
+    fn new(walls: Vec<[DVec2; 2]>, ray: &'a Cell<Ray>) -> Self {
+        let spans: Vec<[f64; 2]> = walls.iter().map(|&[a, b]| [angle(a), angle(b)]).collect();
+        let events = spans
+            .iter()
+            .enumerate()
+            .flat_map(|(wall, &[start, end])| {
+                [
+                    Event {
+                        angle: start,
+                        kind: Kind::Start,
+                        wall,
+                    },
+                    Event {
+                        angle: end,
+                        kind: Kind::End,
+                        wall,
+                    },
+                ]
+            })
+            .map(Reverse)
+            .collect();
+        Self {
+            lines: walls.clone(),
+            walls,
+            spans,
+            events,
+            active: BTreeSet::new(),
+            ray,
+        }
+    }
+
+    /// The angles the nearest wall can change at, in
+    /// order, and the nearest wall from each one to the
+    /// next.
+    fn run(&mut self) -> (Vec<f64>, Vec<usize>) {
+        // walls spanning angle 0 are already in view when
+        // the sweep starts
+        for wall in 0..self.walls.len() {
+            let [start, end] = self.spans[wall];
+            if start > end {
+                self.insert(wall, 0.);
+            }
+        }
+
+        let mut angles = vec![];
+        let mut nearest = vec![];
+        if self
+            .events
+            .peek()
+            .is_none_or(|Reverse(event)| event.angle > 0.)
+        {
+            angles.push(0.);
+            nearest.push(self.nearest());
+        }
+        while let Some(Reverse(event)) = self.events.pop() {
+            match event.kind {
+                Kind::End => self.remove(event.wall, event.angle),
+                Kind::Start => self.insert(event.wall, event.angle),
+            }
+            if self
+                .events
+                .peek()
+                .is_none_or(|Reverse(next)| next.angle != event.angle)
+            {
+                angles.push(event.angle);
+                nearest.push(self.nearest());
+            }
+        }
+        (angles, nearest)
+    }
+
+    fn nearest(&self) -> usize {
+        self.active
+            .first()
+            .expect("the bounds surround the origin")
+            .wall
+    }
+
+    fn key(&self, wall: usize) -> Active<'a> {
+        Active {
+            wall,
+            line: self.lines[wall],
+            ray: self.ray,
+        }
+    }
+
+    /// The walls just nearer and just further than `key`.
+    fn neighbours(&self, key: &Active<'a>) -> [Option<usize>; 2] {
+        [
+            self.active.range(..key).next_back(),
+            self.active.range((Excluded(key), Unbounded)).next(),
+        ]
+        .map(|active| active.map(|active| active.wall))
+    }
+
+    fn insert(&mut self, wall: usize, angle: f64) {
+        self.ray.set(Ray {
+            direction: DVec2::from_angle(angle),
+            side: Side::After,
+        });
+        let key = self.key(wall);
+        let neighbours = self.neighbours(&key);
+        self.active.insert(key);
+        for other in neighbours.into_iter().flatten() {
+            self.split(wall, other, angle);
+        }
+    }
+
+    fn remove(&mut self, wall: usize, angle: f64) {
+        // split at a crossing, and already ended there
+        if self.spans[wall][1] != angle {
+            return;
+        }
+        self.ray.set(Ray {
+            direction: DVec2::from_angle(angle),
+            side: Side::Before,
+        });
+        let key = self.key(wall);
+        let [nearer, further] = self.neighbours(&key);
+        if !self.active.remove(&key) {
+            // rounding put it out of order
+            self.active.retain(|active| active.wall != wall);
+        }
+        if let (Some(nearer), Some(further)) = (nearer, further) {
+            self.split(nearer, further, angle);
+        }
+    }
+
+    /// Splits walls `a` and `b` where they cross, if
+    /// that's ahead of the ray while both are active.
+    fn split(&mut self, a: usize, b: usize, angle: f64) {
+        let ([p, q], [r, s]) = (self.walls[a], self.walls[b]);
+        let (pq, rs) = (q - p, s - r);
+        let denominator = pq.perp_dot(rs);
+        if denominator == 0. {
+            return;
+        }
+        let t = (r - p).perp_dot(rs) / denominator;
+        let u = (r - p).perp_dot(pq) / denominator;
+        // crossing inside both, touching ends don't
+        // split anything
+        const END: f64 = 1e-9;
+        if !(t > END && t < 1. - END && u > END && u < 1. - END) {
+            return;
+        }
+
+        let crossing = p.lerp(q, t);
+        let at = self::angle(crossing);
+        // walls wrapping past angle 0 that cross there
+        // were split at the start of the sweep
+        let until = |wall: usize| {
+            let end = self.spans[wall][1];
+            if end >= angle { end } else { TAU }
+        };
+        if at <= angle || at >= until(a) || at >= until(b) {
+            return;
+        }
+        for wall in [a, b] {
+            self.cut(wall, crossing, at);
+        }
+    }
+
+    /// Ends `wall` at `point`, and carries it on from
+    /// there as a new wall.
+    fn cut(&mut self, wall: usize, point: DVec2, at: f64) {
+        let end = self.walls[wall][1];
+        let end_angle = self.spans[wall][1];
+        self.walls[wall][1] = point;
+        self.spans[wall][1] = at;
+
+        let piece = self.walls.len();
+        self.walls.push([point, end]);
+        self.lines.push(self.lines[wall]);
+        self.spans.push([at, end_angle]);
+        self.events.extend(
+            [
+                Event {
+                    angle: at,
+                    kind: Kind::End,
+                    wall,
+                },
+                Event {
+                    angle: at,
+                    kind: Kind::Start,
+                    wall: piece,
+                },
+            ]
+            .map(Reverse),
+        );
+        // a piece wrapping past angle 0 was swept there
+        // while it was still part of `wall`
+        if end_angle > at {
+            self.events.push(Reverse(Event {
+                angle: end_angle,
+                kind: Kind::End,
+                wall: piece,
+            }));
+        }
+    }
+}
+
+/// Drops repeated vertices, and vertices in the middle
+/// of a straight edge.
+fn simplify(vertices: Vec<DVec2>) -> Vec<DVec2> {
+    let scale = vertices
+        .iter()
+        .fold(0f64, |scale, vertex| scale.max(vertex.abs().max_element()));
+    let epsilon = scale * 1e-9;
+
+    let mut kept: Vec<DVec2> = Vec::with_capacity(vertices.len());
+    for vertex in vertices {
+        if kept
+            .last()
+            .is_some_and(|last| last.distance(vertex) <= epsilon)
+        {
+            continue;
+        }
+        kept.push(vertex);
+    }
+    while kept.len() > 1 && kept[0].distance(kept[kept.len() - 1]) <= epsilon {
+        kept.pop();
+    }
+
+    // a vertex is in the middle of a straight edge when
+    // its neighbours are on either side of it on one
+    // line
+    let mut changed = true;
+    while changed && kept.len() > 3 {
+        changed = false;
+        let mut i = 0;
+        while i < kept.len() && kept.len() > 3 {
+            let len = kept.len();
+            let (previous, vertex, next) =
+                (kept[(i + len - 1) % len], kept[i], kept[(i + 1) % len]);
+            let (to_vertex, to_next) = (vertex - previous, next - vertex);
+            let straight = to_vertex.perp_dot(to_next).abs()
+                <= 1e-9 * to_vertex.length() * to_next.length()
+                && to_vertex.dot(to_next) > 0.;
+            if straight {
+                kept.remove(i);
+                changed = true;
+            } else {
+                i += 1;
+            }
+        }
+    }
+    kept
+}
@@ -0,0 +1,146 @@
+use bevy::{ecs::entity::EntityHashSet, ecs::system::SystemParam, prelude::*};
+
+use crate::{Polygon, ViewPolygon};
+
+/// An entity [`Viewer`](crate::Viewer)s keep track of,
+/// by its position. See [`EnteredView`],
+/// [`ExitedView`] and
+/// [`VisibilityQuery::visible_entities`].
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Detectable;
+
+/// The [`Detectable`] entities a viewer saw this frame.
+#[derive(Component, Clone, Debug, Default)]
+pub struct SeenEntities(EntityHashSet);
+
+impl SeenEntities {
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains(&entity)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// A [`Detectable`] entity came into view. Triggered
+/// on the viewer.
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct EnteredView {
+    #[event_target]
+    pub viewer: Entity,
+    /// What the viewer sees now.
+    pub entity: Entity,
+}
+
+/// A [`Detectable`] entity left the view, or stopped
+/// existing. Triggered on the viewer.
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct ExitedView {
+    #[event_target]
+    pub viewer: Entity,
+    /// What the viewer saw before.
+    pub entity: Entity,
+}
+
+/// Answers what viewers see from the polygons computed
+/// in `PostUpdate`, without casting any rays. Systems
+/// running before that see the last frame's views.
+///
+/// ```ignore
+/// fn alert(
+///     guards: Query<Entity, With<Guard>>,
+///     player: Single<Entity, With<Player>>,
+///     visibility: VisibilityQuery,
+/// ) {
+///     for guard in &guards {
+///         if visibility.can_see(guard, *player) {
+///             // ...
+///         }
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct VisibilityQuery<'w, 's> {
+    viewers: Query<'w, 's, (Entity, &'static ViewPolygon, &'static SeenEntities)>,
+    transforms: Query<'w, 's, &'static GlobalTransform>,
+}
+
+impl VisibilityQuery<'_, '_> {
+    /// The view of `viewer`, in world space.
+    pub fn polygon(&self, viewer: Entity) -> Option<&Polygon> {
+        self.viewers
+            .get(viewer)
+            .ok()
+            .map(|(_, polygon, _)| &polygon.0)
+    }
+
+    /// Whether `viewer` sees `point`.
+    pub fn is_visible(&self, viewer: Entity, point: Vec2) -> bool {
+        self.polygon(viewer)
+            .is_some_and(|polygon| polygon.contains(point))
+    }
+
+    /// Whether `viewer` sees any part of the circle
+    /// around `center`.
+    pub fn is_area_visible(&self, viewer: Entity, center: Vec2, radius: f32) -> bool {
+        self.polygon(viewer)
+            .is_some_and(|polygon| polygon.intersects_circle(center, radius))
+    }
+
+    /// Whether `viewer` sees the position of `entity`,
+    /// which doesn't have to be [`Detectable`].
+    pub fn can_see(&self, viewer: Entity, entity: Entity) -> bool {
+        self.transforms
+            .get(entity)
+            .is_ok_and(|transform| self.is_visible(viewer, transform.translation().xy()))
+    }
+
+    /// The [`Detectable`] entities `viewer` sees.
+    pub fn visible_entities(&self, viewer: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.viewers
+            .get(viewer)
+            .ok()
+            .into_iter()
+            .flat_map(|(_, _, seen)| seen.iter())
+    }
+
+    /// Every viewer that sees `point`.
+    pub fn viewers_seeing(&self, point: Vec2) -> impl Iterator<Item = Entity> + '_ {
+        self.viewers
+            .iter()
+            .filter(move |(_, polygon, _)| polygon.contains(point))
+            .map(|(viewer, _, _)| viewer)
+    }
+}
+
+/// Updates [`SeenEntities`] from the new polygons, and
+/// triggers [`EnteredView`] and [`ExitedView`] for the
+/// difference.
+pub(crate) fn update_seen_entities(
+    mut viewers: Query<(Entity, &ViewPolygon, &mut SeenEntities)>,
+    detectables: Query<(Entity, &GlobalTransform), With<Detectable>>,
+    mut commands: Commands,
+) {
+    for (viewer, polygon, mut seen) in &mut viewers {
+        let now: EntityHashSet = detectables
+            .iter()
+            .filter(|&(entity, transform)| {
+                entity != viewer && polygon.contains(transform.translation().xy())
+            })
+            .map(|(entity, _)| entity)
+            .collect();
+        if now == seen.0 {
+            continue;
+        }
+
+        for &entity in now.difference(&seen.0) {
+            commands.trigger(EnteredView { viewer, entity });
+        }
+        for &entity in seen.0.difference(&now) {
+            commands.trigger(ExitedView { viewer, entity });
+        }
+        seen.0 = now;
+    }
+}
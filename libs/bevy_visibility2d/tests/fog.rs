@@ -0,0 +1,208 @@
+//! Revealing, reading back and saving a `FogMask`, and
+//! keeping a `FogOfWar`'s image up to date.
+
+use bevy::prelude::*;
+use bevy_visibility2d::{
+    FogError, FogMask, FogOfWar, FogState, InViewGroup, Polygon, Viewer, Visibility2dPlugin,
+};
+
+/// 1x1 texels, off center so a flipped row or column
+/// lands somewhere else.
+fn mask() -> FogMask {
+    FogMask::new(Rect::new(-10., -5., 30., 15.), UVec2::new(40, 20))
+}
+
+/// A 6x6 square seen from its middle, in the top left
+/// of [`mask`].
+fn square() -> Polygon {
+    Polygon {
+        origin: Vec2::new(-5., 10.),
+        vertices: vec![
+            Vec2::new(-8., 7.),
+            Vec2::new(-2., 7.),
+            Vec2::new(-2., 13.),
+            Vec2::new(-8., 13.),
+        ],
+    }
+}
+
+const INSIDE: [Vec2; 3] = [
+    Vec2::new(-5., 10.),
+    Vec2::new(-7.9, 12.9),
+    Vec2::new(-2.1, 7.1),
+];
+/// Mirrored across the middle of the mask, and just
+/// outside the square.
+const OUTSIDE: [Vec2; 4] = [
+    Vec2::new(-5., 0.),
+    Vec2::new(15., 10.),
+    Vec2::new(-5., 13.5),
+    Vec2::new(-1.5, 10.),
+];
+
+fn count(texels: &[u8]) -> usize {
+    texels.iter().filter(|&&texel| texel > 0).count()
+}
+
+#[test]
+fn revealed_texels_read_back() {
+    let mut mask = mask();
+    mask.reveal(&square());
+
+    assert_eq!(count(mask.visible()), 36);
+    assert_eq!(count(mask.explored()), 36);
+    for point in INSIDE {
+        assert_eq!(mask.state(point), FogState::Visible, "at {point}");
+    }
+    for point in OUTSIDE {
+        assert_eq!(mask.state(point), FogState::Unexplored, "at {point}");
+    }
+
+    mask.clear_visible();
+    assert_eq!(count(mask.visible()), 0);
+    for point in INSIDE {
+        assert_eq!(mask.state(point), FogState::Explored, "at {point}");
+    }
+    for point in OUTSIDE {
+        assert_eq!(mask.state(point), FogState::Unexplored, "at {point}");
+    }
+}
+
+#[test]
+fn outside_the_bounds_is_unexplored() {
+    let mut mask = mask();
+    mask.reveal(&Polygon {
+        origin: Vec2::ZERO,
+        vertices: vec![
+            Vec2::new(-100., -100.),
+            Vec2::new(100., -100.),
+            Vec2::new(100., 100.),
+            Vec2::new(-100., 100.),
+        ],
+    });
+
+    assert_eq!(count(mask.visible()), 800);
+    assert_eq!(mask.state(Vec2::new(29.9, -4.9)), FogState::Visible);
+    assert_eq!(mask.state(Vec2::new(31., 0.)), FogState::Unexplored);
+    assert_eq!(mask.state(Vec2::new(0., -6.)), FogState::Unexplored);
+}
+
+#[test]
+fn explored_texels_load_back() {
+    let mut saved = mask();
+    saved.reveal(&square());
+
+    let mut loaded = mask();
+    loaded.load_explored(saved.explored()).unwrap();
+    assert_eq!(loaded.explored(), saved.explored());
+    assert_eq!(count(loaded.visible()), 0);
+    for point in INSIDE {
+        assert_eq!(loaded.state(point), FogState::Explored, "at {point}");
+    }
+
+    assert_eq!(
+        loaded.load_explored(&saved.explored()[1..]),
+        Err(FogError::SizeMismatch {
+            expected: 800,
+            found: 799,
+        })
+    );
+    // a failed load keeps what was there
+    assert_eq!(loaded.explored(), saved.explored());
+}
+
+/// How many times fog images were written.
+#[derive(Resource, Default)]
+struct Writes(usize);
+
+/// A fog 1 texel per unit around the origin, seen by a
+/// viewer at the origin.
+fn fog_app() -> (App, Entity, Entity) {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        Visibility2dPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<Image>()
+    .init_resource::<Writes>()
+    .add_systems(
+        Last,
+        |mut events: MessageReader<AssetEvent<Image>>, mut writes: ResMut<Writes>| {
+            writes.0 += events
+                .read()
+                .filter(|event| matches!(event, AssetEvent::Modified { .. }))
+                .count();
+        },
+    );
+    let fog = app
+        .world_mut()
+        .spawn(FogOfWar::new(
+            Rect::new(-20., -20., 20., 20.),
+            UVec2::splat(40),
+        ))
+        .id();
+    let viewer = app
+        .world_mut()
+        .spawn((
+            Viewer {
+                range: 5.,
+                ..default()
+            },
+            Transform::default(),
+            InViewGroup(fog),
+        ))
+        .id();
+    // asset events are sent the frame after the write
+    app.update();
+    app.update();
+    (app, fog, viewer)
+}
+
+fn writes(app: &App) -> usize {
+    app.world().resource::<Writes>().0
+}
+
+/// The red channel of the fog image at `point`.
+fn visible_in_image(app: &App, fog: Entity, point: Vec2) -> bool {
+    let fog = app.world().get::<FogOfWar>(fog).unwrap();
+    let image = app
+        .world()
+        .resource::<Assets<Image>>()
+        .get(&fog.image)
+        .unwrap();
+    let texel = ((point - fog.mask.bounds().min) / fog.mask.texel_size()).as_uvec2();
+    let row = fog.mask.resolution().y - 1 - texel.y;
+    let index = (row * fog.mask.resolution().x + texel.x) as usize;
+    image.data.as_ref().unwrap()[index * 2] > 0
+}
+
+#[test]
+fn fog_images_are_only_written_when_they_change() {
+    let (mut app, fog, viewer) = fog_app();
+    let written = writes(&app);
+    assert!(written > 0);
+    assert!(visible_in_image(&app, fog, Vec2::new(0.5, 0.5)));
+
+    app.update();
+    app.update();
+    assert_eq!(writes(&app), written, "nothing moved");
+
+    app.world_mut()
+        .get_mut::<Transform>(viewer)
+        .unwrap()
+        .translation = Vec3::new(10., 0., 0.);
+    app.update();
+    app.update();
+    assert!(writes(&app) > written, "the viewer moved");
+    assert!(visible_in_image(&app, fog, Vec2::new(10.5, 0.5)));
+    assert!(!visible_in_image(&app, fog, Vec2::new(0.5, 0.5)));
+
+    let written = writes(&app);
+    app.world_mut().get_mut::<FogOfWar>(fog).unwrap().softness = 2;
+    app.update();
+    app.update();
+    assert!(writes(&app) > written, "the softness changed");
+}
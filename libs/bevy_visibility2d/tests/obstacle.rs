@@ -0,0 +1,312 @@
+//! Keeping `ObstacleSegments` in sync with obstacle
+//! meshes, outlines and their transforms.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_visibility2d::{
+    Obstacle, ObstacleGrid, ObstaclePolygon, ObstacleSegments, Segment, ViewPolygon, Viewer,
+    Visibility2dPlugin,
+};
+
+/// The obstacles whose segments changed in the last
+/// update.
+#[derive(Resource, Default)]
+struct Rebuilt(Vec<Entity>);
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        Visibility2dPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_resource::<Rebuilt>()
+    .add_systems(First, |mut rebuilt: ResMut<Rebuilt>| rebuilt.0.clear())
+    .add_systems(
+        Last,
+        |changed: Query<Entity, Changed<ObstacleSegments>>, mut rebuilt: ResMut<Rebuilt>| {
+            rebuilt.0.extend(&changed);
+        },
+    );
+    app
+}
+
+fn segment_count(app: &App, entity: Entity) -> usize {
+    app.world()
+        .get::<ObstacleSegments>(entity)
+        .map_or(0, |segments| segments.segments().len())
+}
+
+fn walls(app: &App, entity: Entity) -> Vec<Segment> {
+    app.world()
+        .get::<ObstacleSegments>(entity)
+        .map_or(vec![], |segments| segments.segments().to_vec())
+}
+
+/// The walls of `entity` are `expected`, in any order
+/// and either direction.
+fn assert_walls(app: &App, entity: Entity, expected: &[(Vec2, Vec2)]) {
+    let walls = walls(app, entity);
+    assert_eq!(walls.len(), expected.len(), "{walls:?}");
+    let near = |a: Vec2, b: Vec2| a.distance(b) < 1e-4;
+    for &(start, end) in expected {
+        assert!(
+            walls
+                .iter()
+                .any(|wall| (near(wall.start, start) && near(wall.end, end))
+                    || (near(wall.start, end) && near(wall.end, start))),
+            "no wall from {start} to {end} in {walls:?}"
+        );
+    }
+}
+
+fn rebuilt(app: &App) -> &[Entity] {
+    &app.world().resource::<Rebuilt>().0
+}
+
+#[test]
+fn loaded_meshes_become_obstacles() {
+    let mut app = app();
+    let mesh = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Rectangle::new(2., 2.));
+    let entity = app
+        .world_mut()
+        .spawn((Mesh2d(mesh), Transform::default()))
+        .id();
+    app.update();
+    app.update();
+
+    // the mesh was loaded long before, so only the new
+    // Obstacle says the walls need building
+    app.world_mut().entity_mut(entity).insert(Obstacle);
+    app.update();
+    assert_eq!(segment_count(&app, entity), 4);
+}
+
+#[test]
+fn obstacles_follow_their_mesh() {
+    let mut app = app();
+    let mut meshes = app.world_mut().resource_mut::<Assets<Mesh>>();
+    let (square, triangle) = (
+        meshes.add(Rectangle::new(2., 2.)),
+        meshes.add(Triangle2d::default()),
+    );
+    let entity = app
+        .world_mut()
+        .spawn((Obstacle, Mesh2d(square), Transform::default()))
+        .id();
+    app.update();
+    assert_eq!(segment_count(&app, entity), 4);
+
+    app.world_mut().entity_mut(entity).insert(Mesh2d(triangle));
+    app.update();
+    assert_eq!(segment_count(&app, entity), 3);
+}
+
+#[test]
+fn walls_follow_rotated_and_scaled_transforms() {
+    let mut app = app();
+    let parent = app
+        .world_mut()
+        .spawn(Transform::from_xyz(5., 0., 0.).with_rotation(Quat::from_rotation_z(FRAC_PI_2)))
+        .id();
+    let obstacle = app
+        .world_mut()
+        .spawn((
+            ObstaclePolygon::closed([Vec2::ZERO, Vec2::X, Vec2::Y]),
+            Transform::from_scale(Vec3::new(2., 3., 1.)),
+            ChildOf(parent),
+        ))
+        .id();
+    app.update();
+    // scaled to (2, 0) and (0, 3), then turned a
+    // quarter to (0, 2) and (-3, 0)
+    let triangle = |offset: Vec2| {
+        let [a, b, c] =
+            [Vec2::new(5., 0.), Vec2::new(5., 2.), Vec2::new(2., 0.)].map(|corner| corner + offset);
+        [(a, b), (b, c), (c, a)]
+    };
+    assert_walls(&app, obstacle, &triangle(Vec2::ZERO));
+
+    app.world_mut()
+        .get_mut::<Transform>(parent)
+        .unwrap()
+        .translation
+        .y = 1.;
+    app.update();
+    assert_walls(&app, obstacle, &triangle(Vec2::Y));
+}
+
+#[test]
+fn grid_cells_become_walls() {
+    let mut app = app();
+    let mut grid = ObstacleGrid::new(UVec2::new(2, 2), Vec2::ONE);
+    // the top left cell
+    grid.set_solid(UVec2::ZERO, true);
+    let obstacle = app
+        .world_mut()
+        .spawn((grid, Transform::from_xyz(10., 0., 0.)))
+        .id();
+    app.update();
+    let [a, b, c, d] = [(9., 0.), (10., 0.), (10., 1.), (9., 1.)].map(Vec2::from);
+    assert_walls(&app, obstacle, &[(a, b), (b, c), (c, d), (d, a)]);
+
+    // the whole bottom row
+    let mut grid = app.world_mut().get_mut::<ObstacleGrid>(obstacle).unwrap();
+    grid.set_solid(UVec2::ZERO, false);
+    grid.set_solid(UVec2::new(0, 1), true);
+    grid.set_solid(UVec2::new(1, 1), true);
+    app.update();
+    let [a, b, c, d] = [(9., -1.), (11., -1.), (11., 0.), (9., 0.)].map(Vec2::from);
+    assert_walls(&app, obstacle, &[(a, b), (b, c), (c, d), (d, a)]);
+}
+
+#[test]
+fn walls_are_only_rebuilt_when_something_changes() {
+    let mut app = app();
+    let mesh = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Rectangle::new(2., 2.));
+    let obstacle = app
+        .world_mut()
+        .spawn((Obstacle, Mesh2d(mesh.clone()), Transform::default()))
+        .id();
+    app.update();
+    assert_eq!(rebuilt(&app), [obstacle]);
+
+    app.update();
+    assert!(rebuilt(&app).is_empty());
+
+    app.world_mut()
+        .get_mut::<Transform>(obstacle)
+        .unwrap()
+        .translation
+        .x = 3.;
+    app.update();
+    assert_eq!(rebuilt(&app), [obstacle]);
+    assert!(walls(&app, obstacle).iter().all(|wall| wall.start.x >= 2.));
+
+    // the same handle, with a new mesh behind it
+    *app.world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .get_mut(&mesh)
+        .unwrap() = Triangle2d::default().into();
+    app.update();
+    assert_eq!(rebuilt(&app), [obstacle]);
+    assert_eq!(segment_count(&app, obstacle), 3);
+}
+
+#[test]
+fn removed_obstacles_stop_blocking() {
+    let mut app = app();
+    let viewer = app
+        .world_mut()
+        .spawn((
+            Viewer {
+                range: 10.,
+                ..default()
+            },
+            Transform::default(),
+        ))
+        .id();
+    let wall = app
+        .world_mut()
+        .spawn((
+            ObstaclePolygon::open([Vec2::new(2., -5.), Vec2::new(2., 5.)]),
+            Transform::default(),
+        ))
+        .id();
+    let sees = |app: &App| {
+        app.world()
+            .get::<ViewPolygon>(viewer)
+            .unwrap()
+            .contains(Vec2::new(5., 0.))
+    };
+    app.update();
+    assert!(!sees(&app));
+
+    app.world_mut().entity_mut(wall).remove::<ObstaclePolygon>();
+    app.update();
+    assert_eq!(segment_count(&app, wall), 0);
+    assert!(sees(&app));
+
+    // meshes stop blocking without their Obstacle or
+    // their mesh
+    let mesh = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Rectangle::new(1., 10.));
+    let obstacle = app
+        .world_mut()
+        .spawn((Obstacle, Mesh2d(mesh), Transform::from_xyz(3., 0., 0.)))
+        .id();
+    app.update();
+    assert_eq!(segment_count(&app, obstacle), 4);
+    assert!(!sees(&app));
+
+    app.world_mut().entity_mut(obstacle).remove::<Obstacle>();
+    app.update();
+    assert_eq!(segment_count(&app, obstacle), 0);
+    assert!(sees(&app));
+
+    app.world_mut().entity_mut(obstacle).insert(Obstacle);
+    app.update();
+    assert_eq!(segment_count(&app, obstacle), 4);
+    assert!(!sees(&app));
+
+    app.world_mut().entity_mut(obstacle).remove::<Mesh2d>();
+    app.update();
+    assert_eq!(segment_count(&app, obstacle), 0);
+    assert!(sees(&app));
+}
+
+#[cfg(feature = "tilemap_chunk")]
+#[test]
+fn tilemap_chunks_block_with_their_visible_tiles() {
+    use bevy::sprite_render::{
+        TileData, TilemapChunk, TilemapChunkMaterial, TilemapChunkMeshCache, TilemapChunkTileData,
+    };
+    use bevy_visibility2d::TilemapObstacle;
+
+    let mut app = app();
+    app.init_asset::<Image>()
+        .init_asset::<TilemapChunkMaterial>()
+        .init_resource::<TilemapChunkMeshCache>();
+    let hidden = TileData {
+        visible: false,
+        ..default()
+    };
+    // the top left tile, and an invisible one next to
+    // it
+    let tiles = vec![Some(TileData::default()), Some(hidden), None, None];
+    let chunk = app
+        .world_mut()
+        .spawn((
+            TilemapChunk {
+                chunk_size: UVec2::splat(2),
+                tile_display_size: UVec2::splat(4),
+                ..default()
+            },
+            TilemapChunkTileData(tiles),
+            TilemapObstacle,
+            Transform::default(),
+        ))
+        .id();
+    app.update();
+    let [a, b, c, d] = [(-4., 0.), (0., 0.), (0., 4.), (-4., 4.)].map(Vec2::from);
+    assert_walls(&app, chunk, &[(a, b), (b, c), (c, d), (d, a)]);
+
+    let mut tiles = app
+        .world_mut()
+        .get_mut::<TilemapChunkTileData>(chunk)
+        .unwrap();
+    tiles[0] = None;
+    app.update();
+    assert_eq!(segment_count(&app, chunk), 0);
+}
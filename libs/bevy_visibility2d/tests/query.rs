@@ -0,0 +1,151 @@
+//! `EnteredView` and `ExitedView` as `Detectable`
+//! entities come and go around a viewer.
+
+use bevy::prelude::*;
+use bevy_visibility2d::{
+    Detectable, EnteredView, ExitedView, ObstaclePolygon, SeenEntities, Viewer, Visibility2dPlugin,
+};
+
+/// Every event triggered since the last
+/// [`take_events`], as `(viewer, entity)`.
+#[derive(Resource, Default)]
+struct Events {
+    entered: Vec<(Entity, Entity)>,
+    exited: Vec<(Entity, Entity)>,
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        Visibility2dPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_resource::<Events>()
+    .add_observer(|entered: On<EnteredView>, mut events: ResMut<Events>| {
+        events.entered.push((entered.viewer, entered.entity));
+    })
+    .add_observer(|exited: On<ExitedView>, mut events: ResMut<Events>| {
+        events.exited.push((exited.viewer, exited.entity));
+    });
+    app
+}
+
+fn take_events(app: &mut App) -> Events {
+    std::mem::take(&mut *app.world_mut().resource_mut::<Events>())
+}
+
+fn move_to(app: &mut App, entity: Entity, position: Vec2) {
+    app.world_mut()
+        .get_mut::<Transform>(entity)
+        .unwrap()
+        .translation = position.extend(0.);
+    app.update();
+}
+
+/// A viewer at the origin seeing 10 units all around,
+/// and a detectable entity out of its range.
+fn viewer_and_target(app: &mut App) -> (Entity, Entity) {
+    let viewer = app
+        .world_mut()
+        .spawn((
+            Viewer {
+                range: 10.,
+                ..default()
+            },
+            // viewers don't see themselves
+            Detectable,
+            Transform::default(),
+        ))
+        .id();
+    let target = app
+        .world_mut()
+        .spawn((Detectable, Transform::from_xyz(20., 0., 0.)))
+        .id();
+    app.update();
+    (viewer, target)
+}
+
+#[test]
+fn entering_and_leaving_the_range() {
+    let mut app = app();
+    let (viewer, target) = viewer_and_target(&mut app);
+    let events = take_events(&mut app);
+    assert!(events.entered.is_empty());
+    assert!(events.exited.is_empty());
+
+    move_to(&mut app, target, Vec2::new(5., 0.));
+    let events = take_events(&mut app);
+    assert_eq!(events.entered, [(viewer, target)]);
+    assert!(events.exited.is_empty());
+    assert!(
+        app.world()
+            .get::<SeenEntities>(viewer)
+            .unwrap()
+            .contains(target)
+    );
+
+    // moving around in view doesn't enter it again
+    move_to(&mut app, target, Vec2::new(0., -5.));
+    app.update();
+    let events = take_events(&mut app);
+    assert!(events.entered.is_empty());
+    assert!(events.exited.is_empty());
+
+    move_to(&mut app, target, Vec2::new(0., -15.));
+    let events = take_events(&mut app);
+    assert!(events.entered.is_empty());
+    assert_eq!(events.exited, [(viewer, target)]);
+    assert!(
+        !app.world()
+            .get::<SeenEntities>(viewer)
+            .unwrap()
+            .contains(target)
+    );
+}
+
+#[test]
+fn walls_hide_what_is_behind_them() {
+    let mut app = app();
+    let (viewer, target) = viewer_and_target(&mut app);
+    move_to(&mut app, target, Vec2::new(5., 0.));
+    take_events(&mut app);
+
+    let wall = app
+        .world_mut()
+        .spawn((
+            ObstaclePolygon::open([Vec2::new(2., -5.), Vec2::new(2., 5.)]),
+            Transform::default(),
+        ))
+        .id();
+    app.update();
+    let events = take_events(&mut app);
+    assert!(events.entered.is_empty());
+    assert_eq!(events.exited, [(viewer, target)]);
+
+    app.world_mut().despawn(wall);
+    app.update();
+    let events = take_events(&mut app);
+    assert_eq!(events.entered, [(viewer, target)]);
+    assert!(events.exited.is_empty());
+}
+
+#[test]
+fn despawning_exits_the_view() {
+    let mut app = app();
+    let (viewer, target) = viewer_and_target(&mut app);
+    move_to(&mut app, target, Vec2::new(5., 0.));
+    take_events(&mut app);
+
+    app.world_mut().despawn(target);
+    app.update();
+    let events = take_events(&mut app);
+    assert!(events.entered.is_empty());
+    assert_eq!(events.exited, [(viewer, target)]);
+
+    app.update();
+    let events = take_events(&mut app);
+    assert!(events.exited.is_empty());
+}
@@ -0,0 +1,352 @@
+//! Properties of the visibility polygon around random
+//! walls, and the corner cases the sweep has to get
+//! right.
+
+use std::f32::consts::TAU;
+
+use bevy_math::{DVec2, Dir2, UVec2, Vec2};
+use bevy_visibility2d::{
+    Combine, Polygon, Segment, ViewCone, combine_polygons, compute_view_polygon,
+    compute_visibility_polygon, grid_outline, triangle_outline,
+};
+use proptest::prelude::*;
+
+/// Whether `a..b` and `c..d` cross somewhere other than
+/// within `tolerance` of their ends.
+fn crosses(a: DVec2, b: DVec2, c: DVec2, d: DVec2, tolerance: f64) -> bool {
+    let (ab, cd) = (b - a, d - c);
+    let denominator = ab.perp_dot(cd);
+    if denominator == 0. {
+        return false;
+    }
+    let t = (c - a).perp_dot(cd) / denominator;
+    let u = (c - a).perp_dot(ab) / denominator;
+    t > tolerance && t < 1. - tolerance && u > tolerance && u < 1. - tolerance
+}
+
+/// The distance from `point` to the closest point on
+/// `segment`.
+fn distance(point: Vec2, segment: &Segment) -> f32 {
+    let along = segment.end - segment.start;
+    let t = ((point - segment.start).dot(along) / along.length_squared()).clamp(0., 1.);
+    point.distance(segment.start + along * t)
+}
+
+fn is_visible(origin: Vec2, point: Vec2, segments: &[Segment]) -> bool {
+    segments.iter().all(|segment| {
+        !crosses(
+            origin.as_dvec2(),
+            point.as_dvec2(),
+            segment.start.as_dvec2(),
+            segment.end.as_dvec2(),
+            1e-4,
+        )
+    })
+}
+
+/// Points spread over the inside of each triangle of
+/// the fan, kept off the outline.
+fn interior_points(polygon: &Polygon) -> impl Iterator<Item = Vec2> + '_ {
+    const WEIGHTS: [[f32; 3]; 4] = [
+        [1. / 3., 1. / 3., 1. / 3.],
+        [0.02, 0.49, 0.49],
+        [0.02, 0.96, 0.02],
+        [0.02, 0.02, 0.96],
+    ];
+    polygon
+        .triangles()
+        // triangles with both far corners on one ray
+        // are all outline
+        .filter(|[a, b, c]| {
+            let (ab, ac) = (*b - *a, *c - *a);
+            ab.perp_dot(ac).abs() > 1e-4 * ab.length() * ac.length()
+        })
+        .flat_map(|[a, b, c]| WEIGHTS.map(|[wa, wb, wc]| a * wa + b * wb + c * wc))
+}
+
+/// Whether `point` is inside `polygon`, or within
+/// `tolerance` of its outline.
+fn covers(polygon: &Polygon, point: Vec2, tolerance: f32) -> bool {
+    polygon.contains(point)
+        || polygon
+            .edges()
+            .any(|(start, end)| distance(point, &Segment::new(start, end)) <= tolerance)
+}
+
+fn point() -> impl Strategy<Value = Vec2> {
+    (-50f32..50., -50f32..50.).prop_map(|(x, y)| Vec2::new(x, y))
+}
+
+fn segments() -> impl Strategy<Value = Vec<Segment>> {
+    prop::collection::vec(
+        (point(), point()).prop_map(|(start, end)| Segment::new(start, end)),
+        0..12,
+    )
+}
+
+fn cone() -> impl Strategy<Value = ViewCone> {
+    (0f32..TAU, 0.1f32..TAU, 1f32..80.).prop_map(|(facing, fov, range)| ViewCone {
+        facing: Dir2::new(Vec2::from_angle(facing)).unwrap(),
+        fov,
+        range,
+    })
+}
+
+proptest! {
+    #[test]
+    fn origin_is_inside(origin in point(), segments in segments()) {
+        prop_assume!(segments.iter().all(|segment| distance(origin, segment) > 1e-2));
+        let polygon = compute_visibility_polygon(origin, &segments);
+
+        prop_assert!(polygon.contains(origin));
+    }
+
+    #[test]
+    fn outline_does_not_cross_itself(origin in point(), segments in segments()) {
+        prop_assume!(segments.iter().all(|segment| distance(origin, segment) > 1e-2));
+        let polygon = compute_visibility_polygon(origin, &segments);
+
+        let edges: Vec<(Vec2, Vec2)> = polygon.edges().collect();
+        for (i, &(a, b)) in edges.iter().enumerate() {
+            for &(c, d) in &edges[i + 1..] {
+                prop_assert!(
+                    !crosses(a.as_dvec2(), b.as_dvec2(), c.as_dvec2(), d.as_dvec2(), 1e-4),
+                    "{a}..{b} crosses {c}..{d}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn everything_inside_is_visible(origin in point(), segments in segments()) {
+        prop_assume!(segments.iter().all(|segment| distance(origin, segment) > 1e-2));
+        let polygon = compute_visibility_polygon(origin, &segments);
+
+        for point in interior_points(&polygon) {
+            prop_assert!(
+                is_visible(origin, point, &segments),
+                "{point} is inside, but hidden"
+            );
+        }
+    }
+
+    #[test]
+    fn view_stays_in_range(origin in point(), segments in segments(), cone in cone()) {
+        let polygon = compute_view_polygon(origin, &segments, &cone);
+
+        for vertex in &polygon.vertices {
+            prop_assert!(
+                vertex.distance(origin) <= cone.range * (1. + 1e-4),
+                "{vertex} is out of range"
+            );
+        }
+    }
+
+    #[test]
+    fn view_stays_in_cone(origin in point(), segments in segments(), cone in cone()) {
+        prop_assume!(segments.iter().all(|segment| distance(origin, segment) > 1e-2));
+        let polygon = compute_view_polygon(origin, &segments, &cone);
+
+        let in_cone = |point: Vec2| {
+            let offset = point - origin;
+            offset.length() < 1e-3 || cone.facing.angle_to(offset).abs() <= cone.fov / 2. + 1e-3
+        };
+        for vertex in &polygon.vertices {
+            prop_assert!(in_cone(*vertex), "{vertex} is outside the cone");
+        }
+        for point in interior_points(&polygon) {
+            prop_assert!(in_cone(point), "{point} is outside the cone");
+        }
+    }
+
+    #[test]
+    fn intersection_is_inside_both(
+        origins in (point(), point()),
+        segments in segments(),
+        cones in (cone(), cone()),
+    ) {
+        let polygons = [
+            compute_view_polygon(origins.0, &segments, &cones.0),
+            compute_view_polygon(origins.1, &segments, &cones.1),
+        ];
+
+        for [a, b, c] in combine_polygons(&polygons, Combine::Intersection) {
+            let centroid = (a + b + c) / 3.;
+            for polygon in &polygons {
+                prop_assert!(
+                    covers(polygon, centroid, 1e-3),
+                    "{centroid} is outside one of the views"
+                );
+            }
+        }
+    }
+}
+
+/// Without walls the view reaches the bounds all
+/// around.
+#[test]
+fn open_view_is_a_box() {
+    let polygon = compute_visibility_polygon(Vec2::ZERO, &[]);
+
+    assert_eq!(polygon.vertices.len(), 4);
+    assert!(polygon.contains(Vec2::ZERO));
+}
+
+/// A wall made of several collinear pieces leaves no
+/// vertices where the pieces meet.
+#[test]
+fn collinear_walls_merge() {
+    let y = 2.;
+    let segments = [
+        Segment::new(Vec2::new(-3., y), Vec2::new(-1., y)),
+        Segment::new(Vec2::new(-1., y), Vec2::new(1., y)),
+        Segment::new(Vec2::new(1., y), Vec2::new(3., y)),
+    ];
+    let polygon = compute_visibility_polygon(Vec2::ZERO, &segments);
+
+    let on_wall = polygon
+        .vertices
+        .iter()
+        .filter(|vertex| (vertex.y - y).abs() < 1e-5)
+        .count();
+    // only the two ends of the whole wall
+    assert_eq!(on_wall, 2);
+    assert!(!polygon.contains(Vec2::new(0., 2.5)));
+    assert!(polygon.contains(Vec2::new(0., 1.5)));
+}
+
+/// Rays that graze a corner carry on past it, and stop
+/// at corners straight behind it.
+#[test]
+fn grazing_rays_pass_corners() {
+    let square = |min: Vec2| {
+        let corners = [min, min + Vec2::X, min + Vec2::ONE, min + Vec2::Y];
+        [0, 1, 2, 3].map(|i| Segment::new(corners[i], corners[(i + 1) % 4]))
+    };
+    // the ray from the origin through (2, 1) grazes the
+    // first box and runs into the corner of the second
+    let segments: Vec<Segment> = square(Vec2::new(1., 1.))
+        .into_iter()
+        .chain(square(Vec2::new(6., 3.)))
+        .collect();
+    let origin = Vec2::ZERO;
+    let polygon = compute_visibility_polygon(origin, &segments);
+
+    // just below the ray, past the first box
+    assert!(polygon.contains(Vec2::new(5., 2.4)));
+    // just above it, behind the first box
+    assert!(!polygon.contains(Vec2::new(5., 2.6)));
+    for corner in [Vec2::new(2., 1.), Vec2::new(6., 3.)] {
+        assert!(
+            polygon
+                .vertices
+                .iter()
+                .any(|vertex| vertex.distance(corner) < 1e-5),
+            "{corner} isn't on the outline"
+        );
+    }
+    for point in interior_points(&polygon) {
+        assert!(is_visible(origin, point, &segments), "{point} is hidden");
+    }
+}
+
+/// Where walls cross, the outline bends at the
+/// crossing, and follows whichever is nearer on either
+/// side of it, also where both walls wrap past angle 0.
+#[test]
+fn crossing_walls_bend_at_the_crossing() {
+    // an X crossing at (0, 2), and another across +x
+    // crossing at (3, 0.5)
+    let segments = [
+        Segment::new(Vec2::new(-2., 1.), Vec2::new(2., 3.)),
+        Segment::new(Vec2::new(-2., 3.), Vec2::new(2., 1.)),
+        Segment::new(Vec2::new(2., -1.5), Vec2::new(4., 2.5)),
+        Segment::new(Vec2::new(4., -1.5), Vec2::new(2., 2.5)),
+    ];
+    let origin = Vec2::ZERO;
+    let polygon = compute_visibility_polygon(origin, &segments);
+
+    for crossing in [Vec2::new(0., 2.), Vec2::new(3., 0.5)] {
+        assert!(
+            polygon
+                .vertices
+                .iter()
+                .any(|vertex| vertex.distance(crossing) < 1e-5),
+            "{crossing} isn't on the outline"
+        );
+    }
+    assert!(polygon.contains(Vec2::new(0., 1.9)));
+    assert!(!polygon.contains(Vec2::new(0., 2.1)));
+    assert!(polygon.contains(Vec2::new(2.9, 0.5)));
+    assert!(!polygon.contains(Vec2::new(3.1, 0.5)));
+    for point in interior_points(&polygon) {
+        assert!(is_visible(origin, point, &segments), "{point} is hidden");
+    }
+}
+
+/// Walls pointing straight at the origin don't hide
+/// anything.
+#[test]
+fn edge_on_walls_are_ignored() {
+    let segments = [Segment::new(Vec2::new(1., 0.), Vec2::new(3., 0.))];
+    let polygon = compute_visibility_polygon(Vec2::ZERO, &segments);
+
+    assert_eq!(polygon.vertices.len(), 4);
+}
+
+/// Whether `segments` has a segment between `a` and
+/// `b`, either way around.
+fn has_segment(segments: &[Segment], a: Vec2, b: Vec2) -> bool {
+    segments.iter().any(|segment| {
+        (segment.start, segment.end) == (a, b) || (segment.start, segment.end) == (b, a)
+    })
+}
+
+/// A quad whose triangles don't share vertices, like
+/// meshes split for uvs, is outlined by its 4 sides.
+#[test]
+fn seams_are_welded() {
+    let corners = [
+        Vec2::new(0., 0.),
+        Vec2::new(1., 0.),
+        Vec2::new(1., 1.),
+        Vec2::new(0., 1.),
+    ];
+    let positions = [
+        corners[0], corners[1], corners[2], corners[0], corners[2], corners[3],
+    ];
+    let segments = triangle_outline(&positions, &[[0, 1, 2], [3, 4, 5]]);
+
+    assert_eq!(segments.len(), 4);
+    for i in 0..4 {
+        assert!(has_segment(&segments, corners[i], corners[(i + 1) % 4]));
+    }
+    assert!(!has_segment(&segments, corners[0], corners[2]));
+}
+
+/// An L of tiles is outlined by its 6 sides, however
+/// many tiles each runs along.
+#[test]
+fn grid_walls_merge() {
+    // the left column and the bottom row of 3x3 tiles
+    let solid = |cell: UVec2| cell.x == 0 || cell.y == 2;
+    let segments = grid_outline(UVec2::splat(3), Vec2::ONE, solid);
+
+    assert_eq!(segments.len(), 6, "{segments:?}");
+    let corners = [
+        Vec2::new(-1.5, 1.5),
+        Vec2::new(-0.5, 1.5),
+        Vec2::new(-0.5, -0.5),
+        Vec2::new(1.5, -0.5),
+        Vec2::new(1.5, -1.5),
+        Vec2::new(-1.5, -1.5),
+    ];
+    for i in 0..6 {
+        assert!(
+            has_segment(&segments, corners[i], corners[(i + 1) % 6]),
+            "no wall from {} to {}",
+            corners[i],
+            corners[(i + 1) % 6]
+        );
+    }
+}